@@ -5,12 +5,14 @@ use crate::{
 };
 use crossbeam::queue::ArrayQueue;
 use std::{
+    collections::BTreeMap,
     mem::MaybeUninit,
     ptr::{null, null_mut},
 };
 
 pub struct Allocator {
     pub(crate) queue: ArrayQueue<*mut f32>,
+    pub(crate) silence: Vec<f32>,
 }
 
 pub(crate) struct SlabAllocator<T> {
@@ -20,25 +22,41 @@ pub(crate) struct SlabAllocator<T> {
 }
 
 impl Allocator {
-    pub(crate) unsafe fn assign(&self, bus: &AudioBus) {
+    /// Assign a buffer from the pool to every channel of `bus`. Returns false if the pool ran out,
+    /// in which case the remaining channels point at a shared buffer of silence that must not be
+    /// written to.
+    pub(crate) unsafe fn try_assign(&self, bus: &AudioBus) -> bool {
+        let mut assigned = true;
         for index in 0..bus.ptrs.len() {
-            let channel = self.queue.pop().unwrap();
+            let channel = self.queue.pop().unwrap_or_else(|| {
+                assigned = false;
+                self.silence.as_ptr().cast_mut()
+            });
             *bus.ptrs[index].get() = channel;
         }
+        assigned
     }
 
-    pub(crate) unsafe fn assign_mut(&self, bus: &AudioBusMut) {
+    /// See [Allocator::try_assign].
+    pub(crate) unsafe fn try_assign_mut(&self, bus: &AudioBusMut) -> bool {
+        let mut assigned = true;
         for index in 0..bus.ptrs.len() {
-            let channel = self.queue.pop().unwrap();
+            let channel = self.queue.pop().unwrap_or_else(|| {
+                assigned = false;
+                self.silence.as_ptr().cast_mut()
+            });
             *bus.ptrs[index].get() = channel;
         }
+        assigned
     }
 
     pub(crate) unsafe fn release(&self, bus: &AudioBus) {
         for index in 0..bus.ptrs.len() {
             let channel = (*bus.ptrs[index].get()).cast_mut();
             *bus.ptrs[index].get() = null();
-            self.queue.push(channel).unwrap();
+            if !self.is_silence(channel) {
+                self.queue.push(channel).unwrap();
+            }
         }
     }
 
@@ -46,29 +64,47 @@ impl Allocator {
         for index in 0..bus.ptrs.len() {
             let channel = *bus.ptrs[index].get();
             *bus.ptrs[index].get() = null_mut();
-            self.queue.push(channel).unwrap();
+            if !self.is_silence(channel) {
+                self.queue.push(channel).unwrap();
+            }
         }
     }
+
+    /// Whether every buffer is back in the pool, as it should be between blocks.
+    pub(crate) fn is_full(&self) -> bool {
+        self.queue.is_full()
+    }
+
+    /// Whether `ptr` is the buffer handed out when the pool runs out.
+    pub(crate) fn is_silence(&self, ptr: *const f32) -> bool {
+        std::ptr::eq(ptr, self.silence.as_ptr())
+    }
 }
 
 unsafe impl Send for Allocator {}
 unsafe impl Sync for Allocator {}
 
 impl<T> SlabAllocator<T> {
-    pub fn new(slab_size: usize) -> Self {
+    /// Create an allocator that carves slabs out of the capacity of `data`. The data is never
+    /// reallocated, so pointers to slabs remain valid for as long as it lives.
+    pub fn new(slab_size: usize, mut data: Vec<MaybeUninit<T>>) -> Self {
+        data.clear();
         Self {
             slab_size,
             pointers: vec![],
-            data: vec![],
+            data,
         }
     }
 
-    pub fn alloc(&mut self) -> *mut T {
+    /// Allocate a slab, or return `None` if the data is out of capacity.
+    pub fn alloc(&mut self) -> Option<*mut T> {
         let ptr = 'a: {
             if let Some(ptr) = self.pointers.pop() {
                 break 'a ptr;
             }
-            self.data.reserve_exact(self.slab_size);
+            if self.data.capacity() - self.data.len() < self.slab_size {
+                return None;
+            }
             unsafe {
                 let ptr = self.data.as_mut_ptr().add(self.data.len());
                 self.data.set_len(self.data.len() + self.slab_size);
@@ -76,7 +112,23 @@ impl<T> SlabAllocator<T> {
             }
         };
         debug_assert!(ptr.is_aligned());
-        ptr
+        Some(ptr)
+    }
+
+    /// Carve slabs until at least `num_slabs` are free. Returns `None` if the data is out of
+    /// capacity.
+    pub fn reserve(&mut self, num_slabs: usize) -> Option<()> {
+        while self.pointers.len() < num_slabs {
+            if self.data.capacity() - self.data.len() < self.slab_size {
+                return None;
+            }
+            unsafe {
+                let ptr = self.data.as_mut_ptr().add(self.data.len());
+                self.data.set_len(self.data.len() + self.slab_size);
+                self.pointers.push(ptr.cast());
+            }
+        }
+        Some(())
     }
 
     pub fn dealloc(&mut self, ptr: *mut T) {
@@ -88,33 +140,81 @@ impl<T> SlabAllocator<T> {
     }
 }
 
+/// An upper bound on the number of samples [compile] needs for its pool, for a graph with
+/// `num_channels` channels across all of its buses: one buffer per channel held by the initial
+/// assignment, and one more per channel left free for the renderer.
+pub(crate) fn pool_size(max_num_frames: usize, num_channels: usize) -> usize {
+    2 * num_channels * max_num_frames
+}
+
+/// The number of channels of every bus of `nodes`.
+pub(crate) fn num_channels(nodes: &[renderer::Node]) -> usize {
+    nodes
+        .iter()
+        .map(|node| unsafe {
+            let inputs = &*node.audio_inputs.get();
+            let outputs = &*node.audio_outputs.get();
+            inputs
+                .iter()
+                .map(|bus| (*bus.get()).num_channels())
+                .sum::<usize>()
+                + outputs
+                    .iter()
+                    .map(|bus| (*bus.get()).num_channels())
+                    .sum::<usize>()
+        })
+        .sum::<usize>()
+}
+
+/// Assign buffers to every bus of `nodes`, carving them out of `pool`. Returns `None` if the pool
+/// is too small for the initial assignment.
 pub(crate) fn compile(
     input_node: usize,
     output_node: usize,
     max_num_frames: usize,
-    num_workers: usize,
     nodes: &[renderer::Node],
-) -> (Allocator, Vec<MaybeUninit<f32>>) {
-    let mut alloc: SlabAllocator<f32> = SlabAllocator::new(max_num_frames);
-    let mut max_breadth = 0;
+    pool: Vec<MaybeUninit<f32>>,
+) -> Option<(Allocator, Vec<MaybeUninit<f32>>)> {
+    let mut alloc: SlabAllocator<f32> = SlabAllocator::new(max_num_frames, pool);
+    let mut shared = BTreeMap::new();
     unsafe {
         for (node_index, node) in nodes.iter().enumerate() {
-            let mut breadth = 0;
             if node_index != input_node {
                 for (bus_index, incoming) in node.incoming.iter().enumerate() {
                     let bus = &mut *(*node.audio_inputs.get())[bus_index].get();
-                    breadth += bus.num_channels();
 
+                    // A summing input reads the buffers of each of its sources.
+                    let mut ptrs = vec![];
                     for channel_index in 0..bus.num_channels() {
-                        if incoming.is_none() {
-                            let ptr = alloc.alloc();
+                        if incoming.is_empty() {
+                            let ptr = alloc.alloc()?;
                             for n in 0..max_num_frames {
                                 std::ptr::write(ptr.add(n), 0.0);
                             }
                             *bus.ptrs[channel_index].get() = ptr.cast();
                         }
-                        let ptr = *bus.ptrs[channel_index].get();
-                        eprintln!("input: {node_index}.{bus_index}.{channel_index} {ptr:x?}");
+                        if incoming.len() < 2 {
+                            ptrs.push(*bus.ptrs[channel_index].get());
+                        }
+                    }
+                    if incoming.len() > 1 {
+                        for &(source, output) in incoming.iter() {
+                            let output_bus = &*(*nodes[source].audio_outputs.get())[output].get();
+                            ptrs.extend(
+                                output_bus.ptrs.iter().map(|ptr| (*ptr.get()).cast_const()),
+                            );
+                        }
+                    }
+
+                    for ptr in ptrs {
+                        // Buffers shared by several sinks are freed after the last one.
+                        if let Some(readers) = shared.get_mut(&ptr.cast_mut()) {
+                            *readers -= 1;
+                            if *readers > 0 {
+                                continue;
+                            }
+                            shared.remove(&ptr.cast_mut());
+                        }
                         alloc.dealloc(ptr.cast_mut());
                     }
                 }
@@ -123,17 +223,22 @@ pub(crate) fn compile(
             if node_index != output_node {
                 for (bus_index, outgoing) in node.outgoing.iter().enumerate() {
                     let output_bus = &mut *(*node.audio_outputs.get())[bus_index].get();
-                    breadth += output_bus.num_channels();
 
                     for channel_index in 0..output_bus.num_channels() {
-                        let ptr = alloc.alloc();
+                        let ptr = alloc.alloc()?;
                         *output_bus.ptrs[channel_index].get() = ptr;
                     }
-                    if let Some(input) = outgoing {
+                    for input in outgoing.iter() {
                         let input_node = &nodes[input.0];
                         let input_bus = &mut *(*input_node.audio_inputs.get())[input.1].get();
                         output_bus.push(input_bus);
-                    } else {
+                    }
+                    if outgoing.len() > 1 {
+                        for ptr in &output_bus.ptrs {
+                            shared.insert(*ptr.get(), outgoing.len());
+                        }
+                    }
+                    if outgoing.is_empty() {
                         for ptr in &output_bus.ptrs {
                             let ptr = *ptr.get();
                             for n in 0..max_num_frames {
@@ -144,14 +249,14 @@ pub(crate) fn compile(
                     }
                 }
             }
-
-            max_breadth = max_breadth.max(breadth);
         }
 
-        for _ in 0..(max_breadth * num_workers) {
-            let ptr = alloc.alloc();
-            alloc.dealloc(ptr);
-        }
+        // The renderer reassigns buffers every block, in whatever order the nodes are processed and
+        // however many workers hold them, so there must be enough free for every bus to hold its
+        // own at once. That covers each source of a summing input, as they are separate buses. A
+        // pool smaller than `Graph::pool_size` may fall short, in which case the renderer skips
+        // the nodes it can't give buffers to and reports them as `XrunKind::PoolExhausted`.
+        let _ = alloc.reserve(num_channels(nodes));
     }
 
     let SlabAllocator { pointers, data, .. } = alloc;
@@ -160,6 +265,69 @@ pub(crate) fn compile(
     for ptr in pointers {
         queue.push(ptr).ok();
     }
-    let alloc = Allocator { queue };
-    (alloc, data)
+    let alloc = Allocator {
+        queue,
+        silence: vec![0.0; max_num_frames],
+    };
+    Some((alloc, data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allocator(buffers: &mut [Vec<f32>]) -> Allocator {
+        let queue = ArrayQueue::new(buffers.len());
+        for buffer in buffers {
+            queue.push(buffer.as_mut_ptr()).unwrap();
+        }
+        Allocator {
+            queue,
+            silence: vec![0.0; 4],
+        }
+    }
+
+    #[test]
+    fn try_assign_falls_back_to_silence() {
+        let mut buffers = vec![vec![1.0; 4]; 2];
+        let alloc = allocator(&mut buffers);
+        let mut channels = vec![vec![0.0; 4]; 3];
+        let bus = AudioBusMut::from_channels(&mut channels);
+        unsafe {
+            assert!(!alloc.try_assign_mut(&bus));
+            assert!(!alloc.is_silence(*bus.ptrs[0].get()));
+            assert!(!alloc.is_silence(*bus.ptrs[1].get()));
+            assert!(alloc.is_silence(*bus.ptrs[2].get()));
+
+            // Only the pooled buffers go back.
+            alloc.release_mut(&bus);
+            assert!(alloc.is_full());
+            assert_eq!(alloc.queue.len(), 2);
+        }
+    }
+
+    #[test]
+    fn try_assign_takes_from_the_pool() {
+        let mut buffers = vec![vec![1.0; 4]; 2];
+        let alloc = allocator(&mut buffers);
+        let channels = vec![vec![0.0; 4]; 2];
+        let bus = AudioBus::from_channels(&channels);
+        unsafe {
+            assert!(alloc.try_assign(&bus));
+            assert!(alloc.queue.is_empty());
+            alloc.release(&bus);
+            assert!(alloc.is_full());
+        }
+    }
+
+    #[test]
+    fn reserve_carves_free_slabs() {
+        let mut slabs = SlabAllocator::<f32>::new(4, Vec::with_capacity(12));
+        let held = slabs.alloc().unwrap();
+        slabs.reserve(2).unwrap();
+        assert_eq!(slabs.pointers.len(), 2);
+        assert!(slabs.reserve(3).is_none());
+        slabs.dealloc(held);
+        assert_eq!(slabs.pointers.len(), 3);
+    }
 }
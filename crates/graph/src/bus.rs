@@ -4,14 +4,18 @@ use std::{
 
 use tesi_util::IsSendSync;
 
+pub mod kernels;
+
 pub struct AudioBus {
     pub(crate) num_frames: usize,
     pub(crate) ptrs: Vec<IsSendSync<UnsafeCell<*const f32>>>,
+    pub(crate) silent: bool,
 }
 
 pub struct AudioBusMut {
     pub(crate) num_frames: usize,
     pub(crate) ptrs: Vec<IsSendSync<UnsafeCell<*mut f32>>>,
+    pub(crate) silent: bool,
 }
 
 pub struct Iter<'a> {
@@ -31,7 +35,11 @@ impl AudioBus {
         for _ in 0..num_channels {
             ptrs.push(IsSendSync::new(UnsafeCell::new(null())));
         }
-        Self { num_frames, ptrs }
+        Self {
+            num_frames,
+            ptrs,
+            silent: false,
+        }
     }
 
     pub fn iter(&self) -> Iter<'_> {
@@ -45,6 +53,13 @@ impl AudioBus {
     pub fn num_channels(&self) -> usize {
         self.ptrs.len()
     }
+
+    /// Whether the bus is known to be silent this block: it is unbound, or each of its sources
+    /// reported silence with [crate::proc::Context::silent_outputs]. A bus that isn't known to be
+    /// silent may still be.
+    pub fn is_silent(&self) -> bool {
+        self.silent
+    }
 }
 
 impl AudioBusMut {
@@ -54,7 +69,11 @@ impl AudioBusMut {
         for _ in 0..num_channels {
             ptrs.push(IsSendSync::new(UnsafeCell::new(null_mut())));
         }
-        Self { num_frames, ptrs }
+        Self {
+            num_frames,
+            ptrs,
+            silent: false,
+        }
     }
 
     pub(crate) unsafe fn push(&self, dst: &mut AudioBus) {
@@ -100,6 +119,69 @@ impl AudioBusMut {
             }
         }
     }
+
+    /// Multiply every channel by `gain`, see [kernels::scale].
+    pub fn apply_gain(&mut self, gain: f32) {
+        for channel in self.iter() {
+            kernels::scale(channel, gain);
+        }
+    }
+
+    /// Multiply every channel by a gain ramping from `from` towards `to` over the block, see
+    /// [kernels::scale_ramp].
+    pub fn apply_gain_ramp(&mut self, from: f32, to: f32) {
+        for channel in self.iter() {
+            kernels::scale_ramp(channel, from, to);
+        }
+    }
+
+    /// Zero the denormal samples of every channel, see [kernels::flush_denormals]. Useful at the
+    /// end of a decaying tail, like a filter's or a reverb's, on CPUs that are slow with them.
+    pub fn flush_denormals(&mut self) {
+        for channel in self.iter() {
+            kernels::flush_denormals(channel);
+        }
+    }
+
+    /// Whether no sample of the bus exceeds `threshold` in magnitude, e.g. to find the end of a
+    /// decaying tail and report the output silent with [crate::proc::Context::silent_outputs].
+    pub fn is_below(&self, threshold: f32) -> bool {
+        (0..self.num_channels()).all(|channel| {
+            self[channel]
+                .iter()
+                .all(|sample| sample.abs() <= threshold)
+        })
+    }
+}
+
+#[cfg(test)]
+impl AudioBus {
+    /// A bus reading from `channels`, to test processors without a graph.
+    pub(crate) fn from_channels(channels: &[Vec<f32>]) -> Self {
+        Self {
+            num_frames: channels.first().map_or(0, Vec::len),
+            ptrs: channels
+                .iter()
+                .map(|channel| IsSendSync::new(UnsafeCell::new(channel.as_ptr())))
+                .collect(),
+            silent: false,
+        }
+    }
+}
+
+#[cfg(test)]
+impl AudioBusMut {
+    /// A bus writing to `channels`, to test processors without a graph.
+    pub(crate) fn from_channels(channels: &mut [Vec<f32>]) -> Self {
+        Self {
+            num_frames: channels.first().map_or(0, Vec::len),
+            ptrs: channels
+                .iter_mut()
+                .map(|channel| IsSendSync::new(UnsafeCell::new(channel.as_mut_ptr())))
+                .collect(),
+            silent: false,
+        }
+    }
 }
 
 impl Index<usize> for AudioBus {
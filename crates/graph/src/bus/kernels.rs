@@ -0,0 +1,193 @@
+//! Block kernels for mixing, gain and denormal flushing.
+//!
+//! Each kernel walks its buffers in fixed-width lanes so the compiler can emit packed
+//! instructions without having to see through the bus pointer indirection, then finishes the
+//! remainder with a scalar loop.
+
+const LANES: usize = 4;
+
+/// Accumulate `src` into `dst`, sample by sample.
+#[inline]
+pub fn add_assign(dst: &mut [f32], src: &[f32]) {
+    debug_assert_eq!(dst.len(), src.len());
+    let len = dst.len().min(src.len());
+    let mut dst = dst[..len].chunks_exact_mut(LANES);
+    let mut src = src[..len].chunks_exact(LANES);
+    for (dst, src) in (&mut dst).zip(&mut src) {
+        let dst: &mut [f32; LANES] = dst.try_into().unwrap();
+        let src: &[f32; LANES] = src.try_into().unwrap();
+        for (dst, src) in dst.iter_mut().zip(src) {
+            *dst += *src;
+        }
+    }
+    for (dst, src) in dst.into_remainder().iter_mut().zip(src.remainder()) {
+        *dst += *src;
+    }
+}
+
+/// Multiply every sample in `buf` by `gain`.
+#[inline]
+pub fn scale(buf: &mut [f32], gain: f32) {
+    let mut buf = buf.chunks_exact_mut(LANES);
+    for buf in &mut buf {
+        let buf: &mut [f32; LANES] = buf.try_into().unwrap();
+        for sample in buf {
+            *sample *= gain;
+        }
+    }
+    for sample in buf.into_remainder() {
+        *sample *= gain;
+    }
+}
+
+/// Multiply `buf` by a gain ramping linearly from `from` at the first sample towards `to`, which
+/// would be reached one sample past the end, so that consecutive ramps join without a repeated
+/// value.
+#[inline]
+pub fn scale_ramp(buf: &mut [f32], from: f32, to: f32) {
+    let step = (to - from) / buf.len().max(1) as f32;
+    let mut buf = buf.chunks_exact_mut(LANES);
+    let mut index = 0;
+    for buf in &mut buf {
+        let buf: &mut [f32; LANES] = buf.try_into().unwrap();
+        for (lane, sample) in buf.iter_mut().enumerate() {
+            *sample *= from + step * (index + lane) as f32;
+        }
+        index += LANES;
+    }
+    for sample in buf.into_remainder() {
+        *sample *= from + step * index as f32;
+        index += 1;
+    }
+}
+
+/// Replace every sample smaller in magnitude than [f32::MIN_POSITIVE], i.e. denormals, with zero.
+/// Works on the bits without branching, so it's cheap enough to run on every block.
+#[inline]
+pub fn flush_denormals(buf: &mut [f32]) {
+    #[inline(always)]
+    fn flush(sample: f32) -> f32 {
+        let bits = sample.to_bits();
+        let keep = ((bits & 0x7fff_ffff) >= f32::MIN_POSITIVE.to_bits()) as u32;
+        f32::from_bits(bits & keep.wrapping_neg())
+    }
+    let mut buf = buf.chunks_exact_mut(LANES);
+    for buf in &mut buf {
+        let buf: &mut [f32; LANES] = buf.try_into().unwrap();
+        for sample in buf {
+            *sample = flush(*sample);
+        }
+    }
+    for sample in buf.into_remainder() {
+        *sample = flush(*sample);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signal(len: usize, seed: f32) -> Vec<f32> {
+        (0..len).map(|n| (n as f32 * seed).sin()).collect()
+    }
+
+    #[test]
+    fn add_assign_matches_scalar() {
+        for len in [0, 1, 3, 5, 7, 13, 64, 67] {
+            let src = signal(len, 0.3);
+            let mut dst = signal(len, 0.7);
+            let expected = dst.iter().zip(&src).map(|(d, s)| d + s).collect::<Vec<_>>();
+            add_assign(&mut dst, &src);
+            assert_eq!(dst, expected, "len {len}");
+        }
+    }
+
+    #[test]
+    fn scale_matches_scalar() {
+        for len in [0, 1, 3, 5, 7, 13, 64, 67] {
+            let mut buf = signal(len, 0.3);
+            let expected = buf.iter().map(|s| s * 0.25).collect::<Vec<_>>();
+            scale(&mut buf, 0.25);
+            assert_eq!(buf, expected, "len {len}");
+        }
+    }
+
+    #[test]
+    fn scale_ramp_matches_scalar() {
+        for len in [1, 3, 5, 13, 67] {
+            let mut buf = vec![1.0; len];
+            let step = (0.5 - 1.0) / len as f32;
+            let expected = (0..len).map(|n| 1.0 + step * n as f32).collect::<Vec<_>>();
+            scale_ramp(&mut buf, 1.0, 0.5);
+            assert_eq!(buf, expected, "len {len}");
+        }
+    }
+
+    /// The distance between two floats in units in the last place.
+    fn ulps(a: f32, b: f32) -> u32 {
+        let key = |x: f32| {
+            let bits = x.to_bits() as i32;
+            if bits < 0 {
+                i32::MIN - bits
+            } else {
+                bits
+            }
+        };
+        key(a).abs_diff(key(b))
+    }
+
+    #[test]
+    fn bus_gains_match_scalar() {
+        let len = 67;
+        let input = [signal(len, 0.3), signal(len, 0.7)];
+        let mut channels = input.to_vec();
+        let mut bus = crate::bus::AudioBusMut::from_channels(&mut channels);
+        bus.apply_gain(0.8);
+        // Two ramps that join, as over consecutive blocks.
+        bus.apply_gain_ramp(1.0, 0.5);
+        bus.apply_gain_ramp(0.5, 0.0);
+        let ramp = |from: f32, to: f32, n: usize| from + (to - from) / len as f32 * n as f32;
+        for (channel, input) in channels.iter().zip(&input) {
+            for (n, (y, x)) in channel.iter().zip(input).enumerate() {
+                let expected = x * 0.8 * ramp(1.0, 0.5, n) * ramp(0.5, 0.0, n);
+                assert!(ulps(*y, expected) <= 1, "{y} != {expected} at {n}");
+            }
+        }
+    }
+
+    #[test]
+    fn flush_denormals_keeps_normals() {
+        let tiny = f32::MIN_POSITIVE / 2.0;
+        let mut buf = vec![1.0, -tiny, tiny, -0.5, f32::MIN_POSITIVE, 0.0, -tiny];
+        flush_denormals(&mut buf);
+        assert_eq!(buf, [1.0, 0.0, 0.0, -0.5, f32::MIN_POSITIVE, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn flushed_ramps_keep_their_normal_part() {
+        // A ramp from 1e-40 to 1.0, evenly spaced in decibels, on both channels of a bus.
+        let ramp = (0..=400)
+            .map(|n| 10.0f64.powf(-40.0 + n as f64 / 10.0) as f32)
+            .collect::<Vec<_>>();
+        let mut channels = vec![ramp.clone(), ramp.iter().map(|x| -x).collect()];
+        let mut bus = crate::bus::AudioBusMut::from_channels(&mut channels);
+        assert!(!bus.is_below(0.5));
+        bus.flush_denormals();
+        assert!(bus.is_below(1.0));
+
+        let num_denormals = ramp.iter().take_while(|x| **x < f32::MIN_POSITIVE).count();
+        assert!(num_denormals > 0 && num_denormals < ramp.len());
+        for channel in &channels {
+            assert!(channel[..num_denormals].iter().all(|x| x.to_bits() == 0));
+            let normals = channel[num_denormals..].iter().map(|x| x.abs());
+            assert!(normals.eq(ramp[num_denormals..].iter().copied()));
+        }
+
+        // Once flushed, the tail of the ramp is silent.
+        let mut tail = vec![ramp[..num_denormals].to_vec()];
+        let mut bus = crate::bus::AudioBusMut::from_channels(&mut tail);
+        assert!(!bus.is_below(0.0));
+        bus.flush_denormals();
+        assert!(bus.is_below(0.0));
+    }
+}
@@ -0,0 +1,117 @@
+//! Fixed delays on edges.
+//!
+//! A delayed edge still aliases its source's output buffer like any other edge. While the sink
+//! is processed, its input bus is pointed at the delay line's own buffers instead, and restored
+//! afterwards so the allocator sees the same pointers it handed out. The source's buffer is only
+//! read, as other sinks may share it. Each delayed source of a summing input has a delay line of
+//! its own in the [crate::sum::Summer].
+use crate::bus::{AudioBus, AudioBusMut};
+
+pub(crate) struct DelayLine {
+    position: usize,
+    rings: Vec<Vec<f32>>,
+    outputs: Vec<Vec<f32>>,
+    saved: Vec<*const f32>,
+}
+
+impl DelayLine {
+    pub(crate) fn new(num_channels: usize, delay: usize, max_num_frames: usize) -> Self {
+        debug_assert!(delay > 0);
+        Self {
+            position: 0,
+            rings: vec![vec![0.0; delay]; num_channels],
+            outputs: vec![vec![0.0; max_num_frames]; num_channels],
+            saved: vec![std::ptr::null(); num_channels],
+        }
+    }
+
+    /// Delay the signal on `bus` and point it at the delayed signal.
+    pub(crate) unsafe fn apply(&mut self, bus: &AudioBus) {
+        for (channel, ptr) in bus.ptrs.iter().enumerate() {
+            let output = self.outputs[channel].as_mut_ptr();
+            self.process(channel, *ptr.get(), output, bus.num_frames());
+            self.saved[channel] = *ptr.get();
+            *ptr.get() = output;
+        }
+        self.advance(bus.num_frames());
+    }
+
+    /// Delay the signal on a source's output bus into the delay line's own buffers and return
+    /// them. Used by summing inputs, whose sources are delayed separately before they are summed.
+    pub(crate) unsafe fn apply_to_source(&mut self, bus: &AudioBusMut) -> &[Vec<f32>] {
+        for (channel, ptr) in bus.ptrs.iter().enumerate() {
+            let output = self.outputs[channel].as_mut_ptr();
+            self.process(channel, *ptr.get(), output, bus.num_frames());
+        }
+        self.advance(bus.num_frames());
+        &self.outputs
+    }
+
+    /// Delay the first channels of `input` into those of `output`, which must not alias it. Used
+    /// to bypass a node with latency, see [crate::graph::node::Node::set_bypassed].
+    pub(crate) unsafe fn apply_into(&mut self, input: &AudioBus, output: &AudioBusMut) {
+        let num_frames = output.num_frames();
+        for channel in 0..self.rings.len() {
+            let src = *input.ptrs[channel].get();
+            let dst = *output.ptrs[channel].get();
+            self.process(channel, src, dst, num_frames);
+        }
+        self.advance(num_frames);
+    }
+
+    /// Write the first channels of `input` into the delay line without reading it back, to keep
+    /// its history current while the node isn't bypassed.
+    pub(crate) unsafe fn push(&mut self, input: &AudioBus) {
+        let num_frames = input.num_frames();
+        for (channel, ring) in self.rings.iter_mut().enumerate() {
+            let src = *input.ptrs[channel].get();
+            let mut position = self.position;
+            for frame in 0..num_frames {
+                ring[position] = *src.add(frame);
+                position = (position + 1) % ring.len();
+            }
+        }
+        self.advance(num_frames);
+    }
+
+    unsafe fn process(
+        &mut self,
+        channel: usize,
+        input: *const f32,
+        output: *mut f32,
+        num_frames: usize,
+    ) {
+        let ring = &mut self.rings[channel];
+        let mut position = self.position;
+        for frame in 0..num_frames {
+            let x = *input.add(frame);
+            *output.add(frame) = ring[position];
+            ring[position] = x;
+            position = (position + 1) % ring.len();
+        }
+    }
+
+    fn advance(&mut self, num_frames: usize) {
+        self.position = (self.position + num_frames) % self.rings[0].len();
+    }
+
+    /// Point `bus` back at the buffers it had before [DelayLine::apply].
+    pub(crate) unsafe fn restore(&self, bus: &AudioBus) {
+        for (ptr, saved) in bus.ptrs.iter().zip(&self.saved) {
+            *ptr.get() = *saved;
+        }
+    }
+
+    pub(crate) fn warmup(&mut self) {
+        for buffer in self.rings.iter_mut().chain(&mut self.outputs) {
+            buffer.fill(0.0);
+        }
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.position = 0;
+        for ring in &mut self.rings {
+            ring.fill(0.0);
+        }
+    }
+}
@@ -1,28 +1,108 @@
 use std::{
+    borrow::Cow,
     cell::UnsafeCell,
     collections::{BTreeMap, BTreeSet, VecDeque},
     marker::PhantomData,
-    sync::{atomic::AtomicUsize, Arc, RwLock},
+    mem::MaybeUninit,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, RwLock,
+    },
 };
 
 use crossbeam::queue::ArrayQueue;
-use tesi_util::IsSendSync;
+use tesi_util::{
+    collections::{map::IntMap, set::SmallIntSet},
+    IsSendSync,
+};
 
 use crate::{
     bus::{AudioBus, AudioBusMut},
+    delay::DelayLine,
     graph::node::Node,
-    proc::Processor,
-    renderer::{self, Renderer},
+    proc::{
+        analysis::AnalysisReceiver,
+        params::{Flags, ParameterTree, Preset},
+        ParamChange, ParamRamp, PortRole, Processor,
+    },
+    profile::Profile,
+    renderer::{self, Renderer, WorkerConfig, MAX_NUM_PARAM_CHANGES},
+    sum::Summer,
 };
 
 #[derive(Debug)]
 pub enum Error {
-    AlreadyConnected,
-    BusChannelsMismatched,
-    CycleDetected,
+    /// The input `port` of `node` is already connected, and isn't summing.
+    AlreadyConnected {
+        node: usize,
+        port: usize,
+    },
+    /// The output and input of an edge have different numbers of channels.
+    BusChannelsMismatched {
+        output: usize,
+        input: usize,
+    },
+    /// An edge would close a loop through `via`, the path of nodes from its sink to its source.
+    CycleDetected {
+        via: Vec<usize>,
+    },
     InvalidPort,
+    PoolTooSmall,
+    InvalidConfiguration,
+    InvalidBinding,
+    /// The renderer hasn't been initialized, see [renderer::Renderer::initialize].
+    Uninitialized,
+    /// A node has no preset with this index.
+    InvalidPreset(usize),
+    /// Too many parameter changes are pending for a node to take more.
+    TooManyParamChanges,
+    /// A [GraphPreset] was exported in a format this version can't import.
+    UnsupportedPresetVersion(u32),
+    /// A [GraphPreset] holds more than one set of values for the node with this id.
+    DuplicatePresetNode(usize),
+    /// A [renderer::ThreadPriority] outside of 1 to 99.
+    InvalidPriority(u8),
+    /// The OS refused to pin a render worker to a CPU or raise its priority, see
+    /// [Graph::new_with_workers].
+    WorkerConfigRefused(std::io::Error),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::AlreadyConnected { node, port } => {
+                write!(f, "input {port} of node {node} is already connected")
+            }
+            Self::BusChannelsMismatched { output, input } => write!(
+                f,
+                "cannot connect an output with {output} channels to an input with {input}"
+            ),
+            Self::CycleDetected { via } => {
+                write!(f, "edge would create a cycle through nodes {via:?}")
+            }
+            Self::InvalidPort => write!(f, "no such port"),
+            Self::PoolTooSmall => write!(f, "buffer pool is too small for the graph"),
+            Self::InvalidConfiguration => write!(f, "invalid configuration"),
+            Self::InvalidBinding => write!(f, "inconsistent bindings between nodes"),
+            Self::Uninitialized => write!(f, "the renderer hasn't been initialized"),
+            Self::InvalidPreset(index) => write!(f, "no preset with index {index}"),
+            Self::TooManyParamChanges => write!(f, "too many parameter changes are pending"),
+            Self::UnsupportedPresetVersion(version) => {
+                write!(f, "unsupported preset version {version}")
+            }
+            Self::DuplicatePresetNode(id) => write!(f, "preset has node {id} more than once"),
+            Self::InvalidPriority(priority) => {
+                write!(f, "thread priority {priority} is outside 1 to 99")
+            }
+            Self::WorkerConfigRefused(error) => {
+                write!(f, "cannot configure a render worker: {error}")
+            }
+        }
+    }
 }
 
+impl std::error::Error for Error {}
+
 #[derive(Clone)]
 pub struct Graph {
     pub(crate) inner: Arc<RwLock<Inner>>,
@@ -34,20 +114,188 @@ pub struct Options {
     pub num_workers: usize,
 }
 
+/// A node in a snapshot of the graph's topology, see [Graph::nodes].
+#[derive(Clone, Debug)]
+pub struct NodeInfo {
+    pub id: usize,
+    pub name: &'static str,
+    pub options: node::Options,
+    pub summing: Vec<bool>,
+}
+
+/// An edge in a snapshot of the graph's topology, see [Graph::edges].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EdgeInfo {
+    pub source: usize,
+    pub output: usize,
+    pub sink: usize,
+    pub input: usize,
+    /// The delay set on this edge with [edge::Edge::set_delay].
+    pub delay: usize,
+    /// Whether the edge was made with [edge::Edge::new_monitor].
+    pub monitor: bool,
+}
+
+/// A saved copy of the graph's topology, see [Graph::save_topology]. Processors aren't saved, they
+/// are recreated by the factory passed to [Graph::restore_topology].
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GraphState {
+    /// The ports added to the input node, see [Graph::add_host_input].
+    pub host_inputs: Vec<usize>,
+    /// Whether the output node's input is summing, see [node::Node::set_summing].
+    pub output_summing: bool,
+    pub nodes: Vec<NodeState>,
+    pub edges: Vec<EdgeInfo>,
+}
+
+/// A saved node, not counting the graph's input and output nodes.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NodeState {
+    pub id: usize,
+    /// The type name of the processor, for the factory to tell nodes apart.
+    pub name: String,
+    pub audio_inputs: Vec<usize>,
+    pub audio_outputs: Vec<usize>,
+    pub summing: Vec<bool>,
+    pub latency: usize,
+    pub bypassed: bool,
+}
+
+/// A parameter of a node with its current value, see [node::Node::parameter_list].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParamInfo {
+    pub id: usize,
+    pub name: Cow<'static, str>,
+    pub min: f32,
+    pub max: f32,
+    pub unit: Cow<'static, str>,
+    pub flags: Flags,
+    /// The value last sent or set, see [node::Node::param_value] and [node::Node::output_value],
+    /// or the default if there is none.
+    pub value: f32,
+}
+
+/// Where a parameter change reported by [Graph::poll_param_changes] came from.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ParamOrigin {
+    /// Sent from the control thread, with [node::Node::send_param_change] or a preset.
+    Host,
+    /// Made by the processor itself, see [crate::proc::Context::notify_param_change].
+    Processor,
+}
+
+/// A parameter change reported by [Graph::poll_param_changes].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ParamEvent {
+    /// The id of the node, see [node::Node::id].
+    pub node: usize,
+    pub change: ParamChange,
+    pub origin: ParamOrigin,
+}
+
+/// The format version of [GraphPreset]s exported by this version of the crate.
+pub const PRESET_VERSION: u32 = 1;
+
+/// The parameter values of a graph's nodes, see [Graph::export_preset]. Unlike a [GraphState] it
+/// holds no topology, so it can be exchanged between copies of the same node layout.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GraphPreset {
+    pub version: u32,
+    pub nodes: Vec<NodePreset>,
+}
+
+/// The parameter values of one node in a [GraphPreset].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NodePreset {
+    /// The node's id, see [node::Node::id].
+    pub id: usize,
+    /// The type name of the processor, to check the node is still the same kind.
+    pub name: String,
+    /// Parameter ids and values, by id.
+    pub values: Vec<(usize, f32)>,
+}
+
+/// What [Graph::import_preset] couldn't apply.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PresetReport {
+    /// The ids of saved nodes that no longer exist, or now hold a different processor.
+    pub missing: Vec<usize>,
+}
+
+/// The handles of a graph restored by [Graph::restore_topology], which keep it alive.
+pub struct Restored {
+    /// The new nodes, by their saved id.
+    pub nodes: BTreeMap<usize, Node>,
+    pub edges: Vec<edge::Edge>,
+}
+
 pub(crate) struct Inner {
     pub(crate) nodes: Vec<Option<NodeData>>,
+    /// The ids of removed nodes, for reuse.
     pub(crate) stack: Vec<usize>,
-    pub(crate) sender: triple_buffer::Input<renderer::State>,
+    /// Scratch space for the depth first search that checks new edges for cycles: the nodes left
+    /// to visit, each with the index in `trail` of the node it was reached from.
+    pub(crate) search: Vec<(usize, usize)>,
+    /// The nodes visited by the search, in order, each with the index of the node it was reached
+    /// from, to trace back the path of a cycle.
+    pub(crate) trail: Vec<(usize, usize)>,
+    pub(crate) sender: triple_buffer::Input<renderer::Published>,
     pub(crate) input_node: Option<Node>,
     pub(crate) output_node: Option<Node>,
+    pub(crate) sample_rate: f64,
     pub(crate) num_frames: usize,
+    pub(crate) latency: usize,
+    /// The generation to give the next edge, see [NodeData::generations].
+    pub(crate) next_generation: u64,
+    /// The bytes touched per block by the committed state, see [Graph::memory_traffic_per_block].
+    pub(crate) traffic: usize,
     pub(crate) renderer: Option<renderer::Renderer>,
 }
 
 pub(crate) struct NodeData {
+    pub(crate) name: &'static str,
     pub(crate) options: node::Options,
-    pub(crate) incoming: Vec<Option<(usize, usize)>>,
-    pub(crate) outgoing: Vec<Option<(usize, usize)>>,
+    /// The role of each output, see [Processor::output_role].
+    pub(crate) output_roles: Vec<PortRole>,
+    pub(crate) incoming: Vec<Vec<(usize, usize)>>,
+    pub(crate) summing: Vec<bool>,
+    pub(crate) outgoing: Vec<Vec<(usize, usize)>>,
+    /// The delay of each delayed edge into this node, by input, source and output.
+    pub(crate) delays: BTreeMap<(usize, usize, usize), usize>,
+    /// The monitor edges into this node, by input, source and output.
+    pub(crate) monitors: BTreeSet<(usize, usize, usize)>,
+    /// The generation of each edge into this node, by input, source and output. A handle only
+    /// acts on the edge while its generation matches, so a stale [edge::Edge] can't touch a newer
+    /// edge between the same ports.
+    pub(crate) generations: BTreeMap<(usize, usize, usize), u64>,
+    pub(crate) latency: usize,
+    pub(crate) bypassed: Arc<AtomicBool>,
+    pub(crate) profile: Arc<Profile>,
+    pub(crate) params: Arc<ArrayQueue<ParamChange>>,
+    pub(crate) ramps: Arc<ArrayQueue<ParamRamp>>,
+    /// The value last sent to each parameter, see [node::Node::param_value].
+    pub(crate) values: IntMap<f32>,
+    /// The parameters that ignore changes, see [node::Node::set_param_latch]. Parameter ids are up
+    /// to the processor and may be sparse, so they aren't kept in a [SmallIntSet].
+    pub(crate) latched: BTreeSet<usize>,
+    /// The changes sent since the last [Graph::poll_param_changes], oldest first.
+    pub(crate) sent: VecDeque<ParamChange>,
+    pub(crate) notifications: Arc<ArrayQueue<ParamChange>>,
+    /// The values the processor set on its read-only parameters since they were last read, see
+    /// [node::Node::output_value].
+    pub(crate) outputs: Arc<ArrayQueue<(usize, f32)>>,
+    /// The ids of the read-only parameters, see [Flags::OUTPUT].
+    pub(crate) output_ids: BTreeSet<usize>,
+    pub(crate) analysis: Option<AnalysisReceiver>,
+    pub(crate) parameters: Option<ParameterTree>,
+    pub(crate) presets: Vec<Preset>,
+    pub(crate) current_preset: Option<usize>,
+    pub(crate) committed: bool,
     pub(crate) processor: Arc<IsSendSync<UnsafeCell<dyn Processor>>>,
 }
 
@@ -56,8 +304,14 @@ struct InputNode;
 struct OutputNode;
 
 pub mod node {
-    use crate::{graph, proc::Processor};
-    use std::sync::{Arc, RwLock, Weak};
+    use crate::{
+        graph::{self, edge::Edge},
+        proc::{
+            analysis::AnalysisReceiver, params::ParameterTree, ParamChange, ParamRamp, Processor,
+        },
+        NodeProfile,
+    };
+    use std::sync::{atomic::Ordering, Arc, RwLock, Weak};
 
     #[derive(Clone)]
     pub struct Node {
@@ -83,6 +337,276 @@ pub mod node {
             Self { inner }
         }
 
+        /// The id of this node within its graph. Ids are unique among live nodes, but may be reused
+        /// after a node is dropped.
+        pub fn id(&self) -> usize {
+            self.inner.index
+        }
+
+        /// Connect the graph's input node to `input` on this node, so that it reads the host input
+        /// directly regardless of where it sits in the graph.
+        pub fn tap_graph_input(&self, input: usize) -> Result<Edge, graph::Error> {
+            let graph = graph::Graph {
+                inner: self.inner.graph.upgrade().unwrap(),
+            };
+            Edge::new(&graph, &graph.input_node(), 0, self, input)
+        }
+
+        /// Report that the processor's latency is now `latency` frames. On the next commit, the
+        /// other paths into each node are delayed to line up with the ones through this node.
+        pub fn latency_changed(&self, latency: usize) {
+            let graph = self.inner.graph.upgrade().unwrap();
+            let mut graph = graph.write().unwrap();
+            graph.nodes[self.inner.index].as_mut().unwrap().latency = latency;
+        }
+
+        /// Bypass the processor, passing its first input straight through to its first output and
+        /// silencing any other outputs. Channels are matched by index: output channels the input
+        /// doesn't have are zero-filled and input channels the output doesn't have are dropped. A
+        /// node with latency, see [Node::latency_changed], delays the pass-through by as much, so
+        /// the mix stays aligned with the paths compensated for it. Takes effect from the next
+        /// block without a commit. The switch is hard, with no crossfade, so toggling it on a
+        /// running signal may click.
+        pub fn set_bypassed(&self, bypassed: bool) {
+            let graph = self.inner.graph.upgrade().unwrap();
+            let graph = graph.read().unwrap();
+            let data = graph.nodes[self.inner.index].as_ref().unwrap();
+            data.bypassed.store(bypassed, Ordering::Relaxed);
+        }
+
+        pub fn is_bypassed(&self) -> bool {
+            let graph = self.inner.graph.upgrade().unwrap();
+            let graph = graph.read().unwrap();
+            let data = graph.nodes[self.inner.index].as_ref().unwrap();
+            data.bypassed.load(Ordering::Relaxed)
+        }
+
+        /// Send a parameter change to the processor without a commit. Changes are delivered with
+        /// the next block through [crate::proc::Context::param_changes], with times past its end
+        /// clamped to its last frame. Changes to a latched parameter are dropped, see
+        /// [Node::set_param_latch], as are changes to a read-only one. Fails with
+        /// [graph::Error::TooManyParamChanges] if too many changes are pending.
+        pub fn send_param_change(&self, change: ParamChange) -> Result<(), graph::Error> {
+            let graph = self.inner.graph.upgrade().unwrap();
+            let mut graph = graph.write().unwrap();
+            graph.nodes[self.inner.index].as_mut().unwrap().send(change)
+        }
+
+        /// Send a parameter ramp to the processor without a commit, like [Node::send_param_change].
+        /// It is delivered with the next block through [crate::proc::Context::param_ramps], and
+        /// again with every block after until it ends. Fails with
+        /// [graph::Error::TooManyParamChanges] if too many ramps are pending.
+        pub fn send_param_ramp(&self, ramp: ParamRamp) -> Result<(), graph::Error> {
+            let graph = self.inner.graph.upgrade().unwrap();
+            let mut graph = graph.write().unwrap();
+            graph.nodes[self.inner.index]
+                .as_mut()
+                .unwrap()
+                .send_ramp(ramp)
+        }
+
+        /// The value last sent to parameter `id` with [Node::send_param_change] or
+        /// [Node::load_preset], or set by the processor, either when it was initialized or later
+        /// and picked up by [graph::Graph::poll_param_changes], or `None` if there is none.
+        pub fn param_value(&self, id: usize) -> Option<f32> {
+            let graph = self.inner.graph.upgrade().unwrap();
+            let graph = graph.read().unwrap();
+            let data = graph.nodes[self.inner.index].as_ref().unwrap();
+            data.values.get(id as u64).copied()
+        }
+
+        /// The value the processor last set on read-only parameter `id` with
+        /// [crate::proc::Context::set_param_output], or `None` if it hasn't. Meant to be called
+        /// from the UI thread, e.g. once per frame to draw a meter. It never blocks the audio
+        /// thread.
+        pub fn output_value(&self, id: usize) -> Option<f32> {
+            let graph = self.inner.graph.upgrade().unwrap();
+            let mut graph = graph.write().unwrap();
+            let data = graph.nodes[self.inner.index].as_mut().unwrap();
+            data.read_outputs();
+            data.values
+                .get(id as u64)
+                .copied()
+                .filter(|_| data.output_ids.contains(&id))
+        }
+
+        /// Latch parameter `id` at its current value, so that changes sent with
+        /// [Node::send_param_change] or [Node::load_preset] are dropped until it is unlatched, e.g.
+        /// to hold a value a performer has set against the host's automation. Changes already
+        /// pending are still delivered, and the processor may still change the parameter itself.
+        pub fn set_param_latch(&self, id: usize, latched: bool) {
+            let graph = self.inner.graph.upgrade().unwrap();
+            let mut graph = graph.write().unwrap();
+            let data = graph.nodes[self.inner.index].as_mut().unwrap();
+            if latched {
+                data.latched.insert(id);
+            } else {
+                data.latched.remove(&id);
+            }
+        }
+
+        pub fn is_param_latched(&self, id: usize) -> bool {
+            let graph = self.inner.graph.upgrade().unwrap();
+            let graph = graph.read().unwrap();
+            let data = graph.nodes[self.inner.index].as_ref().unwrap();
+            data.latched.contains(&id)
+        }
+
+        /// Drain the changes the processor made to its own parameters since the last call, oldest
+        /// first, see [crate::proc::Context::notify_param_change]. Meant to be called from the UI
+        /// thread, e.g. once per frame, to keep controls in sync. It's cheap and never blocks the
+        /// audio thread. See [graph::Graph::poll_param_changes] for the changes of every node,
+        /// which drains the same queue.
+        pub fn poll_param_changes(&self) -> impl Iterator<Item = ParamChange> {
+            let graph = self.inner.graph.upgrade().unwrap();
+            let graph = graph.read().unwrap();
+            let notifications = graph.nodes[self.inner.index]
+                .as_ref()
+                .unwrap()
+                .notifications
+                .clone();
+            std::iter::from_fn(move || notifications.pop())
+        }
+
+        /// The time this node's processor has taken per block since it was added or its profile
+        /// was last reset, while profiling was enabled with [crate::Renderer::set_profiling].
+        pub fn profile(&self) -> NodeProfile {
+            let graph = self.inner.graph.upgrade().unwrap();
+            let graph = graph.read().unwrap();
+            graph.nodes[self.inner.index]
+                .as_ref()
+                .unwrap()
+                .profile
+                .snapshot()
+        }
+
+        pub fn reset_profile(&self) {
+            let graph = self.inner.graph.upgrade().unwrap();
+            let graph = graph.read().unwrap();
+            graph.nodes[self.inner.index]
+                .as_ref()
+                .unwrap()
+                .profile
+                .reset();
+        }
+
+        /// Make `input` a summing input, which accepts edges from any number of outputs and adds
+        /// their signals together, so that mixing doesn't need a dedicated node. Fails with
+        /// [graph::Error::AlreadyConnected] when turning summing off while several edges are
+        /// connected. Takes effect on the next commit.
+        pub fn set_summing(&self, input: usize, summing: bool) -> Result<(), graph::Error> {
+            let graph = self.inner.graph.upgrade().unwrap();
+            let mut graph = graph.write().unwrap();
+            let data = graph.nodes[self.inner.index].as_mut().unwrap();
+            let sources = data.incoming.get(input).ok_or(graph::Error::InvalidPort)?;
+            if !summing && sources.len() > 1 {
+                return Err(graph::Error::AlreadyConnected {
+                    node: self.inner.index,
+                    port: input,
+                });
+            }
+            data.summing[input] = summing;
+            Ok(())
+        }
+
+        /// The processor's analysis tap, if it has one. See [crate::proc::analysis].
+        pub fn analysis_tap(&self) -> Option<AnalysisReceiver> {
+            self.inner.graph.upgrade().unwrap().read().unwrap().nodes[self.inner.index]
+                .as_ref()
+                .unwrap()
+                .analysis
+                .clone()
+        }
+
+        /// The description of the processor's parameters, if it has one. See [crate::proc::params].
+        pub fn parameters(&self) -> Option<ParameterTree> {
+            self.inner.graph.upgrade().unwrap().read().unwrap().nodes[self.inner.index]
+                .as_ref()
+                .unwrap()
+                .parameters
+                .clone()
+        }
+
+        /// The processor's parameters in the order of [Node::parameters], each with its current
+        /// value, e.g. to list automation lanes. Empty if the processor doesn't describe any.
+        pub fn parameter_list(&self) -> Vec<graph::ParamInfo> {
+            let graph = self.inner.graph.upgrade().unwrap();
+            let mut graph = graph.write().unwrap();
+            let data = graph.nodes[self.inner.index].as_mut().unwrap();
+            data.read_outputs();
+            let Some(parameters) = &data.parameters else {
+                return vec![];
+            };
+            parameters
+                .iter()
+                .map(|parameter| graph::ParamInfo {
+                    id: parameter.id,
+                    name: parameter.name.clone(),
+                    min: parameter.min,
+                    max: parameter.max,
+                    unit: parameter.unit.clone(),
+                    flags: parameter.flags,
+                    value: data
+                        .values
+                        .get(parameter.id as u64)
+                        .copied()
+                        .unwrap_or(parameter.default),
+                })
+                .collect()
+        }
+
+        /// The number of presets the processor offers, see [crate::proc::params::Preset].
+        pub fn preset_count(&self) -> usize {
+            self.inner.graph.upgrade().unwrap().read().unwrap().nodes[self.inner.index]
+                .as_ref()
+                .unwrap()
+                .presets
+                .len()
+        }
+
+        pub fn preset_name(&self, index: usize) -> Option<String> {
+            let graph = self.inner.graph.upgrade().unwrap();
+            let graph = graph.read().unwrap();
+            let data = graph.nodes[self.inner.index].as_ref().unwrap();
+            let preset = data.presets.get(index)?;
+            Some(preset.name.to_string())
+        }
+
+        /// Send the values of preset `index` as parameter changes at the start of the next block,
+        /// like [Node::send_param_change], skipping latched parameters. Fails with
+        /// [graph::Error::InvalidPreset] if there is no such preset, or
+        /// [graph::Error::TooManyParamChanges] if the pending changes leave no room for all of its
+        /// values, in which case none are sent.
+        pub fn load_preset(&self, index: usize) -> Result<(), graph::Error> {
+            let graph = self.inner.graph.upgrade().unwrap();
+            let mut graph = graph.write().unwrap();
+            let data = graph.nodes[self.inner.index].as_mut().unwrap();
+            let preset = data
+                .presets
+                .get(index)
+                .ok_or(graph::Error::InvalidPreset(index))?;
+            let values = preset.values.iter().filter(|(id, _)| data.accepts(*id));
+            if data.params.capacity() - data.params.len() < values.clone().count() {
+                return Err(graph::Error::TooManyParamChanges);
+            }
+            let changes = values
+                .map(|&(id, value)| ParamChange { id, time: 0, value })
+                .collect::<Vec<_>>();
+            for change in changes {
+                data.send(change).ok();
+            }
+            data.current_preset = Some(index);
+            Ok(())
+        }
+
+        /// The preset last loaded with [Node::load_preset], if any.
+        pub fn current_preset(&self) -> Option<usize> {
+            self.inner.graph.upgrade().unwrap().read().unwrap().nodes[self.inner.index]
+                .as_ref()
+                .unwrap()
+                .current_preset
+        }
+
         pub fn options(&self) -> Options {
             self.inner.graph.upgrade().unwrap().read().unwrap().nodes[self.inner.index]
                 .as_ref()
@@ -114,6 +638,7 @@ pub mod edge {
         output: usize,
         sink: Arc<node::Inner>,
         input: usize,
+        generation: u64,
         graph: Weak<RwLock<graph::Inner>>,
     }
 
@@ -125,7 +650,7 @@ pub mod edge {
             sink: &node::Node,
             input: usize,
         ) -> Result<Self, graph::Error> {
-            graph.inner.write().unwrap().add_edge(
+            let generation = graph.inner.write().unwrap().add_edge(
                 source.inner.index,
                 output,
                 sink.inner.index,
@@ -136,11 +661,33 @@ pub mod edge {
                 output,
                 sink: sink.inner.clone(),
                 input,
+                generation,
                 graph: Arc::downgrade(&graph.inner),
             });
             Ok(Self { inner })
         }
 
+        /// Connect `output` of `source` to `input` of `sink` for monitoring only, as for a meter or
+        /// a scope. The signal is routed as usual, but the edge doesn't count towards the latency
+        /// of the sink, so a monitor on a path with latency doesn't make the graph delay the
+        /// other paths into the sink to match it. Nor is the edge delayed to match them: it
+        /// carries the source's signal as is, plus its own [Edge::set_delay].
+        pub fn new_monitor(
+            graph: &graph::Graph,
+            source: &node::Node,
+            output: usize,
+            sink: &node::Node,
+            input: usize,
+        ) -> Result<Self, graph::Error> {
+            let edge = Self::new(graph, source, output, sink, input)?;
+            graph.inner.write().unwrap().nodes[sink.inner.index]
+                .as_mut()
+                .unwrap()
+                .monitors
+                .insert((input, source.inner.index, output));
+            Ok(edge)
+        }
+
         pub fn source(&self) -> (node::Node, usize) {
             (
                 node::Node {
@@ -150,6 +697,28 @@ pub mod edge {
             )
         }
 
+        /// Delay the signal carried by this edge by a fixed number of frames. Other edges into
+        /// the same summing input aren't delayed by it, but paths that end up shorter than the
+        /// one through this edge are compensated like for node latency. Takes effect on the next
+        /// commit. Does nothing once the edge has been removed, see
+        /// [graph::Graph::disconnect_node].
+        pub fn set_delay(&self, frames: usize) {
+            let Some(graph) = self.inner.graph.upgrade() else {
+                return;
+            };
+            let mut graph = graph.write().unwrap();
+            if !self.inner.is_current(&graph) {
+                return;
+            }
+            let sink = graph.nodes[self.inner.sink.index].as_mut().unwrap();
+            let key = (self.inner.input, self.inner.source.index, self.inner.output);
+            if frames == 0 {
+                sink.delays.remove(&key);
+            } else {
+                sink.delays.insert(key, frames);
+            }
+        }
+
         pub fn sink(&self) -> (node::Node, usize) {
             (
                 node::Node {
@@ -160,25 +729,45 @@ pub mod edge {
         }
     }
 
+    impl Inner {
+        fn is_current(&self, graph: &graph::Inner) -> bool {
+            graph.is_current_edge(
+                self.source.index,
+                self.output,
+                self.sink.index,
+                self.input,
+                self.generation,
+            )
+        }
+    }
+
     impl Drop for Inner {
         fn drop(&mut self) {
             let Some(graph) = self.graph.upgrade() else {
                 return;
             };
-            graph.write().unwrap().remove_edge(
-                self.source.index,
-                self.output,
-                self.sink.index,
-                self.input,
-            );
+            let mut graph = graph.write().unwrap();
+            if self.is_current(&graph) {
+                graph.remove_edge(self.source.index, self.output, self.sink.index, self.input);
+            }
         }
     }
 }
 
 impl Graph {
     pub fn new(options: Options) -> Self {
+        Self::new_with_workers(options, WorkerConfig::default())
+            .expect("the default worker config leaves the workers to the OS")
+    }
+
+    /// Create a graph whose render workers are pinned to CPUs or run at a realtime priority, as
+    /// set by `workers`. Only supported on Linux, elsewhere the config is ignored. Fails with
+    /// [Error::InvalidPriority] for a priority outside of 1 to 99, or with
+    /// [Error::WorkerConfigRefused] if the OS refuses a setting, e.g. a CPU that doesn't exist or
+    /// a realtime priority without permission.
+    pub fn new_with_workers(options: Options, workers: WorkerConfig) -> Result<Self, Error> {
         // Create the sender/receiver
-        let (sender, receiver) = triple_buffer::triple_buffer(&renderer::State::new());
+        let (sender, receiver) = triple_buffer::triple_buffer(&renderer::Published::default());
 
         // Create the graph.
         let nodes = vec![];
@@ -188,10 +777,16 @@ impl Graph {
         let inner = Arc::new(RwLock::new(Inner {
             nodes,
             stack,
+            search: vec![],
+            trail: vec![],
             sender,
             input_node,
             output_node,
+            sample_rate: 48e3,
             num_frames: 2048,
+            latency: 0,
+            next_generation: 0,
+            traffic: 0,
             renderer: None,
         }));
 
@@ -200,7 +795,7 @@ impl Graph {
             let mut inner_ = inner.write().unwrap();
             let renderer = Renderer {
                 graph: Some(Arc::downgrade(&inner)),
-                inner: renderer::Inner::new(options.num_workers, receiver),
+                inner: renderer::Inner::new(options.num_workers, workers, receiver)?,
                 _p: PhantomData,
             };
             inner_.renderer.replace(renderer);
@@ -226,57 +821,171 @@ impl Graph {
             graph_.output_node.replace(output_node);
         }
 
-        graph
+        Ok(graph)
     }
 
+    /// The renderer of the graph, or `None` if it is held by a
+    /// [crate::proc::builtin::subgraph::Processor].
     pub fn renderer(&self) -> Option<renderer::Renderer> {
         let graph = Arc::downgrade(&self.inner);
         let mut renderer = self.inner.write().unwrap().renderer.clone().take()?;
+        if renderer.inner.claimed.load(Ordering::Acquire) {
+            return None;
+        }
         renderer.graph.replace(graph);
         Some(renderer)
     }
 
-    pub fn commit_changes(&self) {
+    /// Render the last committed state of the graph offline, for tests and bounces. `inputs` and
+    /// `outputs` hold one slice per host channel, all of the same length, and are processed in
+    /// blocks of `block_size` frames, which may not exceed [Graph::max_buffer_size].
+    ///
+    /// The renderer is initialized at [Graph::sample_rate] and reset first, and rendered single
+    /// threaded, so the output is the same on every call regardless of the number of workers.
+    /// Don't call this while a host is driving the renderer, and initialize it again before the
+    /// host resumes.
+    ///
+    /// Returns the number of frames rendered. A render cancelled from another thread with
+    /// [Renderer::cancel] stops after the current block, discarding it, and the rest of the
+    /// outputs are zeroed. Fails with [Error::InvalidConfiguration] if the number or lengths of
+    /// the slices or the block size don't fit the graph, before any processor is initialized.
+    pub fn render_offline(
+        &self,
+        inputs: &[&[f32]],
+        outputs: &mut [&mut [f32]],
+        block_size: usize,
+    ) -> Result<usize, Error> {
+        let num_frames = outputs.first().map_or(0, |output| output.len());
+        let lengths = inputs.iter().map(|input| input.len());
+        if block_size == 0
+            || block_size > self.max_buffer_size()
+            || lengths
+                .chain(outputs.iter().map(|output| output.len()))
+                .any(|len| len != num_frames)
+        {
+            return Err(Error::InvalidConfiguration);
+        }
+        let mut renderer = self.renderer().ok_or(Error::InvalidConfiguration)?;
+
+        // Check the channels against the last committed state before touching any processor.
+        unsafe { (*renderer.inner.state.get()).update() };
+        if renderer.inner.num_io_channels() != (inputs.len(), outputs.len()) {
+            return Err(Error::InvalidConfiguration);
+        }
+        let driven = renderer.inner.driven.load(Ordering::Relaxed);
+        renderer.initialize(self.sample_rate(), block_size);
+        renderer.inner.driven.store(driven, Ordering::Relaxed);
+        renderer.reset();
+
+        // Force the single threaded path for the duration of the render.
+        let deterministic = renderer.inner.deterministic.load(Ordering::Relaxed);
+        renderer.set_deterministic(true);
+        renderer.inner.cancelled.store(false, Ordering::Relaxed);
+        renderer.inner.offline.store(true, Ordering::Release);
+        let mut input_ptrs = vec![std::ptr::null(); inputs.len()];
+        let mut output_ptrs = vec![std::ptr::null_mut(); outputs.len()];
+        let mut num_rendered = 0;
+        for start in (0..num_frames).step_by(block_size) {
+            let len = block_size.min(num_frames - start);
+            for (ptr, input) in input_ptrs.iter_mut().zip(inputs) {
+                *ptr = input[start..].as_ptr();
+            }
+            for (ptr, output) in output_ptrs.iter_mut().zip(outputs.iter_mut()) {
+                *ptr = output[start..].as_mut_ptr();
+            }
+            renderer.render(
+                input_ptrs.as_ptr(),
+                output_ptrs.as_mut_ptr(),
+                inputs.len(),
+                outputs.len(),
+                len,
+            );
+            if renderer.inner.cancelled.load(Ordering::Acquire) {
+                break;
+            }
+            num_rendered += len;
+        }
+        for output in outputs.iter_mut() {
+            output[num_rendered..].fill(0.0);
+        }
+        renderer.inner.offline.store(false, Ordering::Release);
+        renderer.inner.cancelled.store(false, Ordering::Relaxed);
+        renderer.set_deterministic(deterministic);
+        Ok(num_rendered)
+    }
+
+    /// Commit changes, publishing them to the renderer. Fails with [Error::InvalidBinding] if the
+    /// compiled state would bind a port that doesn't exist or doesn't bind back, which the
+    /// renderer can't afford to check, in which case nothing is published.
+    pub fn commit_changes(&self) -> Result<(), Error> {
+        self.commit(None)
+    }
+
+    /// Commit changes, carving the renderer's audio buffers out of a caller-supplied pool rather
+    /// than allocating one. Any existing contents of the pool are discarded. A pool of
+    /// [Graph::pool_size] always suffices. A smaller one may leave the renderer short of buffers,
+    /// in which case the nodes it can't give buffers to are skipped and their outputs silenced,
+    /// each reported as [renderer::XrunKind::PoolExhausted]. Fails with [Error::PoolTooSmall] if
+    /// the pool can't even hold the buffers the graph starts out with.
+    pub fn commit_changes_with_pool(&self, pool: Vec<MaybeUninit<f32>>) -> Result<(), Error> {
+        self.commit(Some(pool))
+    }
+
+    fn commit(&self, pool: Option<Vec<MaybeUninit<f32>>>) -> Result<(), Error> {
         // Acquire an exclusive lock over the graph.
         let mut graph = self.inner.write().unwrap();
 
-        // Sort topologically with BFS to remap nodes to indices.
+        // Sort topologically with Kahn's algorithm to remap nodes to indices. Every node with no
+        // bound inputs is a source, whether or not it has input ports.
         let mut indices = BTreeMap::new();
-        let sources = graph
+        let mut indegrees = graph
             .nodes
             .iter()
             .enumerate()
             .filter_map(|(index, node)| {
-                let node = node.as_ref()?;
-                node.incoming.is_empty().then_some(index)
+                Some((index, node.as_ref()?.incoming.iter().flatten().count()))
             })
-            .collect::<Vec<_>>();
-        let mut queue: VecDeque<_> = sources.clone().into();
+            .collect::<BTreeMap<_, _>>();
+        let mut queue = indegrees
+            .iter()
+            .filter_map(|(index, indegree)| (*indegree == 0).then_some(*index))
+            .collect::<VecDeque<_>>();
         while let Some(node) = queue.pop_front() {
-            if indices.contains_key(&node) {
-                continue;
-            }
             let index = indices.len();
             indices.insert(node, index);
             let node = graph.nodes[node].as_ref().unwrap();
-            let adjacent = node
-                .outgoing
-                .iter()
-                .flatten()
-                .map(|(node, _)| *node)
-                .collect::<Vec<_>>();
-            queue.extend(adjacent);
-        }
-
-        // Make sure the output node is present.
-        if !indices.contains_key(&1) {
-            indices.insert(1, indices.len());
+            for (sink, _) in node.outgoing.iter().flatten() {
+                let indegree = indegrees.get_mut(sink).unwrap();
+                *indegree -= 1;
+                if *indegree == 0 {
+                    queue.push_back(*sink);
+                }
+            }
         }
+        debug_assert_eq!(indices.len(), indegrees.len(), "the graph has a cycle");
 
         // Get the input and output nodes.
         let input_node = *indices.get(&0).unwrap();
         let output_node = *indices.get(&1).unwrap();
 
+        // Find the latency at every node's outputs, to align the paths into each node.
+        let mut latencies = BTreeMap::new();
+        for index in indices.keys() {
+            output_latency(&graph.nodes, *index, &mut latencies);
+        }
+        let input_latency =
+            |index: usize| latencies[&index] - graph.nodes[index].as_ref().unwrap().latency;
+        // Each edge is delayed by its own delay plus whatever lines it up with the latest path
+        // into its sink. Monitor edges are left out of the alignment.
+        let edge_delay = |sink: usize, input: usize, (source, output): (usize, usize)| {
+            let node = graph.nodes[sink].as_ref().unwrap();
+            if node.is_monitor(input, (source, output)) {
+                node.delay(input, (source, output))
+            } else {
+                input_latency(sink) - latencies[&source]
+            }
+        };
+
         // Create the renderer state for each node.
         let mut sorted_indices = indices.iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>();
         sorted_indices.sort_unstable_by_key(|(_, new)| *new);
@@ -284,31 +993,68 @@ impl Graph {
             .into_iter()
             .map(|(old, _)| {
                 let data = graph.nodes[old].as_ref().unwrap();
+                let edge_delays = data
+                    .incoming
+                    .iter()
+                    .enumerate()
+                    .map(|(input, sources)| {
+                        sources
+                            .iter()
+                            .map(|source| edge_delay(old, input, *source))
+                            .collect::<Vec<_>>()
+                    })
+                    .collect::<Vec<_>>();
+
+                // The sources of a summing input are delayed separately by the summer, and so is a
+                // delayed source of the output node, which is copied to the host's buffers rather
+                // than delayed in place. Any other input with a single delayed source is delayed
+                // by its own delay line.
+                let is_summed = |sources: &[(usize, usize)], delays: &[usize]| {
+                    sources.len() > 1 || (old == 1 && delays.iter().any(|delay| *delay > 0))
+                };
                 let incoming = data
                     .incoming
                     .iter()
                     .map(|old| {
-                        let Some(old) = old else {
-                            return None;
-                        };
-                        let new = (*indices.get(&old.0).unwrap(), old.1);
-                        Some(new)
+                        old.iter()
+                            .map(|old| (*indices.get(&old.0).unwrap(), old.1))
+                            .collect::<Vec<_>>()
+                            .into_boxed_slice()
                     })
                     .collect::<Vec<_>>()
                     .into_boxed_slice();
+                let sums = incoming
+                    .iter()
+                    .zip(&data.options.audio_inputs)
+                    .zip(&edge_delays)
+                    .map(|((sources, num_channels), delays)| {
+                        is_summed(sources, delays)
+                            .then(|| Summer::new(*num_channels, graph.num_frames, delays))
+                    })
+                    .collect::<Vec<_>>();
+                let summed = sums
+                    .iter()
+                    .map(Option::is_some)
+                    .collect::<Vec<_>>()
+                    .into_boxed_slice();
+                let sums = IsSendSync::new(UnsafeCell::new(sums));
 
                 let outgoing = data
                     .outgoing
                     .iter()
                     .map(|old| {
-                        let Some(old) = old else {
-                            return None;
-                        };
-                        let new = (*indices.get(&old.0).unwrap(), old.1);
-                        Some(new)
+                        old.iter()
+                            .map(|old| (*indices.get(&old.0).unwrap(), old.1))
+                            .collect::<Vec<_>>()
+                            .into_boxed_slice()
                     })
                     .collect::<Vec<_>>()
                     .into_boxed_slice();
+                let readers = outgoing
+                    .iter()
+                    .map(|sinks| AtomicUsize::new(sinks.len()))
+                    .collect::<Vec<_>>()
+                    .into_boxed_slice();
                 let audio_inputs = data
                     .options
                     .audio_inputs
@@ -334,21 +1080,84 @@ impl Graph {
                     .collect::<Vec<_>>();
 
                 let audio_outputs = IsSendSync::new(UnsafeCell::new(audio_outputs));
+
+                let delays = incoming
+                    .iter()
+                    .zip(&data.options.audio_inputs)
+                    .zip(&edge_delays)
+                    .map(|((sources, num_channels), delays)| {
+                        let delay = delays.first().copied().unwrap_or(0);
+                        (delay > 0 && *num_channels > 0 && !is_summed(sources, delays))
+                            .then(|| DelayLine::new(*num_channels, delay, graph.num_frames))
+                    })
+                    .collect::<Vec<_>>();
+                let delays = IsSendSync::new(UnsafeCell::new(delays));
+
+                // A bypassed node with latency delays its pass-through by as much. The delay line
+                // writes straight to the node's output, so it needs no buffers of its own.
+                let num_bypass_channels = data
+                    .options
+                    .audio_inputs
+                    .first()
+                    .zip(data.options.audio_outputs.first())
+                    .map_or(0, |(inputs, outputs)| *inputs.min(outputs));
+                let bypass_delay = (data.latency > 0 && num_bypass_channels > 0)
+                    .then(|| DelayLine::new(num_bypass_channels, data.latency, 0));
+                let bypass_delay = IsSendSync::new(UnsafeCell::new(bypass_delay));
+
                 renderer::Node {
-                    _id: old,
+                    id: old,
                     audio_inputs,
                     audio_outputs,
-                    indegree: AtomicUsize::new(0),
+                    indegree: AtomicUsize::new(incoming.iter().map(|sources| sources.len()).sum()),
                     incoming,
                     outgoing,
+                    readers,
+                    sums,
+                    summed,
+                    delays,
+                    output_roles: data.output_roles.clone().into_boxed_slice(),
+                    bypassed: data.bypassed.clone(),
+                    bypass_delay,
+                    profile: data.profile.clone(),
+                    params: data.params.clone(),
+                    notifications: data.notifications.clone(),
+                    outputs: data.outputs.clone(),
+                    param_changes: IsSendSync::new(UnsafeCell::new(Vec::with_capacity(
+                        MAX_NUM_PARAM_CHANGES,
+                    ))),
+                    ramps: data.ramps.clone(),
+                    param_ramps: IsSendSync::new(UnsafeCell::new(Vec::with_capacity(
+                        MAX_NUM_PARAM_CHANGES,
+                    ))),
                     processor: data.processor.clone(),
                 }
             })
             .collect::<Vec<_>>();
 
+        let latency = latencies[&1];
+
+        // The renderer seeds its work queue with the input node, then with the other sources.
+        let sources = nodes
+            .iter()
+            .enumerate()
+            .filter(|(index, node)| {
+                *index != input_node && node.incoming.iter().all(|sources| sources.is_empty())
+            })
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+
+        // Check the bindings before publishing them to the renderer, which trusts them.
+        validate_bindings(&nodes)?;
+
         // Allocate audio buffers.
+        let pool = pool.unwrap_or_else(|| {
+            let num_channels = crate::alloc::num_channels(&nodes);
+            Vec::with_capacity(crate::alloc::pool_size(graph.num_frames, num_channels))
+        });
         let (alloc, data) =
-            crate::alloc::compile(input_node, output_node, graph.num_frames, 0, &nodes);
+            crate::alloc::compile(input_node, output_node, graph.num_frames, &nodes, pool)
+                .ok_or(Error::PoolTooSmall)?;
 
         // Create the work queue.
         let queue = ArrayQueue::new(nodes.len());
@@ -362,11 +1171,453 @@ impl Graph {
             output_node,
             sources,
             counter: AtomicUsize::new(0),
-            _data: data,
+            data,
         };
 
+        // Initialize the processors of nodes added since the renderer was initialized, which it
+        // won't do itself until it is initialized again.
+        let (sample_rate, max_num_frames) = graph.renderer.as_ref().map_or((0.0, 0), |renderer| {
+            let sample_rate = renderer.inner.sample_rate.load(Ordering::Relaxed);
+            let max_num_frames = renderer.inner.max_num_frames.load(Ordering::Relaxed);
+            (f64::from_bits(sample_rate), max_num_frames)
+        });
+        for data in graph.nodes.iter_mut().flatten() {
+            if !data.committed && max_num_frames != 0 {
+                unsafe {
+                    (*data.processor.get()).initialize(sample_rate, max_num_frames);
+                    data.cache_initial_values();
+                }
+            }
+            data.committed = true;
+        }
+
         // Update the renderer.
-        graph.sender.write(state);
+        graph.latency = latency;
+        graph.traffic = crate::alloc::num_channels(&state.nodes)
+            * graph.num_frames
+            * std::mem::size_of::<f32>();
+        graph
+            .sender
+            .write(renderer::Published(Some(Box::new(state))));
+
+        Ok(())
+    }
+
+    /// Set the sample rate and maximum buffer size the graph is compiled for. Buffers allocated by
+    /// [Graph::commit_changes] hold `max_buffer_size` frames, rounded up to a multiple of 4, so it
+    /// must be at least as large as the buffer size the renderer is initialized with. Takes effect
+    /// on the next commit.
+    pub fn configure(&self, sample_rate: f64, max_buffer_size: usize) -> Result<(), Error> {
+        if sample_rate.is_nan() || sample_rate <= 0.0 || max_buffer_size == 0 {
+            return Err(Error::InvalidConfiguration);
+        }
+        let mut graph = self.inner.write().unwrap();
+        graph.sample_rate = sample_rate;
+        graph.num_frames = max_buffer_size.next_multiple_of(4);
+        Ok(())
+    }
+
+    pub fn sample_rate(&self) -> f64 {
+        self.inner.read().unwrap().sample_rate
+    }
+
+    /// The capacity in samples of a pool that is large enough to commit the graph as it is now
+    /// with [Graph::commit_changes_with_pool] and render it without running out of buffers.
+    pub fn pool_size(&self) -> usize {
+        let graph = self.inner.read().unwrap();
+        let num_channels = graph
+            .nodes
+            .iter()
+            .flatten()
+            .map(|node| {
+                let options = &node.options;
+                options.audio_inputs.iter().sum::<usize>()
+                    + options.audio_outputs.iter().sum::<usize>()
+            })
+            .sum();
+        crate::alloc::pool_size(graph.num_frames, num_channels)
+    }
+
+    pub fn max_buffer_size(&self) -> usize {
+        self.inner.read().unwrap().num_frames
+    }
+
+    /// The latency from the graph's input to its output in frames, as of the last commit: the
+    /// longest path through the graph, counting the latency of each node and the delay of each
+    /// edge. Shorter paths are delayed to match.
+    pub fn latency(&self) -> usize {
+        self.inner.read().unwrap().latency
+    }
+
+    /// The sample rate the renderer was last initialized with, or zero if it hasn't been.
+    pub fn current_sample_rate(&self) -> f64 {
+        let graph = self.inner.read().unwrap();
+        graph.renderer.as_ref().map_or(0.0, |renderer| {
+            f64::from_bits(renderer.inner.sample_rate.load(Ordering::Relaxed))
+        })
+    }
+
+    /// The maximum buffer size the renderer was last initialized with, or zero if it hasn't been.
+    pub fn current_max_frames(&self) -> usize {
+        let graph = self.inner.read().unwrap();
+        graph.renderer.as_ref().map_or(0, |renderer| {
+            renderer.inner.max_num_frames.load(Ordering::Relaxed)
+        })
+    }
+
+    /// An estimate of the number of bytes read and written per block at the maximum buffer size:
+    /// every channel of every bus of every node, inputs and outputs counted separately. Reflects
+    /// the last commit, and is zero before the first.
+    pub fn memory_traffic_per_block(&self) -> usize {
+        self.inner.read().unwrap().traffic
+    }
+
+    /// Export the current topology of the graph in Graphviz DOT format. Nodes are labeled with
+    /// their id and processor type, edges with their ports and channel counts.
+    pub fn to_dot(&self) -> String {
+        use std::fmt::Write;
+        let graph = self.inner.read().unwrap();
+        let mut dot = String::from("digraph {\n");
+        for (index, node) in graph.nodes.iter().enumerate() {
+            let Some(node) = node else {
+                continue;
+            };
+            writeln!(dot, "    n{index} [label=\"{index}: {}\"];", node.name).unwrap();
+        }
+        for (index, node) in graph.nodes.iter().enumerate() {
+            let Some(node) = node else {
+                continue;
+            };
+            for (output, (sink, input)) in node
+                .outgoing
+                .iter()
+                .enumerate()
+                .flat_map(|(output, sinks)| sinks.iter().map(move |sink| (output, sink)))
+            {
+                let num_channels = node.options.audio_outputs[output];
+                writeln!(
+                    dot,
+                    "    n{index} -> n{sink} [label=\"{output} -> {input} ({num_channels}ch)\"];"
+                )
+                .unwrap();
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Reserve capacity for at least `num_nodes` more nodes and `num_edges` more edges, to avoid
+    /// reallocating while building a large graph. This covers the node arena, the list of free
+    /// ids and the scratch space used to check each new edge for cycles. The buffer pool is
+    /// allocated once per commit at its exact size, and needs no reservation.
+    pub fn reserve(&self, num_nodes: usize, num_edges: usize) {
+        let mut graph = self.inner.write().unwrap();
+        graph.nodes.reserve(num_nodes);
+        let num_ids = graph.nodes.len() + num_nodes - graph.stack.len();
+        graph.stack.reserve(num_ids);
+        // The search pushes each edge at most once, after the edge's sink.
+        let num_edges = graph.num_edges() + num_edges;
+        graph.search.clear();
+        graph.search.reserve(num_edges + 1);
+        let num_nodes = graph.nodes.len() + num_nodes;
+        graph.trail.clear();
+        graph.trail.reserve(num_nodes);
+    }
+
+    /// Add an output port to the input node for another host input stream, such as a second
+    /// audio interface. The stream's channels follow those of the existing ports in the input
+    /// pointers passed to [renderer::Renderer::render]. Returns the port index and the input
+    /// node. Takes effect on the next commit.
+    pub fn add_host_input(&self, num_channels: usize) -> (usize, Node) {
+        let input_node = self.input_node();
+        let mut graph = self.inner.write().unwrap();
+        let data = graph.nodes[input_node.id()].as_mut().unwrap();
+        data.options.audio_outputs.push(num_channels);
+        data.outgoing.push(vec![]);
+        (data.outgoing.len() - 1, input_node)
+    }
+
+    /// Remove every edge into or out of `node`, without needing their [edge::Edge] handles.
+    /// Dropping those handles afterwards is harmless, even if the same ports have been connected
+    /// again in the meantime: the new edge has its own handle and stays. Returns the number of
+    /// edges removed. Takes effect on the next commit.
+    pub fn disconnect_node(&self, node: &Node) -> usize {
+        let mut graph = self.inner.write().unwrap();
+        let index = node.id();
+        let data = graph.nodes[index].as_ref().unwrap();
+        let incoming = data
+            .incoming
+            .iter()
+            .enumerate()
+            .flat_map(|(input, sources)| {
+                sources
+                    .iter()
+                    .map(move |(source, output)| (*source, *output, index, input))
+            });
+        let outgoing = data
+            .outgoing
+            .iter()
+            .enumerate()
+            .flat_map(|(output, sinks)| {
+                sinks
+                    .iter()
+                    .map(move |(sink, input)| (index, output, *sink, *input))
+            });
+        let edges = incoming.chain(outgoing).collect::<Vec<_>>();
+        for (source, output, sink, input) in &edges {
+            graph.remove_edge(*source, *output, *sink, *input);
+        }
+        edges.len()
+    }
+
+    /// A snapshot of the nodes currently in the graph, including uncommitted changes, in order
+    /// of id.
+    pub fn nodes(&self) -> impl Iterator<Item = NodeInfo> {
+        let graph = self.inner.read().unwrap();
+        let nodes = graph
+            .nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(id, node)| {
+                let node = node.as_ref()?;
+                Some(NodeInfo {
+                    id,
+                    name: node.name,
+                    options: node.options.clone(),
+                    summing: node.summing.clone(),
+                })
+            })
+            .collect::<Vec<_>>();
+        nodes.into_iter()
+    }
+
+    /// A snapshot of the edges currently in the graph, including uncommitted changes, ordered by
+    /// source.
+    pub fn edges(&self) -> impl Iterator<Item = EdgeInfo> {
+        let graph = self.inner.read().unwrap();
+        let edges = graph
+            .nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(source, node)| Some((source, node.as_ref()?)))
+            .flat_map(|(source, node)| {
+                let nodes = &graph.nodes;
+                node.outgoing
+                    .iter()
+                    .enumerate()
+                    .flat_map(move |(output, sinks)| {
+                        sinks.iter().map(move |&(sink, input)| EdgeInfo {
+                            source,
+                            output,
+                            sink,
+                            input,
+                            delay: nodes[sink].as_ref().unwrap().delay(input, (source, output)),
+                            monitor: nodes[sink]
+                                .as_ref()
+                                .unwrap()
+                                .is_monitor(input, (source, output)),
+                        })
+                    })
+            })
+            .collect::<Vec<_>>();
+        edges.into_iter()
+    }
+
+    /// Drain the parameter changes of every node since the last call, for an editor to follow.
+    /// Each node's changes sent from the control thread come first, tagged [ParamOrigin::Host],
+    /// then those its processor made itself, tagged [ParamOrigin::Processor], each oldest first.
+    /// The processor's changes also update [node::Node::param_value]. This drains the same queue
+    /// as [node::Node::poll_param_changes], so use one or the other. A few hundred changes are
+    /// kept per node and origin; past that the oldest are dropped.
+    pub fn poll_param_changes(&self) -> Vec<ParamEvent> {
+        let mut graph = self.inner.write().unwrap();
+        let mut events = vec![];
+        for (id, node) in graph.nodes.iter_mut().enumerate() {
+            let Some(node) = node else {
+                continue;
+            };
+            events.extend(node.sent.drain(..).map(|change| ParamEvent {
+                node: id,
+                change,
+                origin: ParamOrigin::Host,
+            }));
+            while let Some(change) = node.notifications.pop() {
+                node.values.insert(change.id as u64, change.value);
+                events.push(ParamEvent {
+                    node: id,
+                    change,
+                    origin: ParamOrigin::Processor,
+                });
+            }
+        }
+        events
+    }
+
+    /// Export the parameter values of every node, as last sent with
+    /// [node::Node::send_param_change] or [node::Node::load_preset]. Nodes without any are left
+    /// out.
+    pub fn export_preset(&self) -> GraphPreset {
+        let graph = self.inner.read().unwrap();
+        let nodes = graph
+            .nodes
+            .iter()
+            .enumerate()
+            .skip(2)
+            .filter_map(|(id, node)| {
+                let node = node.as_ref()?;
+                let values = node
+                    .values
+                    .iter()
+                    .map(|(id, value)| (id as usize, *value))
+                    .filter(|(id, _)| !node.output_ids.contains(id))
+                    .collect::<Vec<_>>();
+                (!values.is_empty()).then(|| NodePreset {
+                    id,
+                    name: node.name.to_owned(),
+                    values,
+                })
+            })
+            .collect();
+        GraphPreset {
+            version: PRESET_VERSION,
+            nodes,
+        }
+    }
+
+    /// Send the values of a preset exported with [Graph::export_preset] to the matching nodes, as
+    /// parameter changes at the start of the next block like [node::Node::load_preset], skipping
+    /// latched parameters. Nodes that no longer exist, or hold a different processor, are
+    /// reported and skipped. Fails with [Error::UnsupportedPresetVersion] if the preset is from
+    /// a newer format, [Error::DuplicatePresetNode] if it holds a node more than once, or
+    /// [Error::TooManyParamChanges] if the pending changes of a node leave no room for its values,
+    /// in which case none are sent.
+    pub fn import_preset(&self, preset: &GraphPreset) -> Result<PresetReport, Error> {
+        if preset.version > PRESET_VERSION {
+            return Err(Error::UnsupportedPresetVersion(preset.version));
+        }
+        let mut ids = BTreeSet::new();
+        if let Some(saved) = preset.nodes.iter().find(|saved| !ids.insert(saved.id)) {
+            return Err(Error::DuplicatePresetNode(saved.id));
+        }
+        let mut graph = self.inner.write().unwrap();
+
+        // Match the nodes and check they have room before sending anything.
+        let mut report = PresetReport::default();
+        let mut matched = vec![];
+        for saved in &preset.nodes {
+            let node = graph.nodes.get(saved.id).and_then(Option::as_ref);
+            let Some(node) = node.filter(|node| saved.id >= 2 && node.name == saved.name) else {
+                report.missing.push(saved.id);
+                continue;
+            };
+            let values = saved
+                .values
+                .iter()
+                .filter(|(id, _)| node.accepts(*id))
+                .collect::<Vec<_>>();
+            if node.params.capacity() - node.params.len() < values.len() {
+                return Err(Error::TooManyParamChanges);
+            }
+            matched.push((saved.id, values));
+        }
+
+        for (id, values) in matched {
+            let node = graph.nodes[id].as_mut().unwrap();
+            for &(id, value) in values {
+                node.send(ParamChange { id, time: 0, value }).ok();
+            }
+        }
+        Ok(report)
+    }
+
+    /// Save the nodes and edges of the graph, including uncommitted changes.
+    pub fn save_topology(&self) -> GraphState {
+        let graph = self.inner.read().unwrap();
+        let host_inputs = graph.nodes[0].as_ref().unwrap().options.audio_outputs[1..].to_vec();
+        let nodes = graph
+            .nodes
+            .iter()
+            .enumerate()
+            .skip(2)
+            .filter_map(|(id, node)| {
+                let node = node.as_ref()?;
+                Some(NodeState {
+                    id,
+                    name: node.name.to_owned(),
+                    audio_inputs: node.options.audio_inputs.clone(),
+                    audio_outputs: node.options.audio_outputs.clone(),
+                    summing: node.summing.clone(),
+                    latency: node.latency,
+                    bypassed: node.bypassed.load(Ordering::Relaxed),
+                })
+            })
+            .collect();
+        let output_summing = graph.nodes[1].as_ref().unwrap().summing[0];
+        drop(graph);
+        let edges = self.edges().collect();
+        GraphState {
+            host_inputs,
+            output_summing,
+            nodes,
+            edges,
+        }
+    }
+
+    /// Add the nodes and edges saved by [Graph::save_topology] to this graph. `factory` creates
+    /// each node with the same ports as the saved one. Saved ids are remapped to the ids of the
+    /// new nodes, and the saved input and output nodes to this graph's. Fails with
+    /// [Error::InvalidConfiguration] if the factory creates a node with different ports, or with
+    /// the error of the first edge that can't be connected. Takes effect on the next commit.
+    pub fn restore_topology(
+        &self,
+        state: &GraphState,
+        mut factory: impl FnMut(&Graph, &NodeState) -> Node,
+    ) -> Result<Restored, Error> {
+        // Add the host inputs the graph doesn't have yet.
+        let num_host_inputs = self.input_node().options().audio_outputs.len() - 1;
+        for num_channels in state.host_inputs.iter().skip(num_host_inputs) {
+            self.add_host_input(*num_channels);
+        }
+
+        self.output_node().set_summing(0, state.output_summing)?;
+
+        // Create the nodes.
+        let mut nodes = BTreeMap::new();
+        nodes.insert(0, self.input_node());
+        nodes.insert(1, self.output_node());
+        for saved in &state.nodes {
+            let node = factory(self, saved);
+            let options = node.options();
+            if options.audio_inputs != saved.audio_inputs
+                || options.audio_outputs != saved.audio_outputs
+            {
+                return Err(Error::InvalidConfiguration);
+            }
+            for (input, summing) in saved.summing.iter().enumerate() {
+                node.set_summing(input, *summing)?;
+            }
+            node.latency_changed(saved.latency);
+            node.set_bypassed(saved.bypassed);
+            nodes.insert(saved.id, node);
+        }
+
+        // Connect the edges.
+        let mut edges = vec![];
+        for saved in &state.edges {
+            let source = nodes.get(&saved.source).ok_or(Error::InvalidPort)?;
+            let sink = nodes.get(&saved.sink).ok_or(Error::InvalidPort)?;
+            let edge = if saved.monitor {
+                edge::Edge::new_monitor(self, source, saved.output, sink, saved.input)?
+            } else {
+                edge::Edge::new(self, source, saved.output, sink, saved.input)?
+            };
+            edge.set_delay(saved.delay);
+            edges.push(edge);
+        }
+
+        nodes.remove(&0);
+        nodes.remove(&1);
+        Ok(Restored { nodes, edges })
     }
 
     pub fn input_node(&self) -> Node {
@@ -378,14 +1629,114 @@ impl Graph {
     }
 }
 
+/// The latency at the outputs of `index`: its own latency plus that of its latest input. Memoized
+/// in `latencies`, which must not be stale.
+fn output_latency(
+    nodes: &[Option<NodeData>],
+    index: usize,
+    latencies: &mut BTreeMap<usize, usize>,
+) -> usize {
+    if let Some(latency) = latencies.get(&index) {
+        return *latency;
+    }
+    let node = nodes[index].as_ref().unwrap();
+    let mut latency = 0;
+    for (input, sources) in node.incoming.iter().enumerate() {
+        for &(source, output) in sources {
+            if node.is_monitor(input, (source, output)) {
+                continue;
+            }
+            let arrival =
+                output_latency(nodes, source, latencies) + node.delay(input, (source, output));
+            latency = latency.max(arrival);
+        }
+    }
+    latency += node.latency;
+    latencies.insert(index, latency);
+    latency
+}
+
+/// Check that every binding points to a port that exists and binds back to it.
+fn validate_bindings(nodes: &[renderer::Node]) -> Result<(), Error> {
+    for (index, node) in nodes.iter().enumerate() {
+        for (port, sources) in node.incoming.iter().enumerate() {
+            for &(source, output) in sources.iter() {
+                let source = nodes.get(source).ok_or(Error::InvalidBinding)?;
+                let sinks = source.outgoing.get(output).ok_or(Error::InvalidBinding)?;
+                if !sinks.contains(&(index, port)) {
+                    return Err(Error::InvalidBinding);
+                }
+            }
+        }
+        for (port, sinks) in node.outgoing.iter().enumerate() {
+            for &(sink, input) in sinks.iter() {
+                let sink = nodes.get(sink).ok_or(Error::InvalidBinding)?;
+                let sources = sink.incoming.get(input).ok_or(Error::InvalidBinding)?;
+                if !sources.contains(&(index, port)) {
+                    return Err(Error::InvalidBinding);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
 impl Inner {
+    /// Cache the values the committed processors report after they are initialized, see
+    /// [Processor::initial_values].
+    pub(crate) fn cache_initial_values(&mut self) {
+        for data in self.nodes.iter_mut().flatten() {
+            if data.committed {
+                unsafe { data.cache_initial_values() };
+            }
+        }
+    }
+
     fn add_node(&mut self, options: node::Options, p: impl Processor + 'static) -> usize {
-        let incoming = vec![None; options.audio_inputs.len()];
-        let outgoing = vec![None; options.audio_outputs.len()];
+        let incoming = vec![vec![]; options.audio_inputs.len()];
+        let summing = vec![false; options.audio_inputs.len()];
+        let outgoing = vec![vec![]; options.audio_outputs.len()];
+        let delays = BTreeMap::new();
+        let monitors = BTreeSet::new();
+        let generations = BTreeMap::new();
+        let analysis = p.analysis_tap();
+        let parameters = p.parameters();
+        let presets = p.presets();
+        let output_roles = (0..options.audio_outputs.len())
+            .map(|output| p.output_role(output))
+            .collect();
+        let output_ids = parameters
+            .iter()
+            .flat_map(ParameterTree::iter)
+            .filter(|parameter| parameter.flags.contains(Flags::OUTPUT))
+            .map(|parameter| parameter.id)
+            .collect();
         let node = NodeData {
+            name: std::any::type_name_of_val(&p),
             options,
+            output_roles,
             incoming,
+            summing,
             outgoing,
+            delays,
+            monitors,
+            generations,
+            latency: 0,
+            bypassed: Arc::new(AtomicBool::new(false)),
+            profile: Arc::new(Profile::new()),
+            params: Arc::new(ArrayQueue::new(MAX_NUM_PARAM_CHANGES)),
+            ramps: Arc::new(ArrayQueue::new(MAX_NUM_PARAM_CHANGES)),
+            values: IntMap::new(),
+            latched: BTreeSet::new(),
+            sent: VecDeque::new(),
+            notifications: Arc::new(ArrayQueue::new(MAX_NUM_PARAM_CHANGES)),
+            outputs: Arc::new(ArrayQueue::new(MAX_NUM_PARAM_CHANGES)),
+            output_ids,
+            analysis,
+            parameters,
+            presets,
+            current_preset: None,
+            committed: false,
             processor: Arc::new(IsSendSync::new(UnsafeCell::new(p))),
         };
 
@@ -400,7 +1751,17 @@ impl Inner {
     }
 
     fn remove_node(&mut self, index: usize) {
-        self.nodes.get_mut(index).and_then(|node| node.take());
+        if let Some(Some(_)) = self.nodes.get_mut(index).map(Option::take) {
+            self.stack.push(index);
+        }
+    }
+
+    fn num_edges(&self) -> usize {
+        self.nodes
+            .iter()
+            .flatten()
+            .map(|node| node.outgoing.iter().map(Vec::len).sum::<usize>())
+            .sum()
     }
 
     fn add_edge(
@@ -409,41 +1770,61 @@ impl Inner {
         output: usize,
         sink: usize,
         input: usize,
-    ) -> Result<(), Error> {
+    ) -> Result<u64, Error> {
         let source_ = self.nodes[source].as_ref().unwrap();
         let sink_ = self.nodes[sink].as_ref().unwrap();
 
-        // Check if source:output and sink:input are disconnected
-        if source_
-            .outgoing
-            .get(output)
-            .ok_or(Error::InvalidPort)?
-            .is_some()
-            || sink_
-                .incoming
-                .get(input)
-                .ok_or(Error::InvalidPort)?
-                .is_some()
-        {
-            return Err(Error::AlreadyConnected);
+        // Check that the ports exist and that sink:input is free. An output may feed any number
+        // of inputs, and a summing input may be fed by any number of distinct outputs.
+        source_.outgoing.get(output).ok_or(Error::InvalidPort)?;
+        let sources = sink_.incoming.get(input).ok_or(Error::InvalidPort)?;
+        if (!sink_.summing[input] && !sources.is_empty()) || sources.contains(&(source, output)) {
+            return Err(Error::AlreadyConnected {
+                node: sink,
+                port: input,
+            });
         }
 
         // Check that the connection is valid.
-        if source_.options.audio_outputs[output] != sink_.options.audio_inputs[input] {
-            return Err(Error::BusChannelsMismatched);
+        let (num_outputs, num_inputs) = (
+            source_.options.audio_outputs[output],
+            sink_.options.audio_inputs[input],
+        );
+        if num_outputs != num_inputs {
+            return Err(Error::BusChannelsMismatched {
+                output: num_outputs,
+                input: num_inputs,
+            });
         }
 
-        // Check if the edge would create a cycle.
-        let mut visited = BTreeSet::new();
-        let mut stack = vec![sink];
-        while let Some(node) = stack.pop() {
-            if node == source {
-                return Err(Error::CycleDetected);
-            }
-            if visited.contains(&node) {
+        // Check if the edge would create a cycle, remembering how each node was reached so the
+        // error can report the path.
+        let mut visited = SmallIntSet::new();
+        let mut stack = std::mem::take(&mut self.search);
+        let mut trail = std::mem::take(&mut self.trail);
+        stack.clear();
+        trail.clear();
+        stack.push((sink, 0));
+        while let Some((node, parent)) = stack.pop() {
+            if visited.contains(node) {
                 continue;
             }
             visited.insert(node);
+            let index = trail.len();
+            trail.push((node, parent));
+            if node == source {
+                // The sink is the first node visited, so walking back ends there.
+                let mut via = vec![node];
+                let mut index = index;
+                while index != 0 {
+                    index = trail[index].1;
+                    via.push(trail[index].0);
+                }
+                via.reverse();
+                self.search = stack;
+                self.trail = trail;
+                return Err(Error::CycleDetected { via });
+            }
             stack.extend(
                 self.nodes[node]
                     .as_ref()
@@ -451,20 +1832,120 @@ impl Inner {
                     .outgoing
                     .iter()
                     .flatten()
-                    .map(|(node, _)| *node),
+                    .map(|(next, _)| (*next, index)),
             );
         }
+        self.search = stack;
+        self.trail = trail;
 
         // Update the node data.
-        self.nodes[source].as_mut().unwrap().outgoing[output].replace((sink, input));
-        self.nodes[sink].as_mut().unwrap().incoming[input].replace((source, output));
+        self.nodes[source].as_mut().unwrap().outgoing[output].push((sink, input));
+        let sink = self.nodes[sink].as_mut().unwrap();
+        sink.incoming[input].push((source, output));
+        let generation = self.next_generation;
+        self.next_generation += 1;
+        sink.generations.insert((input, source, output), generation);
 
-        Ok(())
+        Ok(generation)
+    }
+
+    /// Whether the edge from `source:output` into `sink:input` is the one of this generation.
+    fn is_current_edge(
+        &self,
+        source: usize,
+        output: usize,
+        sink: usize,
+        input: usize,
+        generation: u64,
+    ) -> bool {
+        self.nodes[sink]
+            .as_ref()
+            .and_then(|sink| sink.generations.get(&(input, source, output)))
+            == Some(&generation)
     }
 
     fn remove_edge(&mut self, source: usize, output: usize, sink: usize, input: usize) {
-        self.nodes[source].as_mut().unwrap().outgoing[output].take();
-        self.nodes[sink].as_mut().unwrap().incoming[input].take();
+        self.nodes[source].as_mut().unwrap().outgoing[output].retain(|edge| *edge != (sink, input));
+        let sink = self.nodes[sink].as_mut().unwrap();
+        sink.incoming[input].retain(|edge| *edge != (source, output));
+        sink.delays.remove(&(input, source, output));
+        sink.monitors.remove(&(input, source, output));
+        sink.generations.remove(&(input, source, output));
+    }
+}
+
+impl NodeData {
+    /// The delay set on the edge from `source` into `input`, see [edge::Edge::set_delay].
+    fn delay(&self, input: usize, (source, output): (usize, usize)) -> usize {
+        self.delays
+            .get(&(input, source, output))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Whether the host may change parameter `id`, which it can't while it's latched or if it's
+    /// read-only.
+    fn accepts(&self, id: usize) -> bool {
+        !self.latched.contains(&id) && !self.output_ids.contains(&id)
+    }
+
+    /// Cache the values the processor reports after it is initialized, see
+    /// [Processor::initial_values]. The processor must not be processing.
+    unsafe fn cache_initial_values(&mut self) {
+        for (id, value) in (*self.processor.get()).initial_values() {
+            self.values.insert(id as u64, value);
+        }
+    }
+
+    /// Move the values the processor set on its read-only parameters into [NodeData::values].
+    fn read_outputs(&mut self) {
+        while let Some((id, value)) = self.outputs.pop() {
+            self.values.insert(id as u64, value);
+        }
+    }
+
+    /// Send a parameter change to the processor, unless it doesn't accept it, and record it.
+    fn send(&mut self, change: ParamChange) -> Result<(), Error> {
+        if !self.accepts(change.id) {
+            return Ok(());
+        }
+        self.params
+            .push(change)
+            .map_err(|_| Error::TooManyParamChanges)?;
+        self.record(change);
+        Ok(())
+    }
+
+    /// Record a change sent to the processor, for [node::Node::param_value] and
+    /// [Graph::poll_param_changes].
+    fn record(&mut self, change: ParamChange) {
+        self.values.insert(change.id as u64, change.value);
+        if self.sent.len() == MAX_NUM_PARAM_CHANGES {
+            self.sent.pop_front();
+        }
+        self.sent.push_back(change);
+    }
+
+    /// Send a parameter ramp to the processor, unless it doesn't accept it, and record the change
+    /// to its end value.
+    fn send_ramp(&mut self, ramp: ParamRamp) -> Result<(), Error> {
+        if !self.accepts(ramp.id) {
+            return Ok(());
+        }
+        self.ramps
+            .push(ramp)
+            .map_err(|_| Error::TooManyParamChanges)?;
+        self.record(ParamChange {
+            id: ramp.id,
+            time: ramp.end_frame,
+            value: ramp.end_value,
+        });
+        Ok(())
+    }
+
+    /// Whether the edge from `source` into `input` is a monitor, see [edge::Edge::new_monitor].
+    fn is_monitor(&self, input: usize, (source, output): (usize, usize)) -> bool {
+        self.monitors.contains(&(input, source, output))
     }
 }
 
@@ -486,6 +1967,175 @@ unsafe impl Send for Inner {}
 unsafe impl Sync for Inner {}
 impl Drop for Inner {
     fn drop(&mut self) {
-        self.renderer.take();
+        // Tell the renderer to stop, in case it is still being driven.
+        if let Some(renderer) = self.renderer.take() {
+            renderer.inner.detached.store(true, Ordering::Release);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph() -> Graph {
+        Graph::new(Options {
+            num_input_channels: 0,
+            num_output_channels: 0,
+            num_workers: 0,
+        })
+    }
+
+    fn thru() -> node::Options {
+        node::Options {
+            audio_inputs: vec![1],
+            audio_outputs: vec![1],
+        }
+    }
+
+    #[test]
+    fn reserve_avoids_reallocation() {
+        let graph = graph();
+        graph.reserve(1000, 1000);
+        let capacities = |graph: &Graph| {
+            let graph = graph.inner.read().unwrap();
+            (
+                graph.nodes.as_ptr(),
+                graph.nodes.capacity(),
+                graph.search.capacity(),
+                graph.trail.capacity(),
+            )
+        };
+        let reserved = capacities(&graph);
+        let nodes = (0..1000)
+            .map(|_| Node::new(&graph, thru(), InputNode))
+            .collect::<Vec<_>>();
+        let _edges = nodes
+            .windows(2)
+            .map(|pair| edge::Edge::new(&graph, &pair[0], 0, &pair[1], 0).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(capacities(&graph), reserved);
+    }
+
+    #[test]
+    fn ids_are_reused() {
+        let graph = graph();
+        let first = Node::new(&graph, thru(), InputNode);
+        let second = Node::new(&graph, thru(), InputNode);
+        let id = first.id();
+        drop(first);
+        let third = Node::new(&graph, thru(), InputNode);
+        assert_eq!(third.id(), id);
+        assert_ne!(second.id(), id);
+        assert_eq!(graph.nodes().count(), 4);
+    }
+
+    #[test]
+    fn configure_sizes_buffers() {
+        let graph = graph();
+        assert!(graph.configure(0.0, 64).is_err());
+        assert!(graph.configure(f64::NAN, 64).is_err());
+        assert!(graph.configure(48e3, 0).is_err());
+
+        graph.configure(44.1e3, 30).unwrap();
+        assert_eq!(graph.sample_rate(), 44.1e3);
+        assert_eq!(graph.max_buffer_size(), 32);
+        let _node = Node::new(&graph, thru(), InputNode);
+        let pool_size = graph.pool_size();
+        graph.commit_changes().unwrap();
+
+        let renderer = graph.renderer().unwrap();
+        let state = unsafe {
+            let receiver = &mut *renderer.inner.state.get();
+            receiver.update();
+            receiver.output_buffer().0.as_deref().unwrap()
+        };
+        assert_eq!(state.alloc.silence.len(), 32);
+        assert_eq!(state.data.capacity(), pool_size);
+        assert_eq!(pool_size, crate::alloc::pool_size(32, 2));
+    }
+
+    #[test]
+    fn warmup_touches_the_whole_pool() {
+        let graph = Graph::new(Options {
+            num_input_channels: 1,
+            num_output_channels: 1,
+            num_workers: 0,
+        });
+        let node = Node::new(&graph, thru(), InputNode);
+        let edges = [
+            edge::Edge::new(&graph, &graph.input_node(), 0, &node, 0).unwrap(),
+            edge::Edge::new(&graph, &node, 0, &graph.output_node(), 0).unwrap(),
+        ];
+        edges[0].set_delay(16);
+
+        // Fill the pool with a sentinel that committing doesn't overwrite everywhere.
+        let sentinel = f32::from_bits(0x7fc0_1234);
+        let mut pool = vec![MaybeUninit::new(sentinel); graph.pool_size() + 64];
+        pool.clear();
+        graph.commit_changes_with_pool(pool).unwrap();
+
+        let mut renderer = graph.renderer().unwrap();
+        renderer.initialize(48e3, 64);
+        let samples = |renderer: &Renderer| unsafe {
+            let receiver = &mut *renderer.inner.state.get();
+            let data = &receiver.output_buffer().0.as_deref().unwrap().data;
+            std::slice::from_raw_parts(data.as_ptr().cast::<f32>(), data.capacity()).to_vec()
+        };
+        assert!(samples(&renderer)
+            .iter()
+            .any(|sample| sample.to_bits() == sentinel.to_bits()));
+        renderer.warmup();
+        assert!(samples(&renderer)
+            .iter()
+            .all(|sample| sample.to_bits() == 0));
+    }
+
+    #[test]
+    fn inconsistent_bindings_are_caught() {
+        let graph = Graph::new(Options {
+            num_input_channels: 1,
+            num_output_channels: 1,
+            num_workers: 0,
+        });
+        let node = Node::new(&graph, thru(), InputNode);
+        let _edges = [
+            edge::Edge::new(&graph, &graph.input_node(), 0, &node, 0).unwrap(),
+            edge::Edge::new(&graph, &node, 0, &graph.output_node(), 0).unwrap(),
+        ];
+        graph.commit_changes().unwrap();
+
+        let renderer = graph.renderer().unwrap();
+        let nodes = unsafe {
+            let receiver = &mut *renderer.inner.state.get();
+            receiver.update();
+            &mut receiver.output_buffer().0.as_deref_mut().unwrap().nodes
+        };
+        assert!(validate_bindings(nodes).is_ok());
+        let index = nodes.iter().position(|n| n.id == node.id()).unwrap();
+
+        // A sink's input that doesn't exist.
+        let (sink, _) = nodes[index].outgoing[0][0];
+        let outgoing = std::mem::replace(&mut nodes[index].outgoing[0], Box::new([(sink, 5)]));
+        assert!(matches!(
+            validate_bindings(nodes),
+            Err(Error::InvalidBinding)
+        ));
+        nodes[index].outgoing[0] = outgoing;
+
+        // A source that doesn't bind back.
+        let incoming = std::mem::replace(&mut nodes[index].incoming[0], Box::new([(sink, 0)]));
+        assert!(matches!(
+            validate_bindings(nodes),
+            Err(Error::InvalidBinding)
+        ));
+        nodes[index].incoming[0] = incoming;
+
+        // A node that doesn't exist.
+        nodes[index].incoming[0] = Box::new([(nodes.len(), 0)]);
+        assert!(matches!(
+            validate_bindings(nodes),
+            Err(Error::InvalidBinding)
+        ));
     }
 }
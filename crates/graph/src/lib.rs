@@ -26,7 +26,13 @@ pub mod graph;
 pub mod proc;
 
 mod alloc;
+mod delay;
+mod profile;
 mod renderer;
+mod sum;
+mod varispeed;
 
 pub use graph::*;
+pub use profile::NodeProfile;
 pub use renderer::*;
+pub use varispeed::MAX_RATE_SCALE;
@@ -1,12 +1,367 @@
 use crate::bus::{AudioBus, AudioBusMut};
+use crossbeam::queue::ArrayQueue;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+pub mod analysis;
+pub mod builtin;
+pub mod cpu;
+pub mod params;
+
+pub use cpu::{cpu_features, CpuFeatures};
 
 pub trait Processor {
     fn initialize(&mut self, sample_rate: f64, max_num_frames: usize);
     fn process(&mut self, context: &mut Context<'_>);
     fn reset(&mut self);
+
+    /// The receiving end of a tap on this processor's signal for visualization, see [analysis].
+    /// Called once when the processor is added to a graph.
+    fn analysis_tap(&self) -> Option<analysis::AnalysisReceiver> {
+        None
+    }
+
+    /// A description of the parameters this processor accepts as [ParamChange]s, see [params].
+    /// Called once when the processor is added to a graph.
+    fn parameters(&self) -> Option<params::ParameterTree> {
+        None
+    }
+
+    /// The named presets a host may load, see [params::Preset]. Called once when the processor
+    /// is added to a graph.
+    fn presets(&self) -> Vec<params::Preset> {
+        vec![]
+    }
+
+    /// The id and value of each parameter the processor set up in [Processor::initialize], like a
+    /// default adapted to the sample rate. Called after every initialization, and cached for
+    /// [crate::graph::node::Node::param_value] so hosts don't have to ask for each one.
+    fn initial_values(&self) -> Vec<(usize, f32)> {
+        vec![]
+    }
+
+    /// What output port `output` carries, see [PortRole]. Called once for each output when the
+    /// processor is added to a graph.
+    fn output_role(&self, _output: usize) -> PortRole {
+        PortRole::Main
+    }
+}
+
+/// What an output port carries, see [Processor::output_role].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum PortRole {
+    /// Whatever the processor writes to it.
+    #[default]
+    Main,
+    /// The first input as it came in, like the dry signal of a reverb next to its wet one. The
+    /// renderer copies it over after the processor runs, even while the node is bypassed, so the
+    /// processor doesn't have to write to it. Channels past the input's are zeroed.
+    DryPassthrough,
 }
 
 pub struct Context<'a> {
     pub audio_inputs: &'a [AudioBus],
     pub audio_outputs: &'a mut [AudioBusMut],
+    /// The parameter changes for this block, sorted by time. Changes at the same time are in the
+    /// order they were sent. Processors without automated parameters can ignore them.
+    pub param_changes: &'a [ParamChange],
+    /// The parameter ramps for this block, sorted by start frame, see [ParamRamp]. A ramp that ends
+    /// past the block is delivered again with the next, moved to its start.
+    pub param_ramps: &'a [ParamRamp],
+    /// The host's transport at the start of the block, or `None` if the host hasn't provided one.
+    pub transport: Option<Transport>,
+    /// Set by the processor to report its outputs silent for the block, with bit `n` for output
+    /// `n`. The renderer zeroes them, so the processor doesn't have to, and its sinks see them as
+    /// [AudioBus::is_silent] and may skip work. A processor with a tail, like a reverb, should
+    /// only report silence once the tail has decayed. Outputs past the 32nd can't be reported.
+    pub silent_outputs: u32,
+    /// The number of frames the processor produced, which starts as the number in the block.
+    /// A processor that produces fewer, like a rate-limited one, lowers it and the renderer runs it
+    /// again for the rest of the block, with its buses and [Context::param_changes] moved past the
+    /// frames it produced, so no input is lost. A processor that runs out, like a player reaching
+    /// the end of its file, produces none on that next run, and the renderer zeroes the rest of its
+    /// outputs. Producing no frames at all reports every output silent.
+    pub num_frames_produced: usize,
+    /// Where changes the processor makes to its own parameters go, see
+    /// [Context::notify_param_change]. `None` when the context isn't from a graph's renderer.
+    pub param_notifications: Option<&'a ArrayQueue<ParamChange>>,
+    /// Where the processor's readouts go, see [Context::set_param_output]. `None` when the
+    /// context isn't from a graph's renderer.
+    pub param_outputs: Option<&'a ArrayQueue<(usize, f32)>>,
+    /// Set when the offline render is cancelled, see [Context::should_cancel]. `None` when the
+    /// context isn't from a graph's renderer.
+    pub cancel: Option<&'a AtomicBool>,
+    /// A seed for processors that generate random numbers, like noise, to seed their generator
+    /// with on the first block after a reset. It is fixed for each node in deterministic mode, see
+    /// [crate::Renderer::set_deterministic], and changes with every reset otherwise.
+    pub seed: u64,
+}
+
+/// The state of the host's transport, see [crate::Renderer::set_transport].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Transport {
+    pub tempo_bpm: f64,
+    /// The number of beats per bar and the note value of a beat, e.g. `(6, 8)`.
+    pub time_sig: (u32, u32),
+    /// The position of the first frame of the block, in beats from the start of the song.
+    pub song_pos_beats: f64,
+    pub is_playing: bool,
+    /// The start and end of the loop in beats, if the host is looping.
+    pub loop_beats: Option<(f64, f64)>,
+}
+
+impl Context<'_> {
+    /// The number of frames in the block, or zero if the processor has no buses.
+    pub fn num_frames(&self) -> usize {
+        self.audio_outputs
+            .first()
+            .map(AudioBusMut::num_frames)
+            .or_else(|| self.audio_inputs.first().map(AudioBus::num_frames))
+            .unwrap_or(0)
+    }
+
+    pub fn input(&self, index: usize) -> &AudioBus {
+        &self.audio_inputs[index]
+    }
+
+    pub fn output(&mut self, index: usize) -> &mut AudioBusMut {
+        &mut self.audio_outputs[index]
+    }
+
+    /// The inputs and outputs at once, to read one while writing another.
+    pub fn split_io(&mut self) -> (&[AudioBus], &mut [AudioBusMut]) {
+        (self.audio_inputs, self.audio_outputs)
+    }
+
+    /// Tell the host about a change the processor made to one of its own parameters, like a
+    /// preset load or a learned MIDI CC, so its UI can follow. The host picks it up with
+    /// [crate::graph::node::Node::poll_param_changes]. Does not allocate or block: once too many
+    /// are pending the oldest is dropped.
+    pub fn notify_param_change(&self, change: ParamChange) {
+        if let Some(notifications) = self.param_notifications {
+            notifications.force_push(change);
+        }
+    }
+
+    /// Set the value of read-only parameter `id`, like a level meter, see [params::Flags::OUTPUT].
+    /// The host reads it with [crate::graph::node::Node::output_value]. Does not allocate or
+    /// block: once too many are pending the oldest is dropped.
+    pub fn set_param_output(&self, id: usize, value: f32) {
+        if let Some(outputs) = self.param_outputs {
+            outputs.force_push((id, value));
+        }
+    }
+
+    /// Whether the offline render this block belongs to was cancelled with
+    /// [crate::Renderer::cancel]. A processor doing a lot of work in one block, like a long
+    /// convolution, can check it now and then and return early. Its output is discarded.
+    pub fn should_cancel(&self) -> bool {
+        self.cancel
+            .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+    }
+
+    /// Pairs of matching channels of the first input and first output, for processors that map
+    /// one to the other. Channels without a match are skipped.
+    pub fn zip_channels(&mut self) -> impl Iterator<Item = (&[f32], &mut [f32])> {
+        let input = self.audio_inputs.first();
+        let output = self.audio_outputs.first_mut();
+        input
+            .into_iter()
+            .zip(output)
+            .flat_map(|(input, output)| input.into_iter().zip(output))
+    }
+}
+
+/// A change to one of a processor's parameters, sent with
+/// [crate::graph::node::Node::send_param_change].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ParamChange {
+    /// The parameter, as numbered by the processor.
+    pub id: usize,
+    /// The frame of the block at which the parameter reaches `value`. Processors may ramp to it
+    /// from the previous value rather than jump.
+    pub time: usize,
+    pub value: f32,
+}
+
+/// A ramp of one of a processor's parameters over a span of frames, sent with
+/// [crate::graph::node::Node::send_param_ramp], so that a host can automate a smooth change
+/// without a change for every frame. Frames count from the start of the block it's delivered with.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ParamRamp {
+    /// The parameter, as numbered by the processor.
+    pub id: usize,
+    /// The frame at which the parameter takes `start_value`. It keeps its previous value until
+    /// then.
+    pub start_frame: usize,
+    /// The frame at which the parameter reaches `end_value`, which it keeps from then on.
+    pub end_frame: usize,
+    pub start_value: f32,
+    pub end_value: f32,
+    pub curve: Curve,
+}
+
+/// How a [ParamRamp] moves from its start to its end value.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Curve {
+    /// Equal steps in equal times.
+    #[default]
+    Linear,
+    /// Equal ratios in equal times, like a gain fading in decibels. Ramps between values that
+    /// aren't both positive are linear.
+    Exponential,
+}
+
+impl ParamRamp {
+    /// The value of the parameter on `frame`, from its start frame on.
+    pub fn value_at(&self, frame: usize) -> f32 {
+        if frame <= self.start_frame {
+            return self.start_value;
+        }
+        if frame >= self.end_frame {
+            return self.end_value;
+        }
+        let t = (frame - self.start_frame) as f32 / (self.end_frame - self.start_frame) as f32;
+        let (start, end) = (self.start_value, self.end_value);
+        match self.curve {
+            Curve::Exponential if start > 0.0 && end > 0.0 => start * (end / start).powf(t),
+            _ => start + (end - start) * t,
+        }
+    }
+
+    /// The rest of the ramp past its first `num_frames`, counting from the frame after them, or
+    /// `None` if it has reached its end value before then.
+    pub(crate) fn skip(&self, num_frames: usize) -> Option<Self> {
+        (self.end_frame >= num_frames).then(|| Self {
+            start_frame: self.start_frame.saturating_sub(num_frames),
+            end_frame: self.end_frame - num_frames,
+            start_value: self.value_at(num_frames),
+            ..*self
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::kernels;
+
+    fn new_context<'a>(inputs: &'a [AudioBus], outputs: &'a mut [AudioBusMut]) -> Context<'a> {
+        Context {
+            audio_inputs: inputs,
+            audio_outputs: outputs,
+            param_changes: &[],
+            param_ramps: &[],
+            transport: None,
+            silent_outputs: 0,
+            num_frames_produced: 0,
+            param_notifications: None,
+            param_outputs: None,
+            cancel: None,
+            seed: 0,
+        }
+    }
+
+    #[test]
+    fn num_frames_prefers_the_outputs() {
+        let input = vec![vec![0.0; 4]];
+        let mut output = vec![vec![0.0; 8]];
+        let inputs = [AudioBus::from_channels(&input)];
+        let mut outputs = [AudioBusMut::from_channels(&mut output)];
+        assert_eq!(new_context(&inputs, &mut outputs).num_frames(), 8);
+        assert_eq!(new_context(&inputs, &mut []).num_frames(), 4);
+        assert_eq!(new_context(&[], &mut []).num_frames(), 0);
+    }
+
+    #[test]
+    fn zip_channels_pairs_the_first_buses() {
+        let input = vec![vec![1.0; 4], vec![2.0; 4], vec![3.0; 4]];
+        let mut output = vec![vec![0.0; 4]; 2];
+        let mut other = vec![vec![0.0; 4]];
+        let inputs = [AudioBus::from_channels(&input)];
+        let mut outputs = [
+            AudioBusMut::from_channels(&mut output),
+            AudioBusMut::from_channels(&mut other),
+        ];
+        let mut context = new_context(&inputs, &mut outputs);
+        for (input, output) in context.zip_channels() {
+            for (x, y) in input.iter().zip(output) {
+                *y = 2.0 * x;
+            }
+        }
+        assert_eq!(context.zip_channels().count(), 2);
+        assert_eq!(&context.output(0)[0], [2.0; 4]);
+        assert_eq!(&context.output(0)[1], [4.0; 4]);
+        assert_eq!(&context.output(1)[0], [0.0; 4]);
+
+        // Nothing to pair without an input.
+        let mut outputs = [AudioBusMut::from_channels(&mut output)];
+        assert_eq!(new_context(&[], &mut outputs).zip_channels().count(), 0);
+    }
+
+    #[test]
+    fn split_io_reads_while_writing() {
+        let first = vec![vec![1.0; 4]];
+        let second = vec![vec![2.0; 4]];
+        let mut output = vec![vec![0.0; 4]];
+        let inputs = [
+            AudioBus::from_channels(&first),
+            AudioBus::from_channels(&second),
+        ];
+        let mut outputs = [AudioBusMut::from_channels(&mut output)];
+        let mut context = new_context(&inputs, &mut outputs);
+        assert_eq!(&context.input(1)[0], [2.0; 4]);
+        let (inputs, outputs) = context.split_io();
+        for input in inputs {
+            kernels::add_assign(&mut outputs[0][0], &input[0]);
+        }
+        assert_eq!(&context.output(0)[0], [3.0; 4]);
+    }
+
+    #[test]
+    fn ramps_follow_their_curve() {
+        let ramp = ParamRamp {
+            id: 0,
+            start_frame: 2,
+            end_frame: 6,
+            start_value: 1.0,
+            end_value: 16.0,
+            curve: Curve::Linear,
+        };
+        let values = (0..8).map(|frame| ramp.value_at(frame)).collect::<Vec<_>>();
+        assert_eq!(values, [1.0, 1.0, 1.0, 4.75, 8.5, 12.25, 16.0, 16.0]);
+
+        let ramp = ParamRamp {
+            curve: Curve::Exponential,
+            ..ramp
+        };
+        let values = (0..8).map(|frame| ramp.value_at(frame)).collect::<Vec<_>>();
+        assert_eq!(values, [1.0, 1.0, 1.0, 2.0, 4.0, 8.0, 16.0, 16.0]);
+
+        // Through zero, an exponential ramp is linear.
+        let ramp = ParamRamp {
+            start_value: 0.0,
+            end_value: 4.0,
+            ..ramp
+        };
+        assert_eq!(ramp.value_at(4), 2.0);
+    }
+
+    #[test]
+    fn skipped_ramps_carry_on_from_where_they_were() {
+        let ramp = ParamRamp {
+            id: 0,
+            start_frame: 2,
+            end_frame: 6,
+            start_value: 0.0,
+            end_value: 1.0,
+            curve: Curve::Linear,
+        };
+        let rest = ramp.skip(4).unwrap();
+        assert_eq!((rest.start_frame, rest.end_frame), (0, 2));
+        assert_eq!(rest.value_at(1), ramp.value_at(5));
+        assert_eq!(ramp.skip(1).unwrap().start_frame, 1);
+        assert_eq!(ramp.skip(1).unwrap().start_value, 0.0);
+        assert_eq!(ramp.skip(6).unwrap().value_at(0), 1.0);
+        assert_eq!(ramp.skip(7), None);
+    }
 }
@@ -0,0 +1,119 @@
+//! Visualization data from the audio thread.
+//!
+//! A processor embeds a [RingTap], pushes the samples it wants to show from `process`, and
+//! returns the tap's receiver from [super::Processor::analysis_tap]. The UI then obtains it from
+//! [crate::node::Node::analysis_tap] and drains it at its own pace. Neither side blocks: once the
+//! ring is full the oldest samples are overwritten.
+use crossbeam::queue::ArrayQueue;
+use std::sync::Arc;
+
+pub struct RingTap {
+    queue: Arc<ArrayQueue<f32>>,
+    decimation: usize,
+    phase: usize,
+}
+
+#[derive(Clone)]
+pub struct AnalysisReceiver {
+    queue: Arc<ArrayQueue<f32>>,
+}
+
+impl RingTap {
+    /// Create a tap holding up to `capacity` samples, keeping one in every `decimation` samples
+    /// pushed.
+    pub fn new(capacity: usize, decimation: usize) -> Self {
+        Self {
+            queue: Arc::new(ArrayQueue::new(capacity.max(1))),
+            decimation: decimation.max(1),
+            phase: 0,
+        }
+    }
+
+    pub fn receiver(&self) -> AnalysisReceiver {
+        AnalysisReceiver {
+            queue: self.queue.clone(),
+        }
+    }
+
+    /// Push a block of samples. Does not allocate or block.
+    pub fn push(&mut self, samples: &[f32]) {
+        for sample in samples {
+            if self.phase == 0 {
+                self.queue.force_push(*sample);
+            }
+            self.phase = (self.phase + 1) % self.decimation;
+        }
+    }
+
+    /// Forget the decimation phase, e.g. from [super::Processor::reset].
+    pub fn reset(&mut self) {
+        self.phase = 0;
+    }
+}
+
+impl AnalysisReceiver {
+    pub fn capacity(&self) -> usize {
+        self.queue.capacity()
+    }
+
+    /// The number of samples waiting to be read.
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Read the oldest waiting samples into `buffer`. Returns the number of samples read.
+    pub fn read(&self, buffer: &mut [f32]) -> usize {
+        let mut count = 0;
+        for slot in buffer {
+            let Some(sample) = self.queue.pop() else {
+                break;
+            };
+            *slot = sample;
+            count += 1;
+        }
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drain(receiver: &AnalysisReceiver) -> Vec<f32> {
+        let mut buffer = vec![0.0; receiver.capacity()];
+        let count = receiver.read(&mut buffer);
+        buffer.truncate(count);
+        buffer
+    }
+
+    #[test]
+    fn decimation_carries_across_blocks() {
+        let mut tap = RingTap::new(16, 3);
+        let receiver = tap.receiver();
+        let samples = (0..10).map(|n| n as f32).collect::<Vec<_>>();
+        tap.push(&samples[..4]);
+        tap.push(&samples[4..]);
+        assert_eq!(drain(&receiver), [0.0, 3.0, 6.0, 9.0]);
+        assert!(receiver.is_empty());
+
+        tap.reset();
+        tap.push(&[10.0, 11.0]);
+        assert_eq!(drain(&receiver), [10.0]);
+    }
+
+    #[test]
+    fn full_rings_keep_the_newest_samples() {
+        let mut tap = RingTap::new(4, 1);
+        let receiver = tap.receiver();
+        tap.push(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        assert_eq!(receiver.len(), 4);
+        let mut buffer = [0.0; 2];
+        assert_eq!(receiver.read(&mut buffer), 2);
+        assert_eq!(buffer, [3.0, 4.0]);
+        assert_eq!(drain(&receiver), [5.0, 6.0]);
+    }
+}
@@ -0,0 +1,40 @@
+//! Builtin processors.
+pub mod biquad;
+pub mod dynamics;
+pub mod gain;
+pub mod poly;
+pub mod subgraph;
+
+/// Run a processor on one block outside of a graph, for testing.
+#[cfg(test)]
+pub(crate) fn process_block(
+    processor: &mut impl super::Processor,
+    inputs: &[Vec<Vec<f32>>],
+    outputs: &mut [Vec<Vec<f32>>],
+    param_changes: &[super::ParamChange],
+) {
+    use crate::bus::{AudioBus, AudioBusMut};
+    let audio_inputs = inputs
+        .iter()
+        .map(|channels| AudioBus::from_channels(channels))
+        .collect::<Vec<_>>();
+    let mut audio_outputs = outputs
+        .iter_mut()
+        .map(|channels| AudioBusMut::from_channels(channels))
+        .collect::<Vec<_>>();
+    let num_frames = audio_outputs.first().map_or(0, AudioBusMut::num_frames);
+    let mut context = super::Context {
+        audio_inputs: &audio_inputs,
+        audio_outputs: &mut audio_outputs,
+        param_changes,
+        param_ramps: &[],
+        transport: None,
+        silent_outputs: 0,
+        num_frames_produced: num_frames,
+        param_notifications: None,
+        param_outputs: None,
+        cancel: None,
+        seed: 0,
+    };
+    processor.process(&mut context);
+}
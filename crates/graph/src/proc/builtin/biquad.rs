@@ -0,0 +1,428 @@
+//! A biquad filter with the standard responses from the RBJ audio EQ cookbook.
+//!
+//! Each channel of the first input bus is filtered into the matching channel of the first output
+//! bus, with independent state per channel. Output channels without a matching input are silenced.
+//!
+//! The frequency, Q and gain are parameters. A change glides to the new value over [SMOOTHING]
+//! frames from the frame of the change, exponentially for the frequency and Q and linearly for the
+//! gain, recomputing the coefficients every few frames so that the filter sweeps without zipper
+//! noise. The coefficients and state are kept in double precision, which keeps low cutoffs with a
+//! high Q from rounding onto the unit circle.
+use crate::proc::{
+    self,
+    params::{Flags, Parameter, ParameterTree},
+    Context,
+};
+use std::f64::consts::TAU;
+
+pub const MIN_Q: f64 = 0.01;
+pub const MAX_Q: f64 = 100.0;
+
+/// The id of the cutoff or center frequency parameter, in Hz.
+pub const FREQUENCY: usize = 0;
+/// The id of the quality factor parameter.
+pub const Q: usize = 1;
+/// The id of the gain parameter of peak and shelf responses, in decibels.
+pub const GAIN: usize = 2;
+
+/// The number of frames over which a parameter glides to a new value.
+pub const SMOOTHING: usize = 256;
+
+/// The number of frames between recomputing the coefficients while a parameter glides.
+const SMOOTHING_STEP: usize = 16;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Response {
+    Lowpass,
+    Highpass,
+    Bandpass,
+    Notch,
+    Peak,
+    LowShelf,
+    HighShelf,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Options {
+    pub response: Response,
+    /// The cutoff or center frequency, in Hz.
+    pub frequency: f64,
+    /// The quality factor, clamped to `[MIN_Q, MAX_Q]` to keep the filter well conditioned.
+    pub q: f64,
+    /// The gain of peak and shelf responses, in decibels. Ignored by the other responses.
+    pub gain_db: f64,
+}
+
+pub struct Processor {
+    /// The parameters as last set.
+    options: Options,
+    /// The parameters the coefficients were computed from, which glide towards `options`.
+    current: Options,
+    sample_rate: f64,
+    coefficients: Coefficients,
+    /// The number of frames left in the glide.
+    ramp: usize,
+    state: Vec<[f64; 4]>,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+struct Coefficients {
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a1: f64,
+    a2: f64,
+}
+
+impl Processor {
+    pub fn new(num_channels: usize, options: Options) -> Self {
+        Self {
+            options,
+            current: options,
+            sample_rate: 48e3,
+            coefficients: Coefficients::new(&options, 48e3),
+            ramp: 0,
+            state: vec![[0.0; 4]; num_channels],
+        }
+    }
+
+    fn set_param(&mut self, id: usize, value: f32) {
+        let value = f64::from(value);
+        match id {
+            FREQUENCY => self.options.frequency = value,
+            Q => self.options.q = value,
+            GAIN => self.options.gain_db = value,
+            _ => return,
+        }
+        self.ramp = SMOOTHING;
+    }
+
+    /// Move the current parameters `num_frames` further along the glide and recompute the
+    /// coefficients.
+    fn glide(&mut self, num_frames: usize) {
+        let num_frames = num_frames.min(self.ramp);
+        let t = num_frames as f64 / self.ramp as f64;
+        let (current, target) = (&mut self.current, &self.options);
+        // Glide exponentially between positive values, and jump to anything else.
+        let exp = |from: f64, to: f64| {
+            if from > 0.0 && to > 0.0 {
+                from * (to / from).powf(t)
+            } else {
+                to
+            }
+        };
+        current.frequency = exp(current.frequency, target.frequency);
+        current.q = exp(current.q, target.q);
+        current.gain_db += (target.gain_db - current.gain_db) * t;
+        self.ramp -= num_frames;
+        if self.ramp == 0 {
+            *current = *target;
+        }
+        self.coefficients = Coefficients::new(current, self.sample_rate);
+    }
+
+    /// Filter frames `start..end` of every channel.
+    fn filter(&mut self, context: &mut Context<'_>, mut start: usize, end: usize) {
+        while start < end {
+            let stop = if self.ramp > 0 {
+                self.glide(SMOOTHING_STEP);
+                end.min(start + SMOOTHING_STEP)
+            } else {
+                end
+            };
+            let Coefficients { b0, b1, b2, a1, a2 } = self.coefficients;
+            for ((input, output), state) in context.zip_channels().zip(&mut self.state) {
+                let [mut x1, mut x2, mut y1, mut y2] = *state;
+                for (x, y) in input[start..stop].iter().zip(&mut output[start..stop]) {
+                    // Direct form I, whose state doesn't depend on the coefficients, so it
+                    // doesn't ring when they change.
+                    let x = f64::from(*x);
+                    let out = b0 * x + b1 * x1 + b2 * x2 - a1 * y1 - a2 * y2;
+                    (x1, x2, y1, y2) = (x, x1, out, y1);
+                    *y = out as f32;
+                }
+                *state = [x1, x2, y1, y2];
+            }
+            start = stop;
+        }
+    }
+}
+
+impl proc::Processor for Processor {
+    fn initialize(&mut self, sample_rate: f64, _max_num_frames: usize) {
+        self.sample_rate = sample_rate;
+        self.current = self.options;
+        self.coefficients = Coefficients::new(&self.options, sample_rate);
+        self.ramp = 0;
+        self.reset();
+    }
+
+    fn process(&mut self, context: &mut Context<'_>) {
+        let Some(output) = context.audio_outputs.first_mut() else {
+            return;
+        };
+        let Some(input) = context.audio_inputs.first() else {
+            output.clear();
+            return;
+        };
+        let num_channels = self.state.len().min(input.num_channels());
+
+        // Split the block at every parameter change.
+        let num_frames = output.num_frames();
+        let mut start = 0;
+        let changes = context.param_changes;
+        for change in changes {
+            let end = change.time.min(num_frames);
+            if end > start {
+                self.filter(context, start, end);
+                start = end;
+            }
+            self.set_param(change.id, change.value);
+        }
+        self.filter(context, start, num_frames);
+
+        // Silence the output channels that weren't filtered.
+        let output = &mut context.audio_outputs[0];
+        for channel in num_channels..output.num_channels() {
+            output[channel].fill(0.0);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.state.fill([0.0; 4]);
+    }
+
+    fn parameters(&self) -> Option<ParameterTree> {
+        let Options {
+            frequency,
+            q,
+            gain_db,
+            ..
+        } = self.options;
+        let frequency = Parameter::new(FREQUENCY, "Frequency", 20.0, 20e3, frequency as f32)
+            .unit("Hz")
+            .flags(Flags::LOGARITHMIC | Flags::AUTOMATABLE);
+        let q = Parameter::new(Q, "Q", MIN_Q as f32, MAX_Q as f32, q as f32)
+            .flags(Flags::LOGARITHMIC | Flags::AUTOMATABLE);
+        let gain = Parameter::new(GAIN, "Gain", -24.0, 24.0, gain_db as f32)
+            .unit("dB")
+            .flags(Flags::AUTOMATABLE);
+        let tree = ParameterTree::branch("Biquad")
+            .with(ParameterTree::leaf(frequency))
+            .with(ParameterTree::leaf(q))
+            .with(ParameterTree::leaf(gain));
+        Some(tree)
+    }
+}
+
+impl Coefficients {
+    fn new(options: &Options, sample_rate: f64) -> Self {
+        // Clamp to the upper bound last, which wins at sample rates below 2 Hz.
+        let frequency = options.frequency.max(1.0).min(0.49 * sample_rate);
+        let q = options.q.clamp(MIN_Q, MAX_Q);
+        let w0 = TAU * frequency / sample_rate;
+        let (sin, cos) = w0.sin_cos();
+        let alpha = sin / (2.0 * q);
+        let a = 10f64.powf(options.gain_db / 40.0);
+
+        let (b0, b1, b2, a0, a1, a2) = match options.response {
+            Response::Lowpass => (
+                (1.0 - cos) / 2.0,
+                1.0 - cos,
+                (1.0 - cos) / 2.0,
+                1.0 + alpha,
+                -2.0 * cos,
+                1.0 - alpha,
+            ),
+            Response::Highpass => (
+                (1.0 + cos) / 2.0,
+                -(1.0 + cos),
+                (1.0 + cos) / 2.0,
+                1.0 + alpha,
+                -2.0 * cos,
+                1.0 - alpha,
+            ),
+            Response::Bandpass => (alpha, 0.0, -alpha, 1.0 + alpha, -2.0 * cos, 1.0 - alpha),
+            Response::Notch => (1.0, -2.0 * cos, 1.0, 1.0 + alpha, -2.0 * cos, 1.0 - alpha),
+            Response::Peak => (
+                1.0 + alpha * a,
+                -2.0 * cos,
+                1.0 - alpha * a,
+                1.0 + alpha / a,
+                -2.0 * cos,
+                1.0 - alpha / a,
+            ),
+            Response::LowShelf => {
+                let beta = 2.0 * a.sqrt() * alpha;
+                (
+                    a * ((a + 1.0) - (a - 1.0) * cos + beta),
+                    2.0 * a * ((a - 1.0) - (a + 1.0) * cos),
+                    a * ((a + 1.0) - (a - 1.0) * cos - beta),
+                    (a + 1.0) + (a - 1.0) * cos + beta,
+                    -2.0 * ((a - 1.0) + (a + 1.0) * cos),
+                    (a + 1.0) + (a - 1.0) * cos - beta,
+                )
+            }
+            Response::HighShelf => {
+                let beta = 2.0 * a.sqrt() * alpha;
+                (
+                    a * ((a + 1.0) + (a - 1.0) * cos + beta),
+                    -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+                    a * ((a + 1.0) + (a - 1.0) * cos - beta),
+                    (a + 1.0) - (a - 1.0) * cos + beta,
+                    2.0 * ((a - 1.0) - (a + 1.0) * cos),
+                    (a + 1.0) - (a - 1.0) * cos - beta,
+                )
+            }
+        };
+
+        Self {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proc::{builtin::process_block, ParamChange, Processor as _};
+
+    const SAMPLE_RATE: f64 = 48e3;
+
+    fn sine(frequency: f64, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|n| (TAU * frequency * n as f64 / SAMPLE_RATE).sin() as f32)
+            .collect()
+    }
+
+    fn peak(signal: &[f32]) -> f32 {
+        signal
+            .iter()
+            .fold(0.0, |peak, sample| peak.max(sample.abs()))
+    }
+
+    fn lowpass(frequency: f64, q: f64) -> Processor {
+        let options = Options {
+            response: Response::Lowpass,
+            frequency,
+            q,
+            gain_db: 0.0,
+        };
+        let mut processor = Processor::new(1, options);
+        processor.initialize(SAMPLE_RATE, 4800);
+        processor
+    }
+
+    /// Filter `input` in one block, returning the second half to skip the transient.
+    fn filter(processor: &mut Processor, input: Vec<f32>, changes: &[ParamChange]) -> Vec<f32> {
+        let len = input.len();
+        let mut outputs = vec![vec![vec![0.0; len]]];
+        process_block(processor, &[vec![input]], &mut outputs, changes);
+        outputs[0][0].split_off(len / 2)
+    }
+
+    #[test]
+    fn lowpass_attenuates_high_frequencies() {
+        let low = filter(&mut lowpass(1e3, 0.707), sine(100.0, 4800), &[]);
+        let high = filter(&mut lowpass(1e3, 0.707), sine(10e3, 4800), &[]);
+        assert!(peak(&low) > 0.9, "{}", peak(&low));
+        assert!(peak(&high) < 0.02, "{}", peak(&high));
+    }
+
+    #[test]
+    fn stable_at_extreme_q() {
+        let responses = [
+            Response::Lowpass,
+            Response::Highpass,
+            Response::Bandpass,
+            Response::Notch,
+            Response::Peak,
+            Response::LowShelf,
+            Response::HighShelf,
+        ];
+        for response in responses {
+            for q in [0.0, MIN_Q, MAX_Q, 1e9] {
+                for frequency in [0.0, 20.0, 1e3, 20e3, 1e6] {
+                    let options = Options {
+                        response,
+                        frequency,
+                        q,
+                        gain_db: 24.0,
+                    };
+                    // The poles are inside the unit circle.
+                    let Coefficients { a1, a2, .. } = Coefficients::new(&options, SAMPLE_RATE);
+                    assert!(a2.abs() < 1.0 && a1.abs() < 1.0 + a2, "{options:?}");
+
+                    let mut processor = Processor::new(1, options);
+                    processor.initialize(SAMPLE_RATE, 4800);
+                    let output = filter(&mut processor, sine(frequency, 4800), &[]);
+                    assert!(peak(&output) < 1e3, "{options:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn tiny_sample_rates_dont_panic() {
+        let mut processor = lowpass(1e3, 0.707);
+        processor.initialize(1.0, 16);
+        processor.initialize(1e-3, 16);
+    }
+
+    #[test]
+    fn frequency_changes_take_effect() {
+        let mut processor = lowpass(20e3, 0.707);
+        let change = ParamChange {
+            id: FREQUENCY,
+            time: 0,
+            value: 1e3,
+        };
+        let output = filter(&mut processor, sine(10e3, 4800), &[change]);
+        assert!(peak(&output) < 0.02, "{}", peak(&output));
+        assert!(processor.parameters().unwrap().find(FREQUENCY).is_some());
+    }
+
+    #[test]
+    fn changes_glide() {
+        let mut processor = lowpass(1e3, 0.707);
+        filter(&mut processor, vec![1.0; 4800], &[]);
+        let change = ParamChange {
+            id: FREQUENCY,
+            time: 100,
+            value: 100.0,
+        };
+        let len = 100 + SMOOTHING / 2;
+        let mut outputs = vec![vec![vec![0.0; len]]];
+        process_block(
+            &mut processor,
+            &[vec![vec![1.0; len]]],
+            &mut outputs,
+            &[change],
+        );
+        assert!((processor.current.frequency - 316.23).abs() < 0.01);
+        let output = filter(&mut processor, vec![1.0; SMOOTHING], &[]);
+        assert_eq!(processor.current.frequency, 100.0);
+
+        // A lowpass fed DC stays at DC while its cutoff moves.
+        for sample in outputs[0][0].iter().chain(&output) {
+            assert!((sample - 1.0).abs() < 1e-6, "{sample}");
+        }
+    }
+
+    #[test]
+    fn extra_output_channels_are_cleared() {
+        let mut processor = lowpass(1e3, 0.707);
+        let input = vec![vec![1.0; 16]; 2];
+        let mut outputs = vec![vec![vec![f32::NAN; 16]; 3]];
+        process_block(&mut processor, &[input], &mut outputs, &[]);
+        assert!(outputs[0][0].iter().all(|sample| sample.is_finite()));
+        assert!(outputs[0][1..]
+            .iter()
+            .flatten()
+            .all(|sample| *sample == 0.0));
+    }
+}
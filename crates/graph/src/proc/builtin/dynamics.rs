@@ -0,0 +1,359 @@
+//! An envelope follower driving a gate or a downward expander.
+//!
+//! The first input bus is processed into the first output bus. The detector is keyed off the
+//! first input bus, or off the second input bus when the processor is configured with a
+//! sidechain. Detection is linked across channels, so every channel receives the same gain.
+//! Output channels without a matching input are silenced.
+//!
+//! The threshold, ratio, attack and release are parameters, applied from the frame of the change.
+//! The gain reduction is reported through the analysis tap, see [crate::proc::analysis], as the
+//! largest reduction in each block in decibels, a positive number.
+use crate::{
+    bus::AudioBus,
+    proc::{
+        self,
+        analysis::{AnalysisReceiver, RingTap},
+        params::{Flags, Parameter, ParameterTree},
+        Context,
+    },
+};
+
+/// The window of the RMS detector, in seconds.
+const RMS_WINDOW: f64 = 0.01;
+
+/// The number of blocks of gain reduction the analysis tap holds.
+const TAP_CAPACITY: usize = 256;
+
+/// The gain reduction reported while the gain is zero, in decibels.
+const MAX_REDUCTION_DB: f32 = 120.0;
+
+/// The id of the threshold parameter, in decibels.
+pub const THRESHOLD: usize = 0;
+/// The id of the expansion ratio parameter.
+pub const RATIO: usize = 1;
+/// The id of the attack time parameter, in seconds.
+pub const ATTACK: usize = 2;
+/// The id of the release time parameter, in seconds.
+pub const RELEASE: usize = 3;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Detector {
+    Peak,
+    Rms,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// Silence the signal while the detector is below the threshold.
+    Gate,
+    /// Attenuate the signal below the threshold, by `ratio` decibels per decibel.
+    Expander,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Options {
+    pub detector: Detector,
+    pub mode: Mode,
+    pub threshold_db: f64,
+    /// The expansion ratio. Ignored by the gate.
+    pub ratio: f64,
+    /// The time for the gain to rise towards its target, in seconds.
+    pub attack: f64,
+    /// The time for the gain to fall towards its target, in seconds.
+    pub release: f64,
+    /// Key the detector off the second input bus.
+    pub sidechain: bool,
+}
+
+pub struct Processor {
+    options: Options,
+    sample_rate: f64,
+    tap: RingTap,
+    threshold: f32,
+    attack: f32,
+    release: f32,
+    rms: f32,
+    power: f32,
+    gain: f32,
+}
+
+impl Processor {
+    pub fn new(options: Options) -> Self {
+        let mut processor = Self {
+            options,
+            sample_rate: 48e3,
+            tap: RingTap::new(TAP_CAPACITY, 1),
+            threshold: db_to_gain(options.threshold_db as f32),
+            attack: 0.0,
+            release: 0.0,
+            rms: 0.0,
+            power: 0.0,
+            gain: 0.0,
+        };
+        processor.initialize_coefficients(48e3);
+        processor
+    }
+
+    fn initialize_coefficients(&mut self, sample_rate: f64) {
+        self.sample_rate = sample_rate;
+        self.attack = coefficient(self.options.attack, sample_rate);
+        self.release = coefficient(self.options.release, sample_rate);
+        self.rms = coefficient(RMS_WINDOW, sample_rate);
+    }
+
+    fn set_param(&mut self, id: usize, value: f32) {
+        let value = f64::from(value);
+        match id {
+            THRESHOLD => {
+                self.options.threshold_db = value;
+                self.threshold = db_to_gain(value as f32);
+            }
+            RATIO => self.options.ratio = value,
+            ATTACK => {
+                self.options.attack = value;
+                self.attack = coefficient(value, self.sample_rate);
+            }
+            RELEASE => {
+                self.options.release = value;
+                self.release = coefficient(value, self.sample_rate);
+            }
+            _ => (),
+        }
+    }
+
+    fn detect(&mut self, key: &AudioBus, frame: usize) -> f32 {
+        match self.options.detector {
+            Detector::Peak => key
+                .iter()
+                .map(|channel| channel[frame].abs())
+                .fold(0.0, f32::max),
+            Detector::Rms => {
+                let power = key
+                    .iter()
+                    .map(|channel| channel[frame] * channel[frame])
+                    .fold(0.0, f32::max);
+                self.power = self.rms * self.power + (1.0 - self.rms) * power;
+                self.power.sqrt()
+            }
+        }
+    }
+
+    fn target(&self, level: f32) -> f32 {
+        if level >= self.threshold {
+            return 1.0;
+        }
+        match self.options.mode {
+            Mode::Gate => 0.0,
+            Mode::Expander => {
+                let under_db = gain_to_db(level) - gain_to_db(self.threshold);
+                db_to_gain(under_db * (self.options.ratio as f32 - 1.0))
+            }
+        }
+    }
+}
+
+impl proc::Processor for Processor {
+    fn initialize(&mut self, sample_rate: f64, _max_num_frames: usize) {
+        self.initialize_coefficients(sample_rate);
+        self.reset();
+    }
+
+    fn process(&mut self, context: &mut Context<'_>) {
+        let Some(output) = context.audio_outputs.first_mut() else {
+            return;
+        };
+        let Some(input) = context.audio_inputs.first() else {
+            output.clear();
+            return;
+        };
+        let key = if self.options.sidechain {
+            context.audio_inputs.get(1).unwrap_or(input)
+        } else {
+            input
+        };
+
+        let num_channels = input.num_channels().min(output.num_channels());
+        let mut changes = context.param_changes.iter().peekable();
+        let mut min_gain = f32::INFINITY;
+        for frame in 0..output.num_frames() {
+            while let Some(change) = changes.next_if(|change| change.time <= frame) {
+                self.set_param(change.id, change.value);
+            }
+            let level = self.detect(key, frame);
+            let target = self.target(level);
+            let coefficient = if target > self.gain {
+                self.attack
+            } else {
+                self.release
+            };
+            self.gain = coefficient * self.gain + (1.0 - coefficient) * target;
+            min_gain = min_gain.min(self.gain);
+            for channel in 0..num_channels {
+                output[channel][frame] = input[channel][frame] * self.gain;
+            }
+        }
+        for change in changes {
+            self.set_param(change.id, change.value);
+        }
+        for channel in num_channels..output.num_channels() {
+            output[channel].fill(0.0);
+        }
+        if min_gain.is_finite() {
+            let reduction = (-gain_to_db(min_gain)).clamp(0.0, MAX_REDUCTION_DB);
+            self.tap.push(&[reduction]);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.power = 0.0;
+        self.gain = 0.0;
+        self.tap.reset();
+    }
+
+    fn analysis_tap(&self) -> Option<AnalysisReceiver> {
+        Some(self.tap.receiver())
+    }
+
+    fn parameters(&self) -> Option<ParameterTree> {
+        let Options {
+            threshold_db,
+            ratio,
+            attack,
+            release,
+            ..
+        } = self.options;
+        let threshold = Parameter::new(THRESHOLD, "Threshold", -80.0, 0.0, threshold_db as f32)
+            .unit("dB")
+            .flags(Flags::AUTOMATABLE);
+        let ratio = Parameter::new(RATIO, "Ratio", 1.0, 20.0, ratio as f32)
+            .flags(Flags::LOGARITHMIC | Flags::AUTOMATABLE);
+        let attack = Parameter::new(ATTACK, "Attack", 0.0, 1.0, attack as f32)
+            .unit("s")
+            .flags(Flags::AUTOMATABLE);
+        let release = Parameter::new(RELEASE, "Release", 0.0, 5.0, release as f32)
+            .unit("s")
+            .flags(Flags::AUTOMATABLE);
+        let tree = ParameterTree::branch("Dynamics")
+            .with(ParameterTree::leaf(threshold))
+            .with(ParameterTree::leaf(ratio))
+            .with(ParameterTree::leaf(attack))
+            .with(ParameterTree::leaf(release));
+        Some(tree)
+    }
+}
+
+fn coefficient(time: f64, sample_rate: f64) -> f32 {
+    if time <= 0.0 {
+        return 0.0;
+    }
+    (-1.0 / (time * sample_rate)).exp() as f32
+}
+
+fn db_to_gain(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+fn gain_to_db(gain: f32) -> f32 {
+    20.0 * gain.max(1e-9).log10()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proc::{builtin::process_block, ParamChange, Processor as _};
+
+    const SAMPLE_RATE: f64 = 48e3;
+
+    fn gate() -> Processor {
+        let mut processor = Processor::new(Options {
+            detector: Detector::Peak,
+            mode: Mode::Gate,
+            threshold_db: -40.0,
+            ratio: 1.0,
+            attack: 0.01,
+            release: 0.05,
+            sidechain: false,
+        });
+        processor.initialize(SAMPLE_RATE, 48000);
+        processor
+    }
+
+    /// Process a block of DC at `level`, returning the gain of every frame.
+    fn gains(
+        processor: &mut Processor,
+        level: f32,
+        len: usize,
+        changes: &[ParamChange],
+    ) -> Vec<f32> {
+        let mut outputs = vec![vec![vec![0.0; len]]];
+        process_block(processor, &[vec![vec![level; len]]], &mut outputs, changes);
+        outputs[0][0].iter().map(|sample| sample / level).collect()
+    }
+
+    fn frames(seconds: f64) -> usize {
+        (seconds * SAMPLE_RATE) as usize
+    }
+
+    #[test]
+    fn gate_stays_closed_below_threshold() {
+        let mut gate = gate();
+        let gains = gains(&mut gate, 0.001, frames(0.5), &[]);
+        assert!(gains.iter().all(|gain| *gain < 1e-6));
+    }
+
+    #[test]
+    fn gate_opens_and_closes_in_time() {
+        let mut gate = gate();
+
+        // The gain rises by 1 - 1/e over the attack time.
+        let opening = gains(&mut gate, 0.5, frames(0.2), &[]);
+        let attack = opening[frames(0.01)];
+        assert!((0.55..0.7).contains(&attack), "{attack}");
+        assert!(opening.last().unwrap() > &0.999);
+
+        // And falls to 1/e over the release time.
+        let closing = gains(&mut gate, 0.001, frames(0.5), &[]);
+        let release = closing[frames(0.05)];
+        assert!((0.3..0.45).contains(&release), "{release}");
+        assert!(closing.last().unwrap() < &1e-3);
+    }
+
+    #[test]
+    fn gain_reduction_is_reported() {
+        let mut gate = gate();
+        let receiver = gate.analysis_tap().unwrap();
+        gains(&mut gate, 0.5, frames(0.2), &[]);
+        gains(&mut gate, 0.5, 64, &[]);
+        gains(&mut gate, 0.001, frames(0.5), &[]);
+        let mut reductions = [0.0; 4];
+        assert_eq!(receiver.read(&mut reductions), 3);
+        // The gate starts closed.
+        assert!(reductions[0] > 40.0, "{}", reductions[0]);
+        assert!(reductions[1] < 0.01, "{}", reductions[1]);
+        assert!(reductions[2] > 60.0, "{}", reductions[2]);
+    }
+
+    #[test]
+    fn threshold_changes_take_effect() {
+        let mut gate = gate();
+        gains(&mut gate, 0.5, frames(0.2), &[]);
+        let change = ParamChange {
+            id: THRESHOLD,
+            time: 100,
+            value: 0.0,
+        };
+        let gains = gains(&mut gate, 0.5, frames(0.5), &[change]);
+        assert!(gains[99] > 0.999);
+        assert!(gains.last().unwrap() < &1e-3);
+        assert!(gate.parameters().unwrap().find(RELEASE).is_some());
+    }
+
+    #[test]
+    fn extra_output_channels_are_cleared() {
+        let mut gate = gate();
+        let mut outputs = vec![vec![vec![f32::NAN; 16]; 2]];
+        process_block(&mut gate, &[vec![vec![0.5; 16]]], &mut outputs, &[]);
+        assert!(outputs[0][0].iter().all(|sample| sample.is_finite()));
+        assert!(outputs[0][1].iter().all(|sample| *sample == 0.0));
+    }
+}
@@ -0,0 +1,179 @@
+//! A gain with sample-accurate automation.
+//!
+//! Each channel of the first input bus is scaled into the matching channel of the first output bus.
+//! A change to [GAIN] ramps linearly from the gain at the previous change, or at the start of the
+//! block, so that it reaches the new gain on the frame of the change. A ramp of [GAIN] sets the
+//! gain of every frame from its start, taking over from the changes.
+use crate::{
+    bus::kernels,
+    proc::{
+        self,
+        params::{Flags, Parameter, ParameterTree, Preset},
+        Context,
+    },
+};
+
+/// The id of the linear gain parameter.
+pub const GAIN: usize = 0;
+
+pub struct Processor {
+    gain: f32,
+    /// The gain it was created with, which hosts reset to.
+    default: f32,
+}
+
+impl Processor {
+    pub fn new(gain: f32) -> Self {
+        Self {
+            gain,
+            default: gain,
+        }
+    }
+}
+
+impl proc::Processor for Processor {
+    fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {}
+
+    fn process(&mut self, context: &mut Context<'_>) {
+        if context.audio_outputs.is_empty() {
+            return;
+        }
+        if context.audio_inputs.is_empty() {
+            context.output(0).clear();
+            return;
+        }
+        let changes = context.param_changes;
+        let mut changes = changes.iter().filter(|change| change.id == GAIN);
+        let ramps = context.param_ramps;
+        let num_frames = context.num_frames();
+        let mut ramps = ramps
+            .iter()
+            .filter(|ramp| ramp.id == GAIN && ramp.start_frame < num_frames);
+
+        // Silence in is silence out. Every ramp ends on its change, so the gain still lands on
+        // the last one, or where the last ramp is by the end of the block.
+        if context.input(0).is_silent() {
+            if let Some(change) = changes.next_back() {
+                self.gain = change.value;
+            }
+            if let Some(ramp) = ramps.next_back() {
+                self.gain = ramp.value_at(num_frames - 1);
+            }
+            context.silent_outputs |= 1;
+            return;
+        }
+
+        // Ramp the gain from each change to the next, then hold it for the rest of the block.
+        for (input, output) in context.zip_channels() {
+            output.copy_from_slice(input);
+        }
+        let mut frame = 0;
+        for change in changes {
+            let end = (change.time + 1).min(num_frames);
+            if end > frame {
+                // Shifted by a step, so that the ramp reaches the new gain on the last frame.
+                let step = (change.value - self.gain) / (end - frame) as f32;
+                for (_, output) in context.zip_channels() {
+                    let (from, to) = (self.gain + step, change.value + step);
+                    kernels::scale_ramp(&mut output[frame..end], from, to);
+                }
+                frame = end;
+            }
+            // Land on the new gain exactly, even if an earlier change took the same frame.
+            self.gain = change.value;
+            for (input, output) in context.zip_channels() {
+                output[frame - 1] = input[frame - 1] * self.gain;
+            }
+        }
+        for (_, output) in context.zip_channels() {
+            kernels::scale(&mut output[frame..], self.gain);
+        }
+
+        // Follow each ramp that starts in the block from its start frame.
+        for ramp in ramps {
+            let start = ramp.start_frame;
+            for (input, output) in context.zip_channels() {
+                let frames = output[start..].iter_mut().zip(&input[start..]);
+                for (frame, (output, input)) in frames.enumerate() {
+                    *output = input * ramp.value_at(start + frame);
+                }
+            }
+            self.gain = ramp.value_at(num_frames - 1);
+        }
+    }
+
+    fn reset(&mut self) {}
+
+    fn parameters(&self) -> Option<ParameterTree> {
+        let gain = Parameter::new(GAIN, "Gain", 0.0, 2.0, self.default).flags(Flags::AUTOMATABLE);
+        Some(ParameterTree::leaf(gain))
+    }
+
+    fn presets(&self) -> Vec<Preset> {
+        vec![
+            Preset::new("Unity").with(GAIN, 1.0),
+            Preset::new("-6 dB").with(GAIN, 0.5),
+            Preset::new("Mute").with(GAIN, 0.0),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proc::{builtin::process_block, ParamChange, Processor as _};
+
+    /// Process a block of ones, returning the gain of every frame.
+    fn gains(processor: &mut Processor, len: usize, changes: &[ParamChange]) -> Vec<f32> {
+        let mut outputs = vec![vec![vec![0.0; len]]];
+        process_block(processor, &[vec![vec![1.0; len]]], &mut outputs, changes);
+        outputs.remove(0).remove(0)
+    }
+
+    fn change(time: usize, value: f32) -> ParamChange {
+        ParamChange {
+            id: GAIN,
+            time,
+            value,
+        }
+    }
+
+    #[test]
+    fn changes_ramp_to_their_frame() {
+        let mut gain = Processor::new(1.0);
+        gain.initialize(48e3, 8);
+        assert_eq!(
+            gains(&mut gain, 8, &[change(3, 0.0)]),
+            [0.75, 0.5, 0.25, 0.0, 0.0, 0.0, 0.0, 0.0]
+        );
+
+        // The next ramp starts where the last one ended, and the gain holds into the next block.
+        assert_eq!(
+            gains(&mut gain, 8, &[change(1, 1.0), change(5, 0.0)]),
+            [0.5, 1.0, 0.75, 0.5, 0.25, 0.0, 0.0, 0.0]
+        );
+        assert_eq!(gains(&mut gain, 4, &[]), [0.0; 4]);
+    }
+
+    #[test]
+    fn changes_past_the_block_land_on_its_last_frame() {
+        let mut gain = Processor::new(0.0);
+        gain.initialize(48e3, 4);
+        assert_eq!(
+            gains(&mut gain, 4, &[change(100, 1.0)]),
+            [0.25, 0.5, 0.75, 1.0]
+        );
+    }
+
+    #[test]
+    fn other_params_are_ignored() {
+        let mut gain = Processor::new(0.5);
+        gain.initialize(48e3, 4);
+        let other = ParamChange {
+            id: GAIN + 1,
+            time: 0,
+            value: 2.0,
+        };
+        assert_eq!(gains(&mut gain, 4, &[other]), [0.5; 4]);
+    }
+}
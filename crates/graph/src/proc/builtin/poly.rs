@@ -0,0 +1,451 @@
+//! Polyphonic voice allocation.
+//!
+//! A [Processor] owns a fixed set of [Voice]s and plays the notes sent through its [NoteSender] on
+//! them, summing the voices into the first output bus. Notes land on the frame they are sent for.
+//! Free voices are used round-robin, and once every voice is busy a new note either steals the
+//! voice that was started the longest ago or is dropped, as set by the [Steal] policy. The number
+//! of voices in use and the policy are parameters, see [NUM_VOICES] and [STEAL].
+use crate::{
+    bus::{kernels, AudioBusMut},
+    proc::{
+        self,
+        params::{Flags, Parameter, ParameterTree},
+        Context,
+    },
+};
+use crossbeam::queue::ArrayQueue;
+use std::sync::Arc;
+
+/// The id of the parameter for the number of voices in use, from one up to the number the
+/// processor was created with. Voices beyond it finish the notes they are playing.
+pub const NUM_VOICES: usize = 0;
+
+/// The id of the steal policy parameter, `0.0` for [Steal::Oldest] and `1.0` for [Steal::None].
+pub const STEAL: usize = 1;
+
+/// The number of notes that can be pending for the next block.
+pub const MAX_NUM_NOTES: usize = 256;
+
+pub trait Voice {
+    fn initialize(&mut self, sample_rate: f64, max_num_frames: usize);
+    fn note_on(&mut self, note: u8, velocity: f32);
+    fn note_off(&mut self);
+
+    /// Write the next `num_frames` of the voice to the start of each channel of `output`.
+    fn render(&mut self, output: &mut [Vec<f32>], num_frames: usize);
+
+    /// Whether the voice is sounding, including any release after [Voice::note_off]. Inactive
+    /// voices aren't rendered, and are free to play the next note.
+    fn is_active(&self) -> bool;
+
+    fn reset(&mut self);
+}
+
+/// What to do with a note when every voice is busy.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Steal {
+    /// Take the voice that was started the longest ago.
+    Oldest,
+    /// Drop the note.
+    None,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct NoteEvent {
+    /// The frame of the next block at which the event happens, clamped to its last frame.
+    pub time: usize,
+    pub note: u8,
+    pub kind: NoteKind,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum NoteKind {
+    On { velocity: f32 },
+    Off,
+}
+
+#[derive(Clone)]
+pub struct NoteSender {
+    queue: Arc<ArrayQueue<NoteEvent>>,
+}
+
+pub struct Processor<V> {
+    slots: Vec<Slot<V>>,
+    num_voices: usize,
+    steal: Steal,
+    /// The policy it was created with, which hosts reset to.
+    default_steal: Steal,
+    next: usize,
+    num_notes: u64,
+    queue: Arc<ArrayQueue<NoteEvent>>,
+    events: Vec<NoteEvent>,
+    scratch: Vec<Vec<f32>>,
+}
+
+struct Slot<V> {
+    voice: V,
+    note: Option<u8>,
+    started: u64,
+}
+
+impl<V: Voice> Processor<V> {
+    /// Create a processor that plays `voices`, each writing `num_channels` channels.
+    pub fn new(voices: Vec<V>, num_channels: usize, steal: Steal) -> Self {
+        let slots = voices
+            .into_iter()
+            .map(|voice| Slot {
+                voice,
+                note: None,
+                started: 0,
+            })
+            .collect::<Vec<_>>();
+        Self {
+            num_voices: slots.len(),
+            slots,
+            steal,
+            default_steal: steal,
+            next: 0,
+            num_notes: 0,
+            queue: Arc::new(ArrayQueue::new(MAX_NUM_NOTES)),
+            events: Vec::with_capacity(MAX_NUM_NOTES),
+            scratch: vec![vec![]; num_channels],
+        }
+    }
+
+    pub fn sender(&self) -> NoteSender {
+        NoteSender {
+            queue: self.queue.clone(),
+        }
+    }
+
+    // Render the active voices into frames `start..end` of `output`.
+    fn render(&mut self, output: &mut AudioBusMut, start: usize, end: usize) {
+        if end <= start {
+            return;
+        }
+        for slot in self.slots.iter_mut().filter(|slot| slot.voice.is_active()) {
+            slot.voice.render(&mut self.scratch, end - start);
+            for (channel, scratch) in self.scratch.iter().enumerate() {
+                if channel < output.num_channels() {
+                    kernels::add_assign(&mut output[channel][start..end], &scratch[..end - start]);
+                }
+            }
+        }
+    }
+
+    fn dispatch(&mut self, event: NoteEvent) {
+        match event.kind {
+            NoteKind::On { velocity } => {
+                let Some(index) = self.allocate() else {
+                    return;
+                };
+                self.next = (index + 1) % self.num_voices;
+                self.num_notes += 1;
+                let slot = &mut self.slots[index];
+                slot.note = Some(event.note);
+                slot.started = self.num_notes;
+                slot.voice.note_on(event.note, velocity);
+            }
+            NoteKind::Off => {
+                for slot in &mut self.slots {
+                    if slot.note == Some(event.note) {
+                        slot.note = None;
+                        slot.voice.note_off();
+                    }
+                }
+            }
+        }
+    }
+
+    // The voice to play the next note on, if any.
+    fn allocate(&self) -> Option<usize> {
+        let num_voices = self.num_voices;
+        let free = (0..num_voices)
+            .map(|offset| (self.next + offset) % num_voices)
+            .find(|index| !self.slots[*index].voice.is_active());
+        free.or_else(|| match self.steal {
+            Steal::Oldest => (0..num_voices).min_by_key(|index| self.slots[*index].started),
+            Steal::None => None,
+        })
+    }
+}
+
+impl<V: Voice> proc::Processor for Processor<V> {
+    fn initialize(&mut self, sample_rate: f64, max_num_frames: usize) {
+        for slot in &mut self.slots {
+            slot.voice.initialize(sample_rate, max_num_frames);
+        }
+        for channel in &mut self.scratch {
+            channel.resize(max_num_frames, 0.0);
+        }
+    }
+
+    fn process(&mut self, context: &mut Context<'_>) {
+        let Some(output) = context.audio_outputs.first_mut() else {
+            return;
+        };
+        let num_frames = output.num_frames();
+
+        for change in context.param_changes {
+            match change.id {
+                NUM_VOICES => {
+                    let num_voices = change.value.max(1.0) as usize;
+                    self.num_voices = num_voices.min(self.slots.len());
+                    self.next %= self.num_voices.max(1);
+                }
+                STEAL if change.value < 0.5 => self.steal = Steal::Oldest,
+                STEAL => self.steal = Steal::None,
+                _ => (),
+            }
+        }
+
+        // Collect the notes for this block, sorted by time, without allocating.
+        self.events.clear();
+        while self.events.len() < self.events.capacity() {
+            let Some(mut event) = self.queue.pop() else {
+                break;
+            };
+            event.time = event.time.min(num_frames.saturating_sub(1));
+            let index = self
+                .events
+                .partition_point(|other| other.time <= event.time);
+            self.events.insert(index, event);
+        }
+
+        // Render up to each note, then play it.
+        output.clear();
+        let mut frame = 0;
+        for index in 0..=self.events.len() {
+            let event = self.events.get(index).copied();
+            let end = event.map_or(num_frames, |event| event.time);
+            self.render(output, frame, end);
+            frame = frame.max(end);
+            if let Some(event) = event.filter(|_| self.num_voices > 0) {
+                self.dispatch(event);
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        for slot in &mut self.slots {
+            slot.voice.reset();
+            slot.note = None;
+        }
+        self.next = 0;
+    }
+
+    fn parameters(&self) -> Option<ParameterTree> {
+        // Every voice is in use by default.
+        let max_num_voices = self.slots.len() as f32;
+        let num_voices = Parameter::new(NUM_VOICES, "Voices", 1.0, max_num_voices, max_num_voices)
+            .flags(Flags::STEPPED);
+        let steal = match self.default_steal {
+            Steal::Oldest => 0.0,
+            Steal::None => 1.0,
+        };
+        let steal = Parameter::new(STEAL, "Steal", 0.0, 1.0, steal).flags(Flags::STEPPED);
+        let tree = ParameterTree::branch("Voices")
+            .with(ParameterTree::leaf(num_voices))
+            .with(ParameterTree::leaf(steal));
+        Some(tree)
+    }
+}
+
+impl NoteSender {
+    /// Send a note to be played in the next block. Fails if too many notes are pending.
+    pub fn send(&self, event: NoteEvent) -> Result<(), NoteEvent> {
+        self.queue.push(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        graph::{node, Graph, Options},
+        proc::{builtin::process_block, ParamChange, Processor as _},
+    };
+
+    /// Writes its velocity from note on to note off, with no release.
+    #[derive(Default)]
+    struct Hold {
+        level: f32,
+        active: bool,
+    }
+
+    impl Voice for Hold {
+        fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {}
+
+        fn note_on(&mut self, _note: u8, velocity: f32) {
+            self.level = velocity;
+            self.active = true;
+        }
+
+        fn note_off(&mut self) {
+            self.active = false;
+        }
+
+        fn render(&mut self, output: &mut [Vec<f32>], num_frames: usize) {
+            for channel in output {
+                channel[..num_frames].fill(self.level);
+            }
+        }
+
+        fn is_active(&self) -> bool {
+            self.active
+        }
+
+        fn reset(&mut self) {
+            self.active = false;
+        }
+    }
+
+    fn processor(num_voices: usize, steal: Steal) -> Processor<Hold> {
+        let voices = (0..num_voices).map(|_| Hold::default()).collect();
+        let mut poly = Processor::new(voices, 1, steal);
+        poly.initialize(48e3, 8);
+        poly
+    }
+
+    fn on(time: usize, note: u8, velocity: f32) -> NoteEvent {
+        NoteEvent {
+            time,
+            note,
+            kind: NoteKind::On { velocity },
+        }
+    }
+
+    fn off(time: usize, note: u8) -> NoteEvent {
+        NoteEvent {
+            time,
+            note,
+            kind: NoteKind::Off,
+        }
+    }
+
+    /// Send `events` and process a block of eight frames, returning the output.
+    fn block(
+        poly: &mut Processor<Hold>,
+        events: &[NoteEvent],
+        changes: &[ParamChange],
+    ) -> Vec<f32> {
+        let sender = poly.sender();
+        for event in events {
+            sender.send(*event).unwrap();
+        }
+        let mut outputs = vec![vec![vec![0.0; 8]]];
+        process_block(poly, &[], &mut outputs, changes);
+        outputs.remove(0).remove(0)
+    }
+
+    /// The note each voice is playing.
+    fn notes(poly: &Processor<Hold>) -> Vec<Option<u8>> {
+        poly.slots.iter().map(|slot| slot.note).collect()
+    }
+
+    #[test]
+    fn overlapping_notes_play_on_separate_voices() {
+        let mut poly = processor(4, Steal::Oldest);
+        let output = block(
+            &mut poly,
+            &[on(4, 64, 0.25), on(0, 60, 0.5), on(2, 62, 1.0)],
+            &[],
+        );
+        assert_eq!(output, [0.5, 0.5, 1.5, 1.5, 1.75, 1.75, 1.75, 1.75]);
+        assert_eq!(notes(&poly), [Some(60), Some(62), Some(64), None]);
+        assert_eq!(
+            poly.slots.iter().filter(|slot| slot.voice.active).count(),
+            3
+        );
+
+        // Releasing one leaves the others sounding.
+        let output = block(&mut poly, &[off(4, 62)], &[]);
+        assert_eq!(output, [1.75, 1.75, 1.75, 1.75, 0.75, 0.75, 0.75, 0.75]);
+        assert_eq!(notes(&poly), [Some(60), None, Some(64), None]);
+    }
+
+    #[test]
+    fn free_voices_are_used_round_robin() {
+        let mut poly = processor(3, Steal::Oldest);
+        block(
+            &mut poly,
+            &[on(0, 60, 1.0), off(1, 60), on(2, 62, 1.0)],
+            &[],
+        );
+        assert_eq!(notes(&poly), [None, Some(62), None]);
+        block(&mut poly, &[on(0, 64, 1.0), on(0, 65, 1.0)], &[]);
+        assert_eq!(notes(&poly), [Some(65), Some(62), Some(64)]);
+    }
+
+    #[test]
+    fn busy_voices_follow_the_steal_policy() {
+        // The oldest voice is taken.
+        let mut poly = processor(2, Steal::Oldest);
+        let output = block(
+            &mut poly,
+            &[on(0, 60, 0.5), on(1, 62, 0.5), on(2, 64, 1.0)],
+            &[],
+        );
+        assert_eq!(output, [0.5, 1.0, 1.5, 1.5, 1.5, 1.5, 1.5, 1.5]);
+        assert_eq!(notes(&poly), [Some(64), Some(62)]);
+
+        // Or the note is dropped.
+        let mut poly = processor(2, Steal::None);
+        let output = block(
+            &mut poly,
+            &[on(0, 60, 0.5), on(1, 62, 0.5), on(2, 64, 1.0)],
+            &[],
+        );
+        assert_eq!(output, [0.5, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0]);
+        assert_eq!(notes(&poly), [Some(60), Some(62)]);
+    }
+
+    #[test]
+    fn parameters_limit_the_voices_and_set_the_policy() {
+        let mut poly = processor(4, Steal::Oldest);
+        let changes = [
+            ParamChange {
+                id: NUM_VOICES,
+                time: 0,
+                value: 2.0,
+            },
+            ParamChange {
+                id: STEAL,
+                time: 0,
+                value: 1.0,
+            },
+        ];
+        block(
+            &mut poly,
+            &[on(0, 60, 1.0), on(0, 62, 1.0), on(0, 64, 1.0)],
+            &changes,
+        );
+        assert_eq!(notes(&poly), [Some(60), Some(62), None, None]);
+
+        // The parameters default to how the processor was created, and the host reads what it
+        // has set them to from the node.
+        let graph = Graph::new(Options {
+            num_input_channels: 0,
+            num_output_channels: 0,
+            num_workers: 0,
+        });
+        let options = node::Options {
+            audio_inputs: vec![],
+            audio_outputs: vec![1],
+        };
+        let node = node::Node::new(&graph, options, processor(4, Steal::Oldest));
+        for change in changes {
+            node.send_param_change(change).unwrap();
+        }
+        let values = node
+            .parameters()
+            .unwrap()
+            .iter()
+            .map(|parameter| {
+                let value = node.param_value(parameter.id).unwrap();
+                (parameter.id, parameter.default, value)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(values, [(NUM_VOICES, 4.0, 2.0), (STEAL, 0.0, 1.0)]);
+    }
+}
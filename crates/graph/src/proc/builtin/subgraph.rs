@@ -0,0 +1,98 @@
+//! A processor that renders an entire graph, so that it can be used as a single node in another.
+//!
+//! Each port of the inner graph's input node becomes an input bus of the processor, see
+//! [crate::Graph::add_host_input], and the output node becomes its first output bus. Changes
+//! committed to the inner graph are picked up the next time it is rendered.
+//!
+//! The processor holds the inner graph's renderer for as long as it lives, so
+//! [crate::Graph::renderer] returns `None` for the inner graph, and it can't be wrapped twice.
+use std::sync::atomic::Ordering;
+
+use crate::{
+    graph::{node, Graph},
+    proc::{self, Context},
+    renderer::Renderer,
+};
+
+pub struct Processor {
+    _graph: Graph,
+    renderer: Renderer,
+    inputs: Vec<*const f32>,
+    outputs: Vec<*mut f32>,
+}
+
+impl Processor {
+    /// Wrap `graph`. Returns `None` if the graph's renderer is already driven elsewhere: held by
+    /// another subgraph, or initialized by a host.
+    pub fn new(graph: &Graph) -> Option<Self> {
+        let renderer = graph.renderer()?;
+        if renderer.inner.driven.load(Ordering::Relaxed)
+            || renderer.inner.claimed.swap(true, Ordering::AcqRel)
+        {
+            return None;
+        }
+        let options = Self::node_options(graph);
+        let inputs = vec![std::ptr::null(); options.audio_inputs.iter().sum()];
+        let outputs =
+            vec![std::ptr::null_mut(); options.audio_outputs.first().copied().unwrap_or(0)];
+        Some(Self {
+            _graph: graph.clone(),
+            renderer,
+            inputs,
+            outputs,
+        })
+    }
+
+    /// The options for a node wrapping `graph`, with an input bus for each port of its input node
+    /// and an output bus for its output node. Buses without any channels are omitted.
+    pub fn node_options(graph: &Graph) -> node::Options {
+        let mut audio_inputs = graph.input_node().options().audio_outputs;
+        let mut audio_outputs = graph.output_node().options().audio_inputs;
+        audio_inputs.retain(|num_channels| *num_channels != 0);
+        audio_outputs.retain(|num_channels| *num_channels != 0);
+        node::Options {
+            audio_inputs,
+            audio_outputs,
+        }
+    }
+}
+
+impl proc::Processor for Processor {
+    fn initialize(&mut self, sample_rate: f64, max_num_frames: usize) {
+        self.renderer.initialize(sample_rate, max_num_frames);
+    }
+
+    fn process(&mut self, context: &mut Context<'_>) {
+        let Some(output) = context.audio_outputs.first_mut() else {
+            return;
+        };
+        let num_frames = output.num_frames();
+        for (ptr, channel) in self.outputs.iter_mut().zip(output.iter()) {
+            *ptr = channel.as_mut_ptr();
+        }
+        let channels = context.audio_inputs.iter().flat_map(|input| input.iter());
+        for (ptr, channel) in self.inputs.iter_mut().zip(channels) {
+            *ptr = channel.as_ptr();
+        }
+        if let Some(transport) = context.transport {
+            self.renderer.set_transport(transport);
+        }
+        self.renderer.render(
+            self.inputs.as_ptr(),
+            self.outputs.as_mut_ptr(),
+            self.inputs.len(),
+            self.outputs.len(),
+            num_frames,
+        );
+    }
+
+    fn reset(&mut self) {
+        self.renderer.reset();
+    }
+}
+
+impl Drop for Processor {
+    fn drop(&mut self) {
+        self.renderer.inner.claimed.store(false, Ordering::Release);
+    }
+}
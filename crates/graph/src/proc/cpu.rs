@@ -0,0 +1,69 @@
+//! CPU feature detection for processors that select SIMD kernels at runtime.
+//!
+//! Detection runs once per process. Query [cpu_features] in [super::Processor::initialize] and
+//! store the selected kernel rather than checking on every call to `process`.
+use std::sync::OnceLock;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CpuFeatures {
+    pub sse2: bool,
+    pub sse4_1: bool,
+    pub avx: bool,
+    pub avx2: bool,
+    pub fma: bool,
+    pub neon: bool,
+}
+
+/// The features of the running CPU.
+pub fn cpu_features() -> CpuFeatures {
+    static FEATURES: OnceLock<CpuFeatures> = OnceLock::new();
+    *FEATURES.get_or_init(detect)
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+fn detect() -> CpuFeatures {
+    CpuFeatures {
+        sse2: is_x86_feature_detected!("sse2"),
+        sse4_1: is_x86_feature_detected!("sse4.1"),
+        avx: is_x86_feature_detected!("avx"),
+        avx2: is_x86_feature_detected!("avx2"),
+        fma: is_x86_feature_detected!("fma"),
+        neon: false,
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+fn detect() -> CpuFeatures {
+    CpuFeatures {
+        neon: std::arch::is_aarch64_feature_detected!("neon"),
+        ..CpuFeatures::default()
+    }
+}
+
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+fn detect() -> CpuFeatures {
+    CpuFeatures::default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detection_is_cached() {
+        let features = cpu_features();
+        assert_eq!(features, detect());
+        let threads = (0..4)
+            .map(|_| std::thread::spawn(cpu_features))
+            .collect::<Vec<_>>();
+        for thread in threads {
+            assert_eq!(thread.join().unwrap(), features);
+        }
+    }
+
+    #[test]
+    #[cfg(target_arch = "x86_64")]
+    fn x86_64_has_sse2() {
+        assert!(cpu_features().sse2);
+    }
+}
@@ -0,0 +1,317 @@
+//! Descriptions of a processor's parameters, for hosts to build controls from.
+//!
+//! A processor returns a [ParameterTree] from [super::Processor::parameters], grouping its
+//! parameters into named branches. Hosts read it with [crate::node::Node::parameters] and set the
+//! parameters by id with [crate::node::Node::send_param_change].
+//!
+//! ```
+//! # use tesi_graph::proc::params::{Flags, Parameter, ParameterTree};
+//! let tree = ParameterTree::branch("Filter")
+//!     .with(ParameterTree::leaf(
+//!         Parameter::new(0, "Cutoff", 20.0, 20e3, 1e3)
+//!             .unit("Hz")
+//!             .flags(Flags::LOGARITHMIC | Flags::AUTOMATABLE),
+//!     ))
+//!     .with(ParameterTree::leaf(Parameter::new(1, "Q", 0.1, 10.0, 0.7)));
+//! assert_eq!(tree.iter().count(), 2);
+//! ```
+//!
+//! A processor may also offer named [Preset]s from [super::Processor::presets], which hosts list
+//! and load with [crate::node::Node::load_preset].
+use std::{borrow::Cow, ops::BitOr};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParameterTree {
+    Branch {
+        name: Cow<'static, str>,
+        children: Vec<ParameterTree>,
+    },
+    Leaf(Parameter),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Parameter {
+    pub id: usize,
+    pub name: Cow<'static, str>,
+    pub min: f32,
+    pub max: f32,
+    pub default: f32,
+    pub unit: Cow<'static, str>,
+    pub flags: Flags,
+}
+
+/// A named set of parameter values.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Preset {
+    pub name: Cow<'static, str>,
+    /// The id and value of each parameter the preset sets. Others keep their current value.
+    pub values: Vec<(usize, f32)>,
+}
+
+/// Hints for how a host presents a parameter.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Flags(u32);
+
+/// The leaves of a [ParameterTree], depth first.
+pub struct Iter<'a> {
+    stack: Vec<std::slice::Iter<'a, ParameterTree>>,
+}
+
+impl ParameterTree {
+    /// An empty group, see [ParameterTree::with].
+    pub fn branch(name: impl Into<Cow<'static, str>>) -> Self {
+        Self::Branch {
+            name: name.into(),
+            children: vec![],
+        }
+    }
+
+    pub fn leaf(parameter: Parameter) -> Self {
+        Self::Leaf(parameter)
+    }
+
+    /// Add `child` to a branch. Adding to a leaf turns it into an unnamed branch holding both.
+    pub fn with(self, child: ParameterTree) -> Self {
+        match self {
+            Self::Branch { name, mut children } => {
+                children.push(child);
+                Self::Branch { name, children }
+            }
+            leaf => Self::Branch {
+                name: Cow::Borrowed(""),
+                children: vec![leaf, child],
+            },
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            stack: vec![std::slice::from_ref(self).iter()],
+        }
+    }
+
+    /// The parameter with `id`, if it is in the tree.
+    pub fn find(&self, id: usize) -> Option<&Parameter> {
+        self.iter().find(|parameter| parameter.id == id)
+    }
+}
+
+impl Parameter {
+    pub fn new(
+        id: usize,
+        name: impl Into<Cow<'static, str>>,
+        min: f32,
+        max: f32,
+        default: f32,
+    ) -> Self {
+        debug_assert!(min <= max, "the range of a parameter can't be empty");
+        Self {
+            id,
+            name: name.into(),
+            min,
+            max,
+            default,
+            unit: Cow::Borrowed(""),
+            flags: Flags::default(),
+        }
+    }
+
+    pub fn unit(mut self, unit: impl Into<Cow<'static, str>>) -> Self {
+        self.unit = unit.into();
+        self
+    }
+
+    pub fn flags(mut self, flags: Flags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// Format `value` for display, with its unit. Stepped parameters are shown as integers.
+    pub fn format(&self, value: f32) -> String {
+        let value = if self.flags.contains(Flags::STEPPED) {
+            format!("{}", value.round())
+        } else {
+            format!("{value:.2}")
+        };
+        if self.unit.is_empty() {
+            value
+        } else {
+            format!("{value} {}", self.unit)
+        }
+    }
+
+    /// Parse a value typed by a user, with or without the unit, clamped to the range. Values that
+    /// aren't finite, like `NaN` or `inf`, are rejected.
+    pub fn parse(&self, text: &str) -> Option<f32> {
+        let text = text.trim();
+        let text = text.strip_suffix(self.unit.as_ref()).unwrap_or(text);
+        let value = text
+            .trim()
+            .parse::<f32>()
+            .ok()
+            .filter(|value| value.is_finite())?;
+        let value = if self.flags.contains(Flags::STEPPED) {
+            value.round()
+        } else {
+            value
+        };
+        Some(value.max(self.min).min(self.max))
+    }
+}
+
+impl Preset {
+    pub fn new(name: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            name: name.into(),
+            values: vec![],
+        }
+    }
+
+    /// Set parameter `id` to `value` when the preset is loaded.
+    pub fn with(mut self, id: usize, value: f32) -> Self {
+        self.values.push((id, value));
+        self
+    }
+}
+
+impl Flags {
+    /// The parameter only takes whole values.
+    pub const STEPPED: Self = Self(1);
+    /// The parameter is best shown on a logarithmic scale, like a frequency.
+    pub const LOGARITHMIC: Self = Self(1 << 1);
+    /// The parameter may be automated with sample-accurate changes.
+    pub const AUTOMATABLE: Self = Self(1 << 2);
+    /// The parameter is a read-only readout the processor sets, like a level meter, see
+    /// [crate::proc::Context::set_param_output]. Changes from the host are dropped and it is left
+    /// out of presets.
+    pub const OUTPUT: Self = Self(1 << 3);
+
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for Flags {
+    type Output = Self;
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a Parameter;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.stack.last_mut()?.next() {
+                Some(ParameterTree::Leaf(parameter)) => return Some(parameter),
+                Some(ParameterTree::Branch { children, .. }) => self.stack.push(children.iter()),
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cutoff() -> Parameter {
+        Parameter::new(0, "Cutoff", 20.0, 20e3, 1e3)
+            .unit("Hz")
+            .flags(Flags::LOGARITHMIC | Flags::AUTOMATABLE)
+    }
+
+    fn voices() -> Parameter {
+        Parameter::new(2, "Voices", 1.0, 8.0, 4.0).flags(Flags::STEPPED)
+    }
+
+    #[test]
+    fn builder_makes_a_two_group_tree() {
+        let q = Parameter::new(1, "Q", 0.1, 10.0, 0.7);
+        let tree = ParameterTree::branch("Synth")
+            .with(
+                ParameterTree::branch("Filter")
+                    .with(ParameterTree::leaf(cutoff()))
+                    .with(ParameterTree::leaf(q.clone())),
+            )
+            .with(ParameterTree::branch("Voices").with(ParameterTree::leaf(voices())));
+        assert_eq!(
+            tree,
+            ParameterTree::Branch {
+                name: "Synth".into(),
+                children: vec![
+                    ParameterTree::Branch {
+                        name: "Filter".into(),
+                        children: vec![
+                            ParameterTree::Leaf(cutoff()),
+                            ParameterTree::Leaf(q.clone()),
+                        ],
+                    },
+                    ParameterTree::Branch {
+                        name: "Voices".into(),
+                        children: vec![ParameterTree::Leaf(voices())],
+                    },
+                ],
+            }
+        );
+
+        // Leaves come out depth first, and can be found by id.
+        let ids = tree
+            .iter()
+            .map(|parameter| parameter.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, [0, 1, 2]);
+        assert_eq!(tree.find(1), Some(&q));
+        assert_eq!(tree.find(3), None);
+    }
+
+    #[test]
+    fn adding_to_a_leaf_makes_an_unnamed_branch() {
+        let tree = ParameterTree::leaf(cutoff()).with(ParameterTree::leaf(voices()));
+        assert_eq!(
+            tree,
+            ParameterTree::Branch {
+                name: "".into(),
+                children: vec![ParameterTree::Leaf(cutoff()), ParameterTree::Leaf(voices())],
+            }
+        );
+    }
+
+    #[test]
+    fn flags_combine() {
+        let flags = cutoff().flags;
+        assert!(flags.contains(Flags::LOGARITHMIC));
+        assert!(flags.contains(Flags::AUTOMATABLE));
+        assert!(flags.contains(Flags::LOGARITHMIC | Flags::AUTOMATABLE));
+        assert!(!flags.contains(Flags::STEPPED));
+        assert!(flags.contains(Flags::default()));
+    }
+
+    #[test]
+    fn values_format_and_parse_with_their_units() {
+        let cutoff = cutoff();
+        assert_eq!(cutoff.format(440.0), "440.00 Hz");
+        assert_eq!(cutoff.parse(" 880 Hz "), Some(880.0));
+        assert_eq!(cutoff.parse("880"), Some(880.0));
+        assert_eq!(cutoff.parse("30000 Hz"), Some(20e3));
+        assert_eq!(cutoff.parse("loud"), None);
+        assert_eq!(cutoff.parse("NaN"), None);
+        assert_eq!(cutoff.parse("inf Hz"), None);
+        assert_eq!(cutoff.parse("-inf"), None);
+
+        let voices = voices();
+        assert_eq!(voices.format(2.6), "3");
+        assert_eq!(voices.parse("2.6"), Some(3.0));
+        assert_eq!(voices.parse("0"), Some(1.0));
+
+        // A range built by hand past the check in `new` doesn't make parsing panic.
+        let backwards = Parameter {
+            min: 1.0,
+            max: 0.0,
+            ..cutoff
+        };
+        assert_eq!(backwards.parse("0.5"), Some(0.0));
+    }
+}
@@ -0,0 +1,66 @@
+//! Per-node processing time.
+//!
+//! While profiling is enabled, see [crate::Renderer::set_profiling], the renderer times every call
+//! to a processor and accumulates the result into atomics shared with the node's handle, so that
+//! reading the statistics never blocks the audio thread.
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+pub(crate) struct Profile {
+    num_blocks: AtomicU64,
+    total: AtomicU64,
+    min: AtomicU64,
+    max: AtomicU64,
+}
+
+/// Statistics of the time a node's processor took per block, see [crate::node::Node::profile].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NodeProfile {
+    pub num_blocks: u64,
+    pub min: Duration,
+    pub max: Duration,
+    pub mean: Duration,
+}
+
+impl Profile {
+    pub(crate) fn new() -> Self {
+        Self {
+            num_blocks: AtomicU64::new(0),
+            total: AtomicU64::new(0),
+            min: AtomicU64::new(u64::MAX),
+            max: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn record(&self, nanos: u64) {
+        self.num_blocks.fetch_add(1, Ordering::Relaxed);
+        self.total.fetch_add(nanos, Ordering::Relaxed);
+        self.min.fetch_min(nanos, Ordering::Relaxed);
+        self.max.fetch_max(nanos, Ordering::Relaxed);
+    }
+
+    /// Read the statistics. They are updated independently, so a snapshot taken while the node is
+    /// being processed may be off by a block.
+    pub(crate) fn snapshot(&self) -> NodeProfile {
+        let num_blocks = self.num_blocks.load(Ordering::Relaxed);
+        if num_blocks == 0 {
+            return NodeProfile::default();
+        }
+        let total = self.total.load(Ordering::Relaxed);
+        NodeProfile {
+            num_blocks,
+            min: Duration::from_nanos(self.min.load(Ordering::Relaxed)),
+            max: Duration::from_nanos(self.max.load(Ordering::Relaxed)),
+            mean: Duration::from_nanos(total / num_blocks),
+        }
+    }
+
+    pub(crate) fn reset(&self) {
+        self.num_blocks.store(0, Ordering::Relaxed);
+        self.total.store(0, Ordering::Relaxed);
+        self.min.store(u64::MAX, Ordering::Relaxed);
+        self.max.store(0, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,121 @@
+//! Implicit mixing on summing inputs.
+//!
+//! An input fed by a single edge aliases its source's output buffer. A summing input fed by
+//! several edges can't alias them all, so while the sink is processed its input bus is pointed at
+//! the summer's own buffers, which hold the sum of every source, and restored afterwards like a
+//! delayed input. Each source is delayed first by the delay set on its edge, plus whatever lines
+//! it up with the latest one. The graph's output node sums a single delayed source too, so the
+//! delay is never applied in place on a buffer that other sinks may read.
+use crate::{
+    bus::{kernels, AudioBus, AudioBusMut},
+    delay::DelayLine,
+};
+
+pub(crate) struct Summer {
+    outputs: Vec<Vec<f32>>,
+    saved: Vec<*const f32>,
+    delays: Vec<Option<DelayLine>>,
+}
+
+impl Summer {
+    /// Create a summer for sources that need delaying by `delays` frames each, in the order they
+    /// are passed to [Summer::apply].
+    pub(crate) fn new(num_channels: usize, max_num_frames: usize, delays: &[usize]) -> Self {
+        let delays = delays
+            .iter()
+            .map(|delay| {
+                (*delay > 0 && num_channels > 0)
+                    .then(|| DelayLine::new(num_channels, *delay, max_num_frames))
+            })
+            .collect();
+        Self {
+            outputs: vec![vec![0.0; max_num_frames]; num_channels],
+            saved: vec![std::ptr::null(); num_channels],
+            delays,
+        }
+    }
+
+    /// Sum `sources` and point `bus` at the sum.
+    pub(crate) unsafe fn apply<'a>(
+        &mut self,
+        bus: &AudioBus,
+        sources: impl Iterator<Item = &'a AudioBusMut>,
+    ) {
+        let num_frames = bus.num_frames();
+        for output in &mut self.outputs {
+            output[..num_frames].fill(0.0);
+        }
+        for (source, delay) in sources.zip(&mut self.delays) {
+            let delayed = delay.as_mut().map(|delay| delay.apply_to_source(source));
+            for (channel, output) in self.outputs.iter_mut().enumerate() {
+                let input = Self::channel(delayed, source, channel, num_frames);
+                kernels::add_assign(&mut output[..num_frames], input);
+            }
+        }
+        for ((ptr, saved), output) in bus.ptrs.iter().zip(&mut self.saved).zip(&self.outputs) {
+            *saved = *ptr.get();
+            *ptr.get() = output.as_ptr();
+        }
+    }
+
+    /// Sum `sources` into the buffers of `bus`. Used for the graph's output node, whose input bus
+    /// points at the host's buffers.
+    pub(crate) unsafe fn apply_in_place<'a>(
+        &mut self,
+        bus: &AudioBus,
+        sources: impl Iterator<Item = &'a AudioBusMut>,
+    ) {
+        let num_frames = bus.num_frames();
+        for ptr in &bus.ptrs {
+            std::slice::from_raw_parts_mut((*ptr.get()).cast_mut(), num_frames).fill(0.0);
+        }
+        for (source, delay) in sources.zip(&mut self.delays) {
+            let delayed = delay.as_mut().map(|delay| delay.apply_to_source(source));
+            for (channel, ptr) in bus.ptrs.iter().enumerate() {
+                let output = std::slice::from_raw_parts_mut((*ptr.get()).cast_mut(), num_frames);
+                let input = Self::channel(delayed, source, channel, num_frames);
+                kernels::add_assign(output, input);
+            }
+        }
+    }
+
+    // A channel of a source, or of its delayed signal if it has one.
+    unsafe fn channel<'a>(
+        delayed: Option<&'a [Vec<f32>]>,
+        source: &'a AudioBusMut,
+        channel: usize,
+        num_frames: usize,
+    ) -> &'a [f32] {
+        match delayed {
+            Some(delayed) => &delayed[channel][..num_frames],
+            None => std::slice::from_raw_parts(*source.ptrs[channel].get(), num_frames),
+        }
+    }
+
+    /// Point `bus` back at the buffers it had before [Summer::apply].
+    pub(crate) unsafe fn restore(&self, bus: &AudioBus) {
+        for (ptr, saved) in bus.ptrs.iter().zip(&self.saved) {
+            *ptr.get() = *saved;
+        }
+    }
+
+    /// Whether any source is delayed, and so may still be playing out what it sent earlier.
+    pub(crate) fn is_delayed(&self) -> bool {
+        self.delays.iter().any(Option::is_some)
+    }
+
+    pub(crate) fn warmup(&mut self) {
+        for output in &mut self.outputs {
+            output.fill(0.0);
+        }
+        for delay in self.delays.iter_mut().flatten() {
+            delay.warmup();
+        }
+    }
+
+    pub(crate) fn reset(&mut self) {
+        for delay in self.delays.iter_mut().flatten() {
+            delay.reset();
+        }
+    }
+}
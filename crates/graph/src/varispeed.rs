@@ -0,0 +1,120 @@
+//! Varispeed rendering.
+//!
+//! The graph always runs at its native sample rate. To play it back slower or faster the renderer
+//! pulls as many graph frames as the rate scale calls for into a scratch buffer and resamples them
+//! onto the host's output buffers with linear interpolation. Processors never see the rate change.
+//!
+//! Host inputs are not resampled. While varispeed is active the input node is fed silence.
+use crate::renderer;
+
+/// The largest supported rate scale. Scratch buffers are sized for it in `initialize`, so that
+/// rendering never allocates.
+pub const MAX_RATE_SCALE: f64 = 4.0;
+
+pub(crate) struct Varispeed {
+    pub(crate) rate_scale: f64,
+    position: f64,
+    len: usize,
+    max_num_frames: usize,
+    inputs: Vec<Vec<f32>>,
+    outputs: Vec<Vec<f32>>,
+    input_ptrs: Vec<*const f32>,
+    output_ptrs: Vec<*mut f32>,
+}
+
+impl Varispeed {
+    pub(crate) fn new() -> Self {
+        Self {
+            rate_scale: 1.0,
+            position: 0.0,
+            len: 0,
+            max_num_frames: 0,
+            inputs: vec![],
+            outputs: vec![],
+            input_ptrs: vec![],
+            output_ptrs: vec![],
+        }
+    }
+
+    pub(crate) fn initialize(
+        &mut self,
+        num_inputs: usize,
+        num_outputs: usize,
+        max_num_frames: usize,
+    ) {
+        let capacity = (max_num_frames as f64 * MAX_RATE_SCALE).ceil() as usize + 2;
+        self.inputs = vec![vec![0.0; max_num_frames]; num_inputs];
+        self.outputs = vec![vec![0.0; capacity]; num_outputs];
+        self.input_ptrs = self.inputs.iter().map(|buffer| buffer.as_ptr()).collect();
+        self.output_ptrs = vec![std::ptr::null_mut(); num_outputs];
+        self.max_num_frames = max_num_frames;
+        self.reset();
+    }
+
+    pub(crate) fn warmup(&mut self) {
+        for buffer in self.inputs.iter_mut().chain(&mut self.outputs) {
+            buffer.fill(0.0);
+        }
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.position = 0.0;
+        self.len = 0;
+    }
+
+    pub(crate) fn is_active(&self) -> bool {
+        self.rate_scale != 1.0 || self.len != 0
+    }
+
+    pub(crate) fn render(
+        &mut self,
+        inner: &renderer::Inner,
+        outputs: *const *mut f32,
+        num_outputs: usize,
+        num_frames: usize,
+    ) {
+        debug_assert_eq!(num_outputs, self.outputs.len());
+        if num_frames == 0 {
+            return;
+        }
+        let rate_scale = self.rate_scale;
+
+        // Pull enough frames from the graph to interpolate across the whole block.
+        let needed = (self.position + rate_scale * (num_frames - 1) as f64) as usize + 2;
+        while self.len < needed {
+            let chunk = (needed - self.len).min(self.max_num_frames);
+            for (ptr, buffer) in self.output_ptrs.iter_mut().zip(&mut self.outputs) {
+                *ptr = unsafe { buffer.as_mut_ptr().add(self.len) };
+            }
+            inner.audio_thread(
+                self.input_ptrs.as_ptr(),
+                self.output_ptrs.as_ptr(),
+                self.inputs.len(),
+                self.outputs.len(),
+                chunk,
+            );
+            self.len += chunk;
+        }
+
+        // Resample onto the host buffers.
+        for (channel, buffer) in self.outputs.iter().enumerate() {
+            let output =
+                unsafe { std::slice::from_raw_parts_mut(*outputs.add(channel), num_frames) };
+            for (frame, sample) in output.iter_mut().enumerate() {
+                let position = self.position + rate_scale * frame as f64;
+                let index = position as usize;
+                let fract = (position - index as f64) as f32;
+                *sample = buffer[index] + (buffer[index + 1] - buffer[index]) * fract;
+            }
+        }
+
+        // Discard the frames that have been consumed.
+        let position = self.position + rate_scale * num_frames as f64;
+        let consumed = (position as usize).min(self.len);
+        for buffer in &mut self.outputs {
+            buffer.copy_within(consumed..self.len, 0);
+        }
+        self.len -= consumed;
+        self.position = position - consumed as f64;
+    }
+}
@@ -0,0 +1,76 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+use tesi_graph::{edge::Edge, node, proc::builtin::gain, Graph, Options};
+
+/// Counts the allocations and live bytes of each thread.
+struct Counter;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    static LIVE: Cell<isize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for Counter {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        LIVE.with(|live| live.set(live.get() + layout.size() as isize));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE.with(|live| live.set(live.get() - layout.size() as isize));
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static COUNTER: Counter = Counter;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+fn live() -> isize {
+    LIVE.with(Cell::get)
+}
+
+#[test]
+fn commits_allocate_a_constant_amount() {
+    let graph = Graph::new(Options {
+        num_input_channels: 1,
+        num_output_channels: 1,
+        num_workers: 0,
+    });
+    let options = node::Options {
+        audio_inputs: vec![1],
+        audio_outputs: vec![1],
+    };
+    let gain = node::Node::new(&graph, options, gain::Processor::new(0.5));
+    let _edges = [
+        Edge::new(&graph, &graph.input_node(), 0, &gain, 0).unwrap(),
+        Edge::new(&graph, &gain, 0, &graph.output_node(), 0).unwrap(),
+    ];
+
+    // Each commit allocates the same, and frees as much when it replaces an older state, so
+    // memory doesn't grow with the number of commits.
+    let commit = || {
+        let before = allocations();
+        graph.commit_changes().unwrap();
+        allocations() - before
+    };
+    let first = commit();
+    let mut live_after = [0; 3];
+    for slot in &mut live_after {
+        for _ in 0..32 {
+            assert_eq!(commit(), first);
+        }
+        *slot = live();
+    }
+    assert!(
+        live_after.iter().all(|live| *live == live_after[0]),
+        "{live_after:?}"
+    );
+}
@@ -0,0 +1,75 @@
+mod common;
+
+use common::{render, Ramp, Thru};
+use tesi_graph::{
+    edge::Edge,
+    node,
+    proc::{
+        self,
+        analysis::{AnalysisReceiver, RingTap},
+    },
+    Graph, Options,
+};
+
+/// Passes its input through, tapping every other sample of it for a scope.
+struct Scope(RingTap);
+
+impl proc::Processor for Scope {
+    fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {}
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        Thru.process(context);
+        self.0.push(&context.audio_outputs[0][0]);
+    }
+
+    fn reset(&mut self) {
+        self.0.reset();
+    }
+
+    fn analysis_tap(&self) -> Option<AnalysisReceiver> {
+        Some(self.0.receiver())
+    }
+}
+
+#[test]
+fn scopes_read_the_decimated_waveform() {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 0,
+    });
+    let ramp = node::Node::new(
+        &graph,
+        node::Options {
+            audio_inputs: vec![],
+            audio_outputs: vec![1],
+        },
+        Ramp::default(),
+    );
+    let scope = node::Node::new(
+        &graph,
+        node::Options {
+            audio_inputs: vec![1],
+            audio_outputs: vec![1],
+        },
+        Scope(RingTap::new(64, 2)),
+    );
+    let _edges = [
+        Edge::new(&graph, &ramp, 0, &scope, 0).unwrap(),
+        Edge::new(&graph, &scope, 0, &graph.output_node(), 0).unwrap(),
+    ];
+    graph.commit_changes().unwrap();
+    assert!(ramp.analysis_tap().is_none());
+    let receiver = scope.analysis_tap().unwrap();
+
+    let mut renderer = graph.renderer().unwrap();
+    renderer.initialize(48e3, 16);
+    let output = render(&mut renderer, 0, 1, 16, 2).remove(0);
+    assert_eq!(receiver.len(), 16);
+    let mut waveform = vec![0.0; 64];
+    let count = receiver.read(&mut waveform);
+    waveform.truncate(count);
+    let expected = output.iter().step_by(2).copied().collect::<Vec<_>>();
+    assert_eq!(waveform, expected);
+    assert!(receiver.is_empty());
+}
@@ -0,0 +1,165 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use common::{render, Constant};
+use tesi_graph::{
+    edge::Edge,
+    node,
+    proc::{self, builtin::gain, Curve, ParamChange, ParamRamp},
+    Graph, Options,
+};
+
+/// Records the parameter changes of every block and writes silence.
+struct Recorder(Arc<Mutex<Vec<Vec<ParamChange>>>>);
+
+impl proc::Processor for Recorder {
+    fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {}
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        self.0.lock().unwrap().push(context.param_changes.to_vec());
+        context.silent_outputs = 1;
+    }
+
+    fn reset(&mut self) {}
+}
+
+fn change(id: usize, time: usize, value: f32) -> ParamChange {
+    ParamChange { id, time, value }
+}
+
+#[test]
+fn changes_are_delivered_sorted_with_the_next_block() {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 0,
+    });
+    let blocks = Arc::new(Mutex::new(vec![]));
+    let recorder = node::Node::new(
+        &graph,
+        node::Options {
+            audio_inputs: vec![],
+            audio_outputs: vec![1],
+        },
+        Recorder(blocks.clone()),
+    );
+    let _edge = Edge::new(&graph, &recorder, 0, &graph.output_node(), 0).unwrap();
+    graph.commit_changes().unwrap();
+    let mut renderer = graph.renderer().unwrap();
+    renderer.initialize(48e3, 16);
+
+    for sent in [
+        change(0, 8, 1.0),
+        change(1, 2, 2.0),
+        change(0, 100, 3.0),
+        change(2, 2, 4.0),
+    ] {
+        recorder.send_param_change(sent).unwrap();
+    }
+    render(&mut renderer, 0, 1, 16, 2);
+
+    // Sorted by time, in the order sent at the same time, with late changes on the last frame,
+    // and nothing left for the block after.
+    assert_eq!(
+        *blocks.lock().unwrap(),
+        [
+            vec![
+                change(1, 2, 2.0),
+                change(2, 2, 4.0),
+                change(0, 8, 1.0),
+                change(0, 15, 3.0),
+            ],
+            vec![],
+        ]
+    );
+}
+
+#[test]
+fn gain_automation_is_sample_accurate() {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 0,
+    });
+    let options = |num_inputs| node::Options {
+        audio_inputs: vec![1; num_inputs],
+        audio_outputs: vec![1],
+    };
+    let source = node::Node::new(&graph, options(0), Constant(1.0));
+    let fader = node::Node::new(&graph, options(1), gain::Processor::new(1.0));
+    let _edges = [
+        Edge::new(&graph, &source, 0, &fader, 0).unwrap(),
+        Edge::new(&graph, &fader, 0, &graph.output_node(), 0).unwrap(),
+    ];
+    graph.commit_changes().unwrap();
+    let mut renderer = graph.renderer().unwrap();
+    renderer.initialize(48e3, 8);
+
+    render(&mut renderer, 0, 1, 8, 1);
+    fader.send_param_change(change(gain::GAIN, 3, 0.0)).unwrap();
+    let output = render(&mut renderer, 0, 1, 8, 2);
+    assert_eq!(
+        output[0],
+        [0.75, 0.5, 0.25, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]
+    );
+}
+
+#[test]
+fn gain_follows_ramps_exactly() {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 0,
+    });
+    let source = node::Node::new(&graph, common::options(0, 1), Constant(1.0));
+    let fader = node::Node::new(&graph, common::options(1, 1), gain::Processor::new(1.0));
+    let _edges = [
+        Edge::new(&graph, &source, 0, &fader, 0).unwrap(),
+        Edge::new(&graph, &fader, 0, &graph.output_node(), 0).unwrap(),
+    ];
+    graph.commit_changes().unwrap();
+    let mut renderer = graph.renderer().unwrap();
+    renderer.initialize(48e3, 16);
+
+    // The gain holds until the ramp starts, then takes its value on every frame.
+    let ramp = ParamRamp {
+        id: gain::GAIN,
+        start_frame: 4,
+        end_frame: 12,
+        start_value: 0.0,
+        end_value: 0.5,
+        curve: Curve::Linear,
+    };
+    fader.send_param_ramp(ramp).unwrap();
+    let output = render(&mut renderer, 0, 1, 16, 1);
+    let mut expected = vec![1.0; 4];
+    expected.extend((0..=8).map(|step| step as f32 / 16.0));
+    expected.extend([0.5; 3]);
+    assert_eq!(output[0], expected);
+    assert_eq!(fader.param_value(gain::GAIN), Some(0.5));
+
+    // A ramp past the block carries on into the next.
+    let ramp = ParamRamp {
+        id: gain::GAIN,
+        start_frame: 0,
+        end_frame: 32,
+        start_value: 1.0,
+        end_value: 4.0,
+        curve: Curve::Exponential,
+    };
+    fader.send_param_ramp(ramp).unwrap();
+    let output = render(&mut renderer, 0, 1, 16, 3);
+    let expected = (0..16)
+        .map(|frame| ramp.value_at(frame))
+        .collect::<Vec<_>>();
+    assert_eq!(output[0][..16], expected);
+    for (frame, gain) in output[0].iter().enumerate().skip(16) {
+        let expected = ramp.value_at(frame);
+        assert!(
+            (gain - expected).abs() < 1e-5,
+            "{gain} != {expected} at {frame}"
+        );
+    }
+    assert_eq!(output[0][32..], [4.0; 16]);
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{render, Constant, Thru};
+use tesi_graph::{edge::Edge, node, proc, Graph, Options, XrunEvent, XrunKind};
+
+/// Passes its input through after sleeping.
+struct Slow;
+
+impl proc::Processor for Slow {
+    fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {}
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        std::thread::sleep(Duration::from_millis(5));
+        Thru.process(context);
+    }
+
+    fn reset(&mut self) {}
+}
+
+/// Adds one to its input.
+struct Increment;
+
+impl proc::Processor for Increment {
+    fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {}
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        for (input, output) in context.zip_channels() {
+            for (x, y) in input.iter().zip(output) {
+                *y = *x + 1.0;
+            }
+        }
+    }
+
+    fn reset(&mut self) {}
+}
+
+#[test]
+fn nodes_past_the_budget_are_bypassed() {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 0,
+    });
+    let options = node::Options {
+        audio_inputs: vec![1],
+        audio_outputs: vec![1],
+    };
+    let source = node::Node::new(
+        &graph,
+        node::Options {
+            audio_inputs: vec![],
+            audio_outputs: vec![1],
+        },
+        Constant(0.5),
+    );
+    let fast = node::Node::new(&graph, options.clone(), Increment);
+    let slow = node::Node::new(&graph, options.clone(), Slow);
+    let late = node::Node::new(&graph, options, Increment);
+    let _edges = [
+        Edge::new(&graph, &source, 0, &fast, 0).unwrap(),
+        Edge::new(&graph, &fast, 0, &slow, 0).unwrap(),
+        Edge::new(&graph, &slow, 0, &late, 0).unwrap(),
+        Edge::new(&graph, &late, 0, &graph.output_node(), 0).unwrap(),
+    ];
+    graph.commit_changes().unwrap();
+
+    let mut renderer = graph.renderer().unwrap();
+    renderer.initialize(48e3, 16);
+    let output = render(&mut renderer, 0, 1, 16, 1).remove(0);
+    assert_eq!(output, [2.5; 16]);
+    assert_eq!(renderer.xruns().count(), 0);
+
+    // The fast node still runs, the slow one blows the budget and the node after it is bypassed.
+    renderer.set_time_budget(Duration::from_millis(1));
+    let output = render(&mut renderer, 0, 1, 16, 1).remove(0);
+    assert_eq!(output, [1.5; 16]);
+    assert_eq!(renderer.num_skipped(), 1);
+    let xruns = renderer.xruns().collect::<Vec<_>>();
+    let expected = XrunEvent {
+        node: Some(late.id()),
+        kind: XrunKind::OverBudget,
+    };
+    assert_eq!(xruns, [expected]);
+}
@@ -0,0 +1,155 @@
+mod common;
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use common::{render, Constant, Impulse, Lookahead, Ramp};
+use tesi_graph::{edge::Edge, node, proc, Graph, Options};
+
+const LATENCY: usize = 64;
+
+/// Writes ones to every output and counts its blocks.
+struct Counted(Arc<AtomicUsize>);
+
+impl proc::Processor for Counted {
+    fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {}
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+        for output in context.audio_outputs.iter_mut() {
+            for channel in output.iter() {
+                channel.fill(1.0);
+            }
+        }
+    }
+
+    fn reset(&mut self) {}
+}
+
+fn mono() -> (Graph, node::Options) {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 0,
+    });
+    let options = node::Options {
+        audio_inputs: vec![1],
+        audio_outputs: vec![1],
+    };
+    (graph, options)
+}
+
+#[test]
+fn bypassed_latency_stays_aligned() {
+    // An impulse mixed with itself through a node with latency.
+    let (graph, options) = mono();
+    let source = node::Node::new(
+        &graph,
+        node::Options {
+            audio_inputs: vec![],
+            audio_outputs: vec![1],
+        },
+        Impulse::default(),
+    );
+    let lookahead = node::Node::new(&graph, options, Lookahead::new(LATENCY));
+    lookahead.latency_changed(LATENCY);
+    let output = graph.output_node();
+    output.set_summing(0, true).unwrap();
+    let _edges = [
+        Edge::new(&graph, &source, 0, &lookahead, 0).unwrap(),
+        Edge::new(&graph, &lookahead, 0, &output, 0).unwrap(),
+        Edge::new(&graph, &source, 0, &output, 0).unwrap(),
+    ];
+    graph.commit_changes().unwrap();
+
+    let mut expected = vec![0.0; 4 * 32];
+    expected[LATENCY] = 2.0;
+    let mut renderer = graph.renderer().unwrap();
+    renderer.initialize(48e3, 32);
+    assert_eq!(render(&mut renderer, 0, 1, 32, 4).remove(0), expected);
+
+    lookahead.set_bypassed(true);
+    renderer.reset();
+    assert_eq!(render(&mut renderer, 0, 1, 32, 4).remove(0), expected);
+}
+
+#[test]
+fn bypass_continues_the_delayed_signal() {
+    let (graph, options) = mono();
+    let ramp = node::Node::new(
+        &graph,
+        node::Options {
+            audio_inputs: vec![],
+            audio_outputs: vec![1],
+        },
+        Ramp::default(),
+    );
+    let lookahead = node::Node::new(&graph, options, Lookahead::new(LATENCY));
+    lookahead.latency_changed(LATENCY);
+    let _edges = [
+        Edge::new(&graph, &ramp, 0, &lookahead, 0).unwrap(),
+        Edge::new(&graph, &lookahead, 0, &graph.output_node(), 0).unwrap(),
+    ];
+    graph.commit_changes().unwrap();
+    assert_eq!(graph.latency(), LATENCY);
+
+    // Bypassing the node mid-stream doesn't disturb the delayed ramp. The processor isn't run
+    // while bypassed, so switching back depends on how it handles the gap.
+    let mut renderer = graph.renderer().unwrap();
+    renderer.initialize(48e3, 32);
+    let mut output = vec![];
+    for bypassed in [false, false, false, true, true, true] {
+        lookahead.set_bypassed(bypassed);
+        output.extend(render(&mut renderer, 0, 1, 32, 1).remove(0));
+    }
+    let expected = (0..output.len())
+        .map(|frame| frame.saturating_sub(LATENCY - 1) as f32)
+        .collect::<Vec<_>>();
+    assert_eq!(output, expected);
+}
+
+#[test]
+fn bypass_zero_fills_missing_channels_without_processing() {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 2,
+        num_workers: 0,
+    });
+    let source = node::Node::new(
+        &graph,
+        node::Options {
+            audio_inputs: vec![],
+            audio_outputs: vec![1],
+        },
+        Constant(0.25),
+    );
+    let blocks = Arc::new(AtomicUsize::new(0));
+    let widen = node::Node::new(
+        &graph,
+        node::Options {
+            audio_inputs: vec![1],
+            audio_outputs: vec![2],
+        },
+        Counted(blocks.clone()),
+    );
+    let _edges = [
+        Edge::new(&graph, &source, 0, &widen, 0).unwrap(),
+        Edge::new(&graph, &widen, 0, &graph.output_node(), 0).unwrap(),
+    ];
+    graph.commit_changes().unwrap();
+    let mut renderer = graph.renderer().unwrap();
+    renderer.initialize(48e3, 32);
+    assert_eq!(render(&mut renderer, 0, 2, 32, 1), [[1.0; 32], [1.0; 32]]);
+    assert_eq!(blocks.load(Ordering::Relaxed), 1);
+
+    // The mono input passes to the first channel and the second is zeroed. The processor isn't run.
+    widen.set_bypassed(true);
+    assert_eq!(render(&mut renderer, 0, 2, 32, 2), [[0.25; 64], [0.0; 64]]);
+    assert_eq!(blocks.load(Ordering::Relaxed), 1);
+
+    widen.set_bypassed(false);
+    assert_eq!(render(&mut renderer, 0, 2, 32, 1), [[1.0; 32], [1.0; 32]]);
+    assert_eq!(blocks.load(Ordering::Relaxed), 2);
+}
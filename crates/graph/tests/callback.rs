@@ -0,0 +1,43 @@
+use std::sync::{Arc, Mutex};
+
+use tesi_graph::{edge::Edge, node, proc::builtin::gain, Graph, Options};
+
+#[test]
+fn callback_drives_a_gain() {
+    let graph = Graph::new(Options {
+        num_input_channels: 1,
+        num_output_channels: 1,
+        num_workers: 0,
+    });
+    let options = node::Options {
+        audio_inputs: vec![1],
+        audio_outputs: vec![1],
+    };
+    let gain = node::Node::new(&graph, options, gain::Processor::new(0.5));
+    let _edges = [
+        Edge::new(&graph, &graph.input_node(), 0, &gain, 0).unwrap(),
+        Edge::new(&graph, &gain, 0, &graph.output_node(), 0).unwrap(),
+    ];
+    graph.commit_changes().unwrap();
+
+    let mut renderer = graph.renderer().unwrap();
+    renderer.initialize(48e3, 32);
+    assert!(!renderer.process(32));
+
+    let collected = Arc::new(Mutex::new(vec![]));
+    let blocks = Arc::new(Mutex::new(vec![]));
+    renderer.set_process_callback({
+        let (collected, blocks) = (collected.clone(), blocks.clone());
+        move |mut io| {
+            assert_eq!((io.num_inputs(), io.num_outputs()), (1, 1));
+            blocks.lock().unwrap().push(io.num_frames());
+            io.input_mut(0).fill(0.8);
+            collected.lock().unwrap().extend_from_slice(io.output(0));
+        }
+    });
+
+    // Blocks are split at the maximum buffer size.
+    assert!(renderer.process(100));
+    assert_eq!(*blocks.lock().unwrap(), [32, 32, 32, 4]);
+    assert_eq!(*collected.lock().unwrap(), [0.4; 100]);
+}
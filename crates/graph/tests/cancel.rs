@@ -0,0 +1,110 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use tesi_graph::{edge::Edge, node, proc, Graph, Options};
+
+/// Writes 0.5 to its output, counting blocks. With `stall`, its first block doesn't return until
+/// the render is cancelled.
+struct Bounce {
+    blocks: Arc<AtomicUsize>,
+    stall: bool,
+}
+
+impl proc::Processor for Bounce {
+    fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {}
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        self.blocks.fetch_add(1, Ordering::SeqCst);
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while self.stall && !context.should_cancel() {
+            assert!(Instant::now() < deadline, "the render was never cancelled");
+            thread::yield_now();
+        }
+        thread::sleep(Duration::from_micros(200));
+        for channel in context.output(0).iter() {
+            channel.fill(0.5);
+        }
+    }
+
+    fn reset(&mut self) {}
+}
+
+fn bounce(stall: bool) -> (Graph, Arc<AtomicUsize>, [Edge; 1]) {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 0,
+    });
+    let blocks = Arc::new(AtomicUsize::new(0));
+    let options = node::Options {
+        audio_inputs: vec![],
+        audio_outputs: vec![1],
+    };
+    let processor = Bounce {
+        blocks: blocks.clone(),
+        stall,
+    };
+    let node = node::Node::new(&graph, options, processor);
+    let edges = [Edge::new(&graph, &node, 0, &graph.output_node(), 0).unwrap()];
+    graph.commit_changes().unwrap();
+    (graph, blocks, edges)
+}
+
+// Cancel from another thread once `blocks` have started.
+fn cancel_after(
+    graph: &Graph,
+    counter: &Arc<AtomicUsize>,
+    blocks: usize,
+) -> thread::JoinHandle<()> {
+    let renderer = graph.renderer().unwrap();
+    let counter = counter.clone();
+    thread::spawn(move || {
+        while counter.load(Ordering::SeqCst) < blocks {
+            thread::yield_now();
+        }
+        renderer.cancel();
+    })
+}
+
+#[test]
+fn cancelled_render_stops_early() {
+    let (graph, counter, _edges) = bounce(false);
+    let mut output = vec![1.0; 16 * 1000];
+    let canceller = cancel_after(&graph, &counter, 4);
+    let num_frames = graph.render_offline(&[], &mut [&mut output], 16).unwrap();
+    canceller.join().unwrap();
+
+    assert!(num_frames < output.len());
+    assert!(num_frames >= 3 * 16 && num_frames % 16 == 0);
+    assert!(output[..num_frames].iter().all(|sample| *sample == 0.5));
+    assert!(output[num_frames..].iter().all(|sample| *sample == 0.0));
+}
+
+#[test]
+fn processors_can_yield_to_a_cancel() {
+    let (graph, counter, _edges) = bounce(true);
+    let mut output = vec![1.0; 64];
+    let canceller = cancel_after(&graph, &counter, 1);
+    let num_frames = graph.render_offline(&[], &mut [&mut output], 16).unwrap();
+    canceller.join().unwrap();
+
+    assert_eq!(num_frames, 0);
+    assert_eq!(counter.load(Ordering::SeqCst), 1);
+    assert_eq!(output, [0.0; 64]);
+}
+
+#[test]
+fn cancelling_between_renders_does_nothing() {
+    let (graph, _counter, _edges) = bounce(false);
+    graph.renderer().unwrap().cancel();
+    let mut output = vec![0.0; 64];
+    let num_frames = graph.render_offline(&[], &mut [&mut output], 16).unwrap();
+    assert_eq!(num_frames, 64);
+    assert_eq!(output, [0.5; 64]);
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use common::{Sine, Sum};
+use tesi_graph::{edge::Edge, node, Error, Graph, Options};
+
+#[test]
+fn capture_records_every_node() {
+    // The example's graph: two sines mixed by a sum node into the output.
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 2,
+        num_workers: 2,
+    });
+    let sine = node::Options {
+        audio_inputs: vec![],
+        audio_outputs: vec![2],
+    };
+    let sine440 = node::Node::new(&graph, sine.clone(), Sine::new(440.0));
+    let sine880 = node::Node::new(&graph, sine, Sine::new(880.0));
+    let sum = node::Node::new(
+        &graph,
+        node::Options {
+            audio_inputs: vec![2, 2],
+            audio_outputs: vec![2],
+        },
+        Sum,
+    );
+    let _edges = [
+        Edge::new(&graph, &sine440, 0, &sum, 0).unwrap(),
+        Edge::new(&graph, &sine880, 0, &sum, 1).unwrap(),
+        Edge::new(&graph, &sum, 0, &graph.output_node(), 0).unwrap(),
+    ];
+    graph.commit_changes().unwrap();
+
+    let mut renderer = graph.renderer().unwrap();
+    assert!(matches!(
+        renderer.capture_block(),
+        Err(Error::Uninitialized)
+    ));
+    renderer.initialize(48e3, 128);
+    let capture = renderer.capture_block().unwrap();
+    let ids = [sine440.id(), sine880.id(), sum.id()];
+    assert!(ids.iter().all(|id| capture.nodes().any(|node| node == *id)));
+
+    let a = capture.output(sine440.id(), 0).unwrap();
+    let b = capture.output(sine880.id(), 0).unwrap();
+    let mixed = capture.output(sum.id(), 0).unwrap();
+    assert!(a[0].iter().any(|sample| *sample != 0.0));
+    assert_ne!(a, b);
+    for ((a, b), mixed) in a.iter().zip(b).zip(mixed) {
+        assert_eq!(mixed.len(), 128);
+        let expected = a.iter().zip(b).map(|(a, b)| a + b).collect::<Vec<_>>();
+        assert_eq!(*mixed, expected);
+    }
+    assert!(capture.output(sum.id(), 1).is_none());
+}
@@ -0,0 +1,225 @@
+//! Processors and helpers shared by the graph tests.
+#![allow(dead_code)]
+
+use std::collections::VecDeque;
+
+use tesi_graph::{bus::kernels, node, proc, Renderer};
+
+/// Mono inputs and outputs.
+pub fn options(num_inputs: usize, num_outputs: usize) -> node::Options {
+    node::Options {
+        audio_inputs: vec![1; num_inputs],
+        audio_outputs: vec![1; num_outputs],
+    }
+}
+
+/// A sine oscillator writing the same signal to every channel of its first output.
+pub struct Sine {
+    phase: f32,
+    freq: f32,
+    sample_rate: f32,
+}
+
+impl Sine {
+    pub fn new(freq: f32) -> Self {
+        Self {
+            phase: 0.0,
+            freq,
+            sample_rate: 48e3,
+        }
+    }
+}
+
+impl proc::Processor for Sine {
+    fn initialize(&mut self, sample_rate: f64, _max_num_frames: usize) {
+        self.sample_rate = sample_rate as f32;
+    }
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        let output = &mut context.audio_outputs[0];
+        for sample in 0..output.num_frames() {
+            let sine = (self.phase * std::f32::consts::TAU).sin();
+            self.phase = (self.phase + self.freq / self.sample_rate).fract();
+            for channel in output.iter() {
+                channel[sample] = sine;
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.phase = 0.0;
+    }
+}
+
+/// Copies its first input to its first output.
+pub struct Thru;
+
+impl proc::Processor for Thru {
+    fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {}
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        let (inputs, outputs) = context.split_io();
+        for channel in 0..outputs[0].num_channels() {
+            outputs[0][channel].copy_from_slice(&inputs[0][channel]);
+        }
+    }
+
+    fn reset(&mut self) {}
+}
+
+/// Adds up its inputs into its first output, like the example's mixer.
+pub struct Sum;
+
+impl proc::Processor for Sum {
+    fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {}
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        let output = &mut context.audio_outputs[0];
+        output.clear();
+        for input in context.audio_inputs {
+            for channel in 0..input.num_channels() {
+                kernels::add_assign(&mut output[channel], &input[channel]);
+            }
+        }
+    }
+
+    fn reset(&mut self) {}
+}
+
+/// Writes a constant to every channel of every output.
+pub struct Constant(pub f32);
+
+impl proc::Processor for Constant {
+    fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {}
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        for output in context.audio_outputs.iter_mut() {
+            for channel in output.iter() {
+                channel.fill(self.0);
+            }
+        }
+    }
+
+    fn reset(&mut self) {}
+}
+
+/// Counts up from 1, one per frame, on the first channel of its first output.
+#[derive(Default)]
+pub struct Ramp(pub f32);
+
+impl proc::Processor for Ramp {
+    fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {}
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        for sample in context.output(0)[0].iter_mut() {
+            self.0 += 1.0;
+            *sample = self.0;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.0 = 0.0;
+    }
+}
+
+/// A limiter stand-in that delays its first input channel by its lookahead and passes it through.
+pub struct Lookahead {
+    frames: usize,
+    buffer: VecDeque<f32>,
+}
+
+impl Lookahead {
+    pub fn new(frames: usize) -> Self {
+        Self {
+            frames,
+            buffer: vec![0.0; frames].into(),
+        }
+    }
+}
+
+impl proc::Processor for Lookahead {
+    fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {
+        self.reset();
+    }
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        let (inputs, outputs) = context.split_io();
+        for (input, output) in inputs[0][0].iter().zip(outputs[0][0].iter_mut()) {
+            self.buffer.push_back(*input);
+            *output = self.buffer.pop_front().unwrap();
+        }
+    }
+
+    fn reset(&mut self) {
+        self.buffer = vec![0.0; self.frames].into();
+    }
+}
+
+/// Writes a single 1.0 to every channel of its first output on the first frame after a reset.
+#[derive(Default)]
+pub struct Impulse {
+    done: bool,
+}
+
+impl proc::Processor for Impulse {
+    fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {}
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        let output = &mut context.audio_outputs[0];
+        for channel in output.iter() {
+            channel.fill(0.0);
+            if !self.done {
+                channel[0] = 1.0;
+            }
+        }
+        self.done = true;
+    }
+
+    fn reset(&mut self) {
+        self.done = false;
+    }
+}
+
+/// Render `num_blocks` blocks of `block_size` frames with silent inputs, returning each output
+/// channel.
+pub fn render(
+    renderer: &mut Renderer,
+    num_inputs: usize,
+    num_outputs: usize,
+    block_size: usize,
+    num_blocks: usize,
+) -> Vec<Vec<f32>> {
+    let inputs = vec![vec![0.0; block_size]; num_inputs];
+    let input_ptrs = inputs
+        .iter()
+        .map(|input| input.as_ptr())
+        .collect::<Vec<_>>();
+    let mut outputs = vec![vec![]; num_outputs];
+    let mut block = vec![vec![0.0; block_size]; num_outputs];
+    for _ in 0..num_blocks {
+        let mut output_ptrs = block
+            .iter_mut()
+            .map(|output| output.as_mut_ptr())
+            .collect::<Vec<_>>();
+        renderer.render(
+            input_ptrs.as_ptr(),
+            output_ptrs.as_mut_ptr(),
+            num_inputs,
+            num_outputs,
+            block_size,
+        );
+        for (output, block) in outputs.iter_mut().zip(&block) {
+            output.extend_from_slice(block);
+        }
+    }
+    outputs
+}
+
+/// The frequency of a periodic signal, from its rising zero crossings.
+pub fn frequency(signal: &[f32], sample_rate: f64) -> f64 {
+    let crossings = signal
+        .windows(2)
+        .filter(|pair| pair[0] < 0.0 && pair[1] >= 0.0)
+        .count();
+    crossings as f64 * sample_rate / signal.len() as f64
+}
@@ -0,0 +1,177 @@
+mod common;
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, ThreadId},
+    time::{Duration, Instant},
+};
+
+use common::{render, Constant, Sum, Thru};
+use tesi_graph::{edge::Edge, node, proc, Graph, Options};
+
+/// Writes 0.5. Off the audio thread it holds its worker until `probed` is set, so the audio thread
+/// has to process everything else itself.
+struct Stall {
+    audio_thread: ThreadId,
+    probed: Arc<AtomicBool>,
+    on_worker: Arc<AtomicBool>,
+    timed_out: Arc<AtomicBool>,
+}
+
+impl proc::Processor for Stall {
+    fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {}
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        if thread::current().id() != self.audio_thread {
+            self.on_worker.store(true, Ordering::Relaxed);
+            let start = Instant::now();
+            while !self.probed.load(Ordering::Acquire) {
+                if start.elapsed() > Duration::from_secs(5) {
+                    self.timed_out.store(true, Ordering::Relaxed);
+                    break;
+                }
+                thread::yield_now();
+            }
+        }
+        for channel in context.output(0).iter() {
+            channel.fill(0.5);
+        }
+    }
+
+    fn reset(&mut self) {}
+}
+
+/// Writes 0.5 after sleeping for a millisecond, to keep whichever thread processes it busy.
+struct Busy;
+
+impl proc::Processor for Busy {
+    fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {}
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        thread::sleep(Duration::from_millis(1));
+        for channel in context.output(0).iter() {
+            channel.fill(0.5);
+        }
+    }
+
+    fn reset(&mut self) {}
+}
+
+/// Copies its first input to its first output and sets its flag.
+struct Probe(Arc<AtomicBool>);
+
+impl proc::Processor for Probe {
+    fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {}
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        proc::Processor::process(&mut Thru, context);
+        self.0.store(true, Ordering::Release);
+    }
+
+    fn reset(&mut self) {}
+}
+
+#[test]
+fn audio_thread_works_while_workers_are_busy() {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 1,
+    });
+    let options = |num_inputs| node::Options {
+        audio_inputs: vec![1; num_inputs],
+        audio_outputs: vec![1],
+    };
+    let probed = Arc::new(AtomicBool::new(false));
+    let on_worker = Arc::new(AtomicBool::new(false));
+    let timed_out = Arc::new(AtomicBool::new(false));
+
+    // A node that keeps the first thread to pick it up busy, so that the other takes the next
+    // one. That stalls a worker, leaving a source and a sink only the audio thread can process.
+    // The stall ends once the sink has run.
+    let busy = node::Node::new(&graph, options(0), Busy);
+    let stall = node::Node::new(
+        &graph,
+        options(0),
+        Stall {
+            audio_thread: thread::current().id(),
+            probed: probed.clone(),
+            on_worker: on_worker.clone(),
+            timed_out: timed_out.clone(),
+        },
+    );
+    let source = node::Node::new(&graph, options(0), Constant(0.25));
+    let probe = node::Node::new(&graph, options(1), Probe(probed.clone()));
+    graph.output_node().set_summing(0, true).unwrap();
+    let _edges = [
+        Edge::new(&graph, &source, 0, &probe, 0).unwrap(),
+        Edge::new(&graph, &busy, 0, &graph.output_node(), 0).unwrap(),
+        Edge::new(&graph, &stall, 0, &graph.output_node(), 0).unwrap(),
+        Edge::new(&graph, &probe, 0, &graph.output_node(), 0).unwrap(),
+    ];
+    graph.commit_changes().unwrap();
+
+    let mut renderer = graph.renderer().unwrap();
+    renderer.initialize(48e3, 64);
+
+    // Which thread picks up the stalling node is up to the scheduler, so render until the worker
+    // has had it.
+    for _ in 0..1000 {
+        probed.store(false, Ordering::Relaxed);
+        assert_eq!(render(&mut renderer, 0, 1, 64, 1), [[1.25; 64]]);
+        if on_worker.load(Ordering::Relaxed) {
+            break;
+        }
+    }
+    assert!(on_worker.load(Ordering::Relaxed));
+    assert!(!timed_out.load(Ordering::Relaxed));
+}
+
+#[test]
+fn wide_graphs_render_the_same_with_workers() {
+    // 63 sources summed into one node, rendered by the audio thread alone and with workers.
+    let outputs = [0, 3].map(|num_workers| {
+        let graph = Graph::new(Options {
+            num_input_channels: 0,
+            num_output_channels: 1,
+            num_workers,
+        });
+        let sum = node::Node::new(
+            &graph,
+            node::Options {
+                audio_inputs: vec![1; 63],
+                audio_outputs: vec![1],
+            },
+            Sum,
+        );
+        let sources = (0..63)
+            .map(|index| {
+                node::Node::new(
+                    &graph,
+                    node::Options {
+                        audio_inputs: vec![],
+                        audio_outputs: vec![1],
+                    },
+                    Constant(index as f32 / 64.0),
+                )
+            })
+            .collect::<Vec<_>>();
+        let mut edges = sources
+            .iter()
+            .enumerate()
+            .map(|(index, source)| Edge::new(&graph, source, 0, &sum, index).unwrap())
+            .collect::<Vec<_>>();
+        edges.push(Edge::new(&graph, &sum, 0, &graph.output_node(), 0).unwrap());
+        graph.commit_changes().unwrap();
+
+        let mut renderer = graph.renderer().unwrap();
+        renderer.initialize(48e3, 64);
+        render(&mut renderer, 0, 1, 64, 64)
+    });
+    let expected = (0..63).map(|index| index as f32 / 64.0).sum::<f32>();
+    assert!(outputs[0][0].iter().all(|sample| *sample == expected));
+    assert_eq!(outputs[0], outputs[1]);
+}
@@ -0,0 +1,113 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use common::{options, render, Impulse, Thru};
+use tesi_graph::{edge::Edge, node, proc, EdgeInfo, Graph, Options};
+
+/// Appends its first input to a shared buffer.
+struct Recorder(Arc<Mutex<Vec<f32>>>);
+
+impl proc::Processor for Recorder {
+    fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {}
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        self.0
+            .lock()
+            .unwrap()
+            .extend_from_slice(&context.input(0)[0]);
+    }
+
+    fn reset(&mut self) {}
+}
+
+fn mono() -> Graph {
+    Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 0,
+    })
+}
+
+/// The frames at which `signal` is `value`.
+fn positions(signal: &[f32], value: f32) -> Vec<usize> {
+    (0..signal.len())
+        .filter(|index| signal[*index] == value)
+        .collect()
+}
+
+/// The frames of the impulses in a signal that is otherwise silent.
+fn impulses(signal: &[f32]) -> Vec<usize> {
+    assert!(signal.iter().all(|sample| *sample == 0.0 || *sample == 1.0));
+    positions(signal, 1.0)
+}
+
+#[test]
+fn edge_delays_by_exactly_its_frames() {
+    let graph = mono();
+    let impulse = node::Node::new(&graph, options(0, 1), Impulse::default());
+    let thru = node::Node::new(&graph, options(1, 1), Thru);
+    let delayed = Edge::new(&graph, &impulse, 0, &thru, 0).unwrap();
+    let _output = Edge::new(&graph, &thru, 0, &graph.output_node(), 0).unwrap();
+    delayed.set_delay(32);
+    graph.commit_changes().unwrap();
+    assert_eq!(graph.latency(), 32);
+
+    let mut renderer = graph.renderer().unwrap();
+    renderer.initialize(48e3, 16);
+    let output = render(&mut renderer, 0, 1, 16, 4).remove(0);
+    assert_eq!(impulses(&output), [32]);
+}
+
+#[test]
+fn delay_belongs_to_its_edge() {
+    let graph = mono();
+    let first = node::Node::new(&graph, options(0, 1), Impulse::default());
+    let second = node::Node::new(&graph, options(0, 1), Impulse::default());
+    let sum = node::Node::new(&graph, options(1, 1), Thru);
+    sum.set_summing(0, true).unwrap();
+    let delayed = Edge::new(&graph, &first, 0, &sum, 0).unwrap();
+    let _undelayed = Edge::new(&graph, &second, 0, &sum, 0).unwrap();
+    let _output = Edge::new(&graph, &sum, 0, &graph.output_node(), 0).unwrap();
+    delayed.set_delay(32);
+
+    let delays = graph
+        .edges()
+        .filter(|edge| edge.sink == sum.id())
+        .map(|EdgeInfo { source, delay, .. }| (source, delay))
+        .collect::<Vec<_>>();
+    assert_eq!(delays, [(first.id(), 32), (second.id(), 0)]);
+
+    // The undelayed source is compensated to line up with the delayed one, not delayed twice.
+    graph.commit_changes().unwrap();
+    let mut renderer = graph.renderer().unwrap();
+    renderer.initialize(48e3, 16);
+    let output = render(&mut renderer, 0, 1, 16, 4).remove(0);
+    assert_eq!(positions(&output, 2.0), [32]);
+    assert_eq!(output.iter().filter(|sample| **sample != 0.0).count(), 1);
+
+    // Removing the delayed edge drops its delay, the remaining source isn't delayed.
+    drop(delayed);
+    graph.commit_changes().unwrap();
+    assert_eq!(graph.latency(), 0);
+}
+
+#[test]
+fn output_delay_leaves_shared_buffers_alone() {
+    let graph = mono();
+    let impulse = node::Node::new(&graph, options(0, 1), Impulse::default());
+    let recorded = Arc::new(Mutex::new(vec![]));
+    let recorder = node::Node::new(&graph, options(1, 0), Recorder(recorded.clone()));
+
+    // The output node is processed before the recorder, which reads the same source.
+    let delayed = Edge::new(&graph, &impulse, 0, &graph.output_node(), 0).unwrap();
+    let _recorded = Edge::new(&graph, &impulse, 0, &recorder, 0).unwrap();
+    delayed.set_delay(32);
+    graph.commit_changes().unwrap();
+
+    let mut renderer = graph.renderer().unwrap();
+    renderer.initialize(48e3, 16);
+    let output = render(&mut renderer, 0, 1, 16, 4).remove(0);
+    assert_eq!(impulses(&output), [32]);
+    assert_eq!(impulses(&recorded.lock().unwrap()), [0]);
+}
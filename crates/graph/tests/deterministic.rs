@@ -0,0 +1,84 @@
+mod common;
+
+use common::render;
+use tesi_graph::{edge::Edge, node, proc, Graph, Options, Renderer};
+
+/// White noise from an xorshift generator seeded from the context after each reset.
+#[derive(Default)]
+struct Noise(Option<u64>);
+
+impl proc::Processor for Noise {
+    fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {}
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        let state = self.0.get_or_insert(context.seed | 1);
+        for sample in context.output(0)[0].iter_mut() {
+            *state ^= *state << 13;
+            *state ^= *state >> 7;
+            *state ^= *state << 17;
+            *sample = (*state >> 40) as f32 / (1 << 23) as f32 - 1.0;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.0 = None;
+    }
+}
+
+/// Two noise nodes summed into the output, rendered by two workers.
+fn noise() -> (Graph, Renderer, Vec<node::Node>, Vec<Edge>) {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 2,
+    });
+    let output = graph.output_node();
+    output.set_summing(0, true).unwrap();
+    let options = node::Options {
+        audio_inputs: vec![],
+        audio_outputs: vec![1],
+    };
+    let nodes = vec![
+        node::Node::new(&graph, options.clone(), Noise::default()),
+        node::Node::new(&graph, options, Noise::default()),
+    ];
+    let edges = nodes
+        .iter()
+        .map(|node| Edge::new(&graph, node, 0, &output, 0).unwrap())
+        .collect();
+    graph.commit_changes().unwrap();
+    let mut renderer = graph.renderer().unwrap();
+    renderer.initialize(48e3, 64);
+    (graph, renderer, nodes, edges)
+}
+
+fn bounce(renderer: &mut Renderer) -> Vec<f32> {
+    renderer.reset();
+    render(renderer, 0, 1, 64, 8).remove(0)
+}
+
+#[test]
+fn deterministic_bounces_are_bit_exact() {
+    let (_graph, mut renderer, _nodes, _edges) = noise();
+    renderer.set_deterministic(true);
+    let first = bounce(&mut renderer);
+    assert!(first.iter().any(|sample| *sample != 0.0));
+    let second = bounce(&mut renderer);
+    assert_eq!(
+        first.iter().map(|x| x.to_bits()).collect::<Vec<_>>(),
+        second.iter().map(|x| x.to_bits()).collect::<Vec<_>>()
+    );
+
+    // So is a second renderer of the same graph.
+    let (_graph, mut renderer, _nodes, _edges) = noise();
+    renderer.set_deterministic(true);
+    assert_eq!(bounce(&mut renderer), first);
+}
+
+#[test]
+fn other_bounces_are_reseeded() {
+    let (_graph, mut renderer, _nodes, _edges) = noise();
+    let first = bounce(&mut renderer);
+    let second = bounce(&mut renderer);
+    assert_ne!(first, second);
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use common::{render, Constant, Thru};
+use tesi_graph::{edge::Edge, node, Graph, Options};
+
+fn mono() -> Graph {
+    Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 0,
+    })
+}
+
+fn source(graph: &Graph, value: f32) -> node::Node {
+    let options = node::Options {
+        audio_inputs: vec![],
+        audio_outputs: vec![1],
+    };
+    node::Node::new(graph, options, Constant(value))
+}
+
+#[test]
+fn disconnecting_a_node_removes_all_its_edges() {
+    let graph = mono();
+    let sources = [0.25, 0.5].map(|value| source(&graph, value));
+    let thru = node::Node::new(
+        &graph,
+        node::Options {
+            audio_inputs: vec![1],
+            audio_outputs: vec![1],
+        },
+        Thru,
+    );
+    thru.set_summing(0, true).unwrap();
+    let edges = [
+        Edge::new(&graph, &sources[0], 0, &thru, 0).unwrap(),
+        Edge::new(&graph, &sources[1], 0, &thru, 0).unwrap(),
+        Edge::new(&graph, &thru, 0, &graph.output_node(), 0).unwrap(),
+    ];
+    graph.commit_changes().unwrap();
+    let mut renderer = graph.renderer().unwrap();
+    renderer.initialize(48e3, 32);
+    assert_eq!(render(&mut renderer, 0, 1, 32, 1), [[0.75; 32]]);
+
+    assert_eq!(graph.disconnect_node(&thru), 3);
+    assert_eq!(graph.edges().count(), 0);
+    assert_eq!(graph.disconnect_node(&thru), 0);
+    graph.commit_changes().unwrap();
+    assert_eq!(render(&mut renderer, 0, 1, 32, 1), [[0.0; 32]]);
+
+    // The handles outlive their edges, dropping them is harmless.
+    drop(edges);
+    assert_eq!(graph.edges().count(), 0);
+}
+
+#[test]
+fn stale_handles_leave_new_edges_alone() {
+    let graph = mono();
+    let source = source(&graph, 0.5);
+    let output = graph.output_node();
+    let stale = Edge::new(&graph, &source, 0, &output, 0).unwrap();
+    assert_eq!(graph.disconnect_node(&source), 1);
+
+    // Connect the same ports again, then use and drop the old handle.
+    let edge = Edge::new(&graph, &source, 0, &output, 0).unwrap();
+    stale.set_delay(32);
+    drop(stale);
+    let delays = graph.edges().map(|edge| edge.delay).collect::<Vec<_>>();
+    assert_eq!(delays, [0]);
+
+    graph.commit_changes().unwrap();
+    let mut renderer = graph.renderer().unwrap();
+    renderer.initialize(48e3, 32);
+    assert_eq!(render(&mut renderer, 0, 1, 32, 1), [[0.5; 32]]);
+
+    // The new handle still owns its edge.
+    drop(edge);
+    assert_eq!(graph.edges().count(), 0);
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use std::collections::BTreeSet;
+
+use common::{Sine, Thru};
+use tesi_graph::{edge::Edge, node::Node, node::Options as NodeOptions, proc::builtin::gain};
+use tesi_graph::{Graph, Options};
+
+fn stereo(num_inputs: usize) -> NodeOptions {
+    NodeOptions {
+        audio_inputs: vec![2; num_inputs],
+        audio_outputs: vec![2],
+    }
+}
+
+#[test]
+fn dot_declares_nodes_and_edges() {
+    // The topology of the example: two sines summed into a gain.
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 2,
+        num_workers: 0,
+    });
+    let sine440 = Node::new(&graph, stereo(0), Sine::new(440.0));
+    let sine880 = Node::new(&graph, stereo(0), Sine::new(880.0));
+    let sum = Node::new(&graph, stereo(2), Thru);
+    let gain = Node::new(&graph, stereo(1), gain::Processor::new(1.0));
+    let _edges = [
+        Edge::new(&graph, &sine440, 0, &sum, 0).unwrap(),
+        Edge::new(&graph, &sine880, 0, &sum, 1).unwrap(),
+        Edge::new(&graph, &sum, 0, &gain, 0).unwrap(),
+        Edge::new(&graph, &gain, 0, &graph.output_node(), 0).unwrap(),
+    ];
+    let (a, b, s, g) = (sine440.id(), sine880.id(), sum.id(), gain.id());
+
+    let dot = graph.to_dot();
+    let lines = dot.lines().collect::<Vec<_>>();
+    assert_eq!(lines.first(), Some(&"digraph {"));
+    assert_eq!(lines.last(), Some(&"}"));
+
+    // Every statement is either a node or an edge declaration, and edges join declared nodes.
+    let mut nodes = BTreeSet::new();
+    let mut edges = BTreeSet::new();
+    for statement in &lines[1..lines.len() - 1] {
+        let statement = statement.trim().strip_suffix(';').unwrap();
+        let (head, attributes) = statement.split_once(" [label=\"").unwrap();
+        let label = attributes.strip_suffix("\"]").unwrap();
+        assert!(!label.contains('"'));
+        match head.split_once(" -> ") {
+            Some((source, sink)) => edges.insert((source, sink, label.to_owned())),
+            None => nodes.insert(head),
+        };
+    }
+    for (source, sink, _) in &edges {
+        assert!(nodes.contains(source) && nodes.contains(sink));
+    }
+
+    let ids = [0, 1, a, b, s, g];
+    assert_eq!(nodes.len(), ids.len());
+    for id in ids {
+        assert!(dot.contains(&format!("n{id} [label=\"{id}: ")));
+    }
+    assert!(dot.contains(&format!(
+        "n{a} [label=\"{a}: {}\"]",
+        std::any::type_name::<Sine>()
+    )));
+
+    let expected = [
+        (a, s, "0 -> 0"),
+        (b, s, "0 -> 1"),
+        (s, g, "0 -> 0"),
+        (g, 1, "0 -> 0"),
+    ];
+    assert_eq!(edges.len(), expected.len());
+    for (source, sink, ports) in expected {
+        let edge = format!("n{source} -> n{sink} [label=\"{ports} (2ch)\"];");
+        assert!(dot.contains(&edge), "missing {edge} in\n{dot}");
+    }
+}
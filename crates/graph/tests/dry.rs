@@ -0,0 +1,72 @@
+mod common;
+
+use common::{Ramp, Thru};
+use tesi_graph::{edge::Edge, node, proc, Graph, Options};
+
+/// A reverb stand-in: a feedback comb on its wet output, with its dry output passed through.
+struct Verb {
+    last: f32,
+}
+
+impl proc::Processor for Verb {
+    fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {}
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        let (inputs, outputs) = context.split_io();
+        for (input, wet) in inputs[0][0].iter().zip(outputs[0][0].iter_mut()) {
+            self.last = input + 0.5 * self.last;
+            *wet = self.last;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.last = 0.0;
+    }
+
+    fn output_role(&self, output: usize) -> proc::PortRole {
+        match output {
+            1 => proc::PortRole::DryPassthrough,
+            _ => proc::PortRole::Main,
+        }
+    }
+}
+
+#[test]
+fn dry_outputs_carry_the_unprocessed_input() {
+    for num_workers in [0, 2] {
+        let graph = Graph::new(Options {
+            num_input_channels: 0,
+            num_output_channels: 1,
+            num_workers,
+        });
+        let ramp = node::Node::new(&graph, common::options(0, 1), Ramp::default());
+        let verb = node::Node::new(&graph, common::options(1, 2), Verb { last: 0.0 });
+        let dry = node::Node::new(&graph, common::options(1, 1), Thru);
+        let _edges = [
+            Edge::new(&graph, &ramp, 0, &verb, 0).unwrap(),
+            Edge::new(&graph, &verb, 0, &graph.output_node(), 0).unwrap(),
+            Edge::new(&graph, &verb, 1, &dry, 0).unwrap(),
+        ];
+        graph.commit_changes().unwrap();
+        let mut renderer = graph.renderer().unwrap();
+        renderer.initialize(48e3, 8);
+        let capture = renderer.capture_block().unwrap();
+
+        // The sink of the dry output sees the ramp as it is, while the wet output is processed.
+        let input = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0];
+        assert_eq!(capture.output(dry.id(), 0).unwrap()[0], input);
+        let mut last = 0.0;
+        let wet = input.map(|sample| {
+            last = sample + 0.5 * last;
+            last
+        });
+        assert_eq!(capture.output(verb.id(), 0).unwrap()[0], wet);
+        assert_eq!(capture.output(verb.id(), 1).unwrap()[0], input);
+
+        // Bypassed, the dry output still passes the input through.
+        verb.set_bypassed(true);
+        let capture = renderer.capture_block().unwrap();
+        let input = input.map(|sample| sample + 8.0);
+        assert_eq!(capture.output(dry.id(), 0).unwrap()[0], input);
+    }
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use common::{render, Constant};
+use tesi_graph::{
+    edge::Edge,
+    node,
+    proc::{self, builtin::gain, ParamChange},
+    Graph, Options,
+};
+
+/// Like [Constant], but panics on an empty block.
+struct Strict(Constant);
+
+impl proc::Processor for Strict {
+    fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {}
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        assert_ne!(context.num_frames(), 0, "processed an empty block");
+        proc::Processor::process(&mut self.0, context);
+    }
+
+    fn reset(&mut self) {}
+}
+
+#[test]
+fn empty_blocks_are_skipped() {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 2,
+    });
+    let source = node::Node::new(
+        &graph,
+        node::Options {
+            audio_inputs: vec![],
+            audio_outputs: vec![1],
+        },
+        Strict(Constant(1.0)),
+    );
+    let gain = node::Node::new(
+        &graph,
+        node::Options {
+            audio_inputs: vec![1],
+            audio_outputs: vec![1],
+        },
+        gain::Processor::new(1.0),
+    );
+    let _edges = [
+        Edge::new(&graph, &source, 0, &gain, 0).unwrap(),
+        Edge::new(&graph, &gain, 0, &graph.output_node(), 0).unwrap(),
+    ];
+    graph.commit_changes().unwrap();
+    let mut renderer = graph.renderer().unwrap();
+    renderer.initialize(48e3, 32);
+    assert_eq!(render(&mut renderer, 0, 1, 32, 1).remove(0), [1.0; 32]);
+
+    // A change sent before an empty block is delivered with the next one.
+    let change = ParamChange {
+        id: gain::GAIN,
+        time: 0,
+        value: 0.5,
+    };
+    gain.send_param_change(change).unwrap();
+    assert!(render(&mut renderer, 0, 1, 0, 4).remove(0).is_empty());
+    assert_eq!(render(&mut renderer, 0, 1, 32, 1).remove(0), [0.5; 32]);
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use common::Thru;
+use tesi_graph::{edge::Edge, node, Error, Graph, Options};
+
+fn graph() -> Graph {
+    Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 0,
+    })
+}
+
+fn thru(graph: &Graph, num_channels: usize) -> node::Node {
+    let options = node::Options {
+        audio_inputs: vec![num_channels],
+        audio_outputs: vec![num_channels],
+    };
+    node::Node::new(graph, options, Thru)
+}
+
+#[test]
+fn cycles_report_the_path_that_closes_them() {
+    let graph = graph();
+    let nodes = [0, 1, 2, 3].map(|_| thru(&graph, 1));
+    let _chain = nodes
+        .windows(2)
+        .map(|pair| Edge::new(&graph, &pair[0], 0, &pair[1], 0).unwrap())
+        .collect::<Vec<_>>();
+
+    // Closing the loop from the last node to the first goes through the whole chain.
+    let Err(error) = Edge::new(&graph, &nodes[3], 0, &nodes[0], 0) else {
+        panic!("the cycle wasn't detected");
+    };
+    let via = nodes.each_ref().map(node::Node::id);
+    assert!(matches!(&error, Error::CycleDetected { via: path } if *path == via));
+    assert_eq!(
+        error.to_string(),
+        format!("edge would create a cycle through nodes {via:?}")
+    );
+
+    // A node feeding itself is the shortest cycle.
+    let Err(error) = Edge::new(&graph, &nodes[0], 0, &nodes[0], 0) else {
+        panic!("the cycle wasn't detected");
+    };
+    assert!(matches!(&error, Error::CycleDetected { via } if *via == [nodes[0].id()]));
+}
+
+#[test]
+fn connection_errors_name_the_ports() {
+    let graph = graph();
+    let mono = [thru(&graph, 1), thru(&graph, 1)];
+    let stereo = thru(&graph, 2);
+    let _edge = Edge::new(&graph, &mono[0], 0, &mono[1], 0).unwrap();
+
+    let Err(error) = Edge::new(&graph, &stereo, 0, &mono[1], 0) else {
+        panic!("the input was connected twice");
+    };
+    let id = mono[1].id();
+    assert!(matches!(error, Error::AlreadyConnected { node, port: 0 } if node == id));
+    assert_eq!(
+        error.to_string(),
+        format!("input 0 of node {id} is already connected")
+    );
+
+    let Err(error) = Edge::new(&graph, &stereo, 0, &graph.output_node(), 0) else {
+        panic!("mismatched channels were connected");
+    };
+    assert!(matches!(
+        error,
+        Error::BusChannelsMismatched {
+            output: 2,
+            input: 1
+        }
+    ));
+    assert_eq!(
+        error.to_string(),
+        "cannot connect an output with 2 channels to an input with 1"
+    );
+}
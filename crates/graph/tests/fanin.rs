@@ -0,0 +1,63 @@
+mod common;
+
+use common::{render, Constant, Sum};
+use tesi_graph::{edge::Edge, node, Graph, Options};
+
+const NUM_SOURCES: usize = 32;
+
+/// A graph with three workers and `NUM_SOURCES` stereo sources of 1/64 each, summed into the
+/// output both by a summing input and by a mixer node.
+fn graph() -> (Graph, Vec<node::Node>, Vec<Edge>) {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 2,
+        num_workers: 3,
+    });
+    let stereo = |num_inputs| node::Options {
+        audio_inputs: vec![2; num_inputs],
+        audio_outputs: vec![2],
+    };
+    let mixer = node::Node::new(&graph, stereo(NUM_SOURCES), Sum);
+    let mut nodes = (0..2 * NUM_SOURCES)
+        .map(|_| node::Node::new(&graph, stereo(0), Constant(1.0 / 64.0)))
+        .collect::<Vec<_>>();
+    graph.output_node().set_summing(0, true).unwrap();
+    let mut edges = nodes[..NUM_SOURCES]
+        .iter()
+        .map(|source| Edge::new(&graph, source, 0, &graph.output_node(), 0).unwrap())
+        .collect::<Vec<_>>();
+    edges.extend(
+        nodes[NUM_SOURCES..]
+            .iter()
+            .enumerate()
+            .map(|(input, source)| Edge::new(&graph, source, 0, &mixer, input).unwrap()),
+    );
+    edges.push(Edge::new(&graph, &mixer, 0, &graph.output_node(), 0).unwrap());
+    nodes.push(mixer);
+    (graph, nodes, edges)
+}
+
+#[test]
+fn wide_fan_in_renders_with_workers() {
+    let (graph, _nodes, _edges) = graph();
+    graph.commit_changes().unwrap();
+    let mut renderer = graph.renderer().unwrap();
+    renderer.initialize(48e3, 64);
+    let output = render(&mut renderer, 0, 2, 64, 256);
+    assert_eq!(output, [[1.0; 64 * 256], [1.0; 64 * 256]]);
+    assert_eq!(renderer.xruns().count(), 0);
+}
+
+#[test]
+fn pool_size_suffices_with_workers() {
+    let (graph, _nodes, _edges) = graph();
+    graph.configure(48e3, 64).unwrap();
+    graph
+        .commit_changes_with_pool(Vec::with_capacity(graph.pool_size()))
+        .unwrap();
+    let mut renderer = graph.renderer().unwrap();
+    renderer.initialize(48e3, 64);
+    let output = render(&mut renderer, 0, 2, 64, 256);
+    assert_eq!(output, [[1.0; 64 * 256], [1.0; 64 * 256]]);
+    assert_eq!(renderer.xruns().count(), 0);
+}
@@ -0,0 +1,131 @@
+mod common;
+
+use common::{render, Ramp};
+use tesi_graph::{edge::Edge, node, proc, Error, Graph, Options};
+
+/// Multiplies its input by a constant.
+struct Scale(f32);
+
+impl proc::Processor for Scale {
+    fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {}
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        let (inputs, outputs) = context.split_io();
+        for (input, output) in inputs[0][0].iter().zip(outputs[0][0].iter_mut()) {
+            *output = input * self.0;
+        }
+    }
+
+    fn reset(&mut self) {}
+}
+
+fn mono(graph: &Graph, processor: impl proc::Processor + 'static) -> node::Node {
+    let options = node::Options {
+        audio_inputs: vec![1],
+        audio_outputs: vec![1],
+    };
+    node::Node::new(graph, options, processor)
+}
+
+fn ramp(num_frames: usize, scale: f32) -> Vec<f32> {
+    (1..=num_frames).map(|n| n as f32 * scale).collect()
+}
+
+#[test]
+fn one_output_feeds_every_sink() {
+    for num_workers in [0, 2] {
+        let graph = Graph::new(Options {
+            num_input_channels: 0,
+            num_output_channels: 1,
+            num_workers,
+        });
+        let source = node::Node::new(
+            &graph,
+            node::Options {
+                audio_inputs: vec![],
+                audio_outputs: vec![1],
+            },
+            Ramp::default(),
+        );
+        let sinks = [1.0, 2.0, 4.0].map(|scale| mono(&graph, Scale(scale)));
+        let output = graph.output_node();
+        output.set_summing(0, true).unwrap();
+        let _edges = sinks
+            .iter()
+            .flat_map(|sink| {
+                [
+                    Edge::new(&graph, &source, 0, sink, 0).unwrap(),
+                    Edge::new(&graph, sink, 0, &output, 0).unwrap(),
+                ]
+            })
+            .collect::<Vec<_>>();
+        graph.commit_changes().unwrap();
+
+        // Every sink reads the same block, over several blocks as the shared buffer is reused.
+        let mut renderer = graph.renderer().unwrap();
+        renderer.initialize(48e3, 32);
+        let output = render(&mut renderer, 0, 1, 32, 4).remove(0);
+        assert_eq!(output, ramp(128, 7.0), "{num_workers} workers");
+    }
+}
+
+#[test]
+fn removing_one_sink_keeps_the_others() {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 0,
+    });
+    let source = node::Node::new(
+        &graph,
+        node::Options {
+            audio_inputs: vec![],
+            audio_outputs: vec![1],
+        },
+        Ramp::default(),
+    );
+    let sinks = [1.0, 2.0].map(|scale| mono(&graph, Scale(scale)));
+    let output = graph.output_node();
+    output.set_summing(0, true).unwrap();
+    let first = Edge::new(&graph, &source, 0, &sinks[0], 0).unwrap();
+    let _rest = [
+        Edge::new(&graph, &source, 0, &sinks[1], 0).unwrap(),
+        Edge::new(&graph, &sinks[0], 0, &output, 0).unwrap(),
+        Edge::new(&graph, &sinks[1], 0, &output, 0).unwrap(),
+    ];
+    graph.commit_changes().unwrap();
+    let mut renderer = graph.renderer().unwrap();
+    renderer.initialize(48e3, 32);
+    assert_eq!(render(&mut renderer, 0, 1, 32, 1).remove(0), ramp(32, 3.0));
+
+    drop(first);
+    graph.commit_changes().unwrap();
+    renderer.reset();
+    assert_eq!(render(&mut renderer, 0, 1, 32, 1).remove(0), ramp(32, 2.0));
+}
+
+#[test]
+fn cycles_through_any_sink_are_rejected() {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 0,
+    });
+    let nodes = [0, 1, 2].map(|_| mono(&graph, Scale(1.0)));
+    let _edges = [
+        Edge::new(&graph, &nodes[0], 0, &nodes[1], 0).unwrap(),
+        Edge::new(&graph, &nodes[0], 0, &nodes[2], 0).unwrap(),
+    ];
+
+    // The cycle closes through the second sink of the fan-out.
+    assert!(matches!(
+        Edge::new(&graph, &nodes[2], 0, &nodes[0], 0),
+        Err(Error::CycleDetected { .. })
+    ));
+
+    // Fanning out doesn't relax the single connection into an input.
+    assert!(matches!(
+        Edge::new(&graph, &nodes[2], 0, &nodes[1], 0),
+        Err(Error::AlreadyConnected { .. })
+    ));
+}
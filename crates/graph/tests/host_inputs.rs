@@ -0,0 +1,100 @@
+use tesi_graph::{edge::Edge, node, proc, proc::builtin::subgraph, Graph, Options};
+
+/// Copies each of its mono inputs to a channel of its output.
+struct Pair;
+
+impl proc::Processor for Pair {
+    fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {}
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        let (inputs, outputs) = context.split_io();
+        for (channel, input) in inputs.iter().enumerate() {
+            outputs[0][channel].copy_from_slice(&input[0]);
+        }
+    }
+
+    fn reset(&mut self) {}
+}
+
+fn pair_options() -> node::Options {
+    node::Options {
+        audio_inputs: vec![1, 1],
+        audio_outputs: vec![2],
+    }
+}
+
+/// A graph with two mono host inputs, swapped onto the two output channels.
+fn crossed() -> (Graph, Vec<Edge>) {
+    let graph = Graph::new(Options {
+        num_input_channels: 1,
+        num_output_channels: 2,
+        num_workers: 0,
+    });
+    let (port, input) = graph.add_host_input(1);
+    assert_eq!((port, input.id()), (1, graph.input_node().id()));
+    let pair = node::Node::new(&graph, pair_options(), Pair);
+    let edges = vec![
+        Edge::new(&graph, &input, 0, &pair, 1).unwrap(),
+        Edge::new(&graph, &input, port, &pair, 0).unwrap(),
+        Edge::new(&graph, &pair, 0, &graph.output_node(), 0).unwrap(),
+    ];
+    graph.commit_changes().unwrap();
+    (graph, edges)
+}
+
+fn render(graph: &Graph) -> [Vec<f32>; 2] {
+    let inputs = [[0.25; 64], [0.75; 64]];
+    let mut outputs = [vec![0.0; 64], vec![0.0; 64]];
+    let [left, right] = &mut outputs;
+    graph
+        .render_offline(&[&inputs[0], &inputs[1]], &mut [left, right], 16)
+        .unwrap();
+    outputs
+}
+
+#[test]
+fn host_inputs_route_independently() {
+    let (graph, _edges) = crossed();
+    assert_eq!(render(&graph), [vec![0.75; 64], vec![0.25; 64]]);
+}
+
+#[test]
+fn missing_host_channels_read_silence() {
+    // A host that still passes only the channels of the first port.
+    let (graph, _edges) = crossed();
+    let mut renderer = graph.renderer().unwrap();
+    renderer.initialize(48e3, 16);
+    let input = [0.25f32; 16];
+    let mut outputs = [[1.0f32; 16]; 2];
+    let [left, right] = &mut outputs;
+    let mut output_ptrs = [left.as_mut_ptr(), right.as_mut_ptr()];
+    renderer.render(
+        [input.as_ptr()].as_ptr(),
+        output_ptrs.as_mut_ptr(),
+        1,
+        2,
+        16,
+    );
+    assert_eq!(outputs, [[0.0; 16], [0.25; 16]]);
+}
+
+#[test]
+fn subgraph_binds_every_host_input() {
+    let (inner, _inner_edges) = crossed();
+    let outer = Graph::new(Options {
+        num_input_channels: 1,
+        num_output_channels: 2,
+        num_workers: 0,
+    });
+    let (port, input) = outer.add_host_input(1);
+    let options = subgraph::Processor::node_options(&inner);
+    assert_eq!(options.audio_inputs, [1, 1]);
+    let wrapper = node::Node::new(&outer, options, subgraph::Processor::new(&inner).unwrap());
+    let _edges = [
+        Edge::new(&outer, &input, 0, &wrapper, 0).unwrap(),
+        Edge::new(&outer, &input, port, &wrapper, 1).unwrap(),
+        Edge::new(&outer, &wrapper, 0, &outer.output_node(), 0).unwrap(),
+    ];
+    outer.commit_changes().unwrap();
+    assert_eq!(render(&outer), [vec![0.75; 64], vec![0.25; 64]]);
+}
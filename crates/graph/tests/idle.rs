@@ -0,0 +1,57 @@
+mod common;
+
+#[cfg(target_os = "linux")]
+use std::time::Duration;
+
+use common::{render, Sine};
+use tesi_graph::{edge::Edge, node, Graph, IdlePolicy, Options, Renderer};
+
+/// Render a sine with two workers that idle as `policy` says, returning the output and the
+/// renderer.
+fn sine(policy: IdlePolicy) -> (Vec<f32>, Renderer) {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 2,
+    });
+    let options = node::Options {
+        audio_inputs: vec![],
+        audio_outputs: vec![1],
+    };
+    let sine = node::Node::new(&graph, options, Sine::new(440.0));
+    let _edge = Edge::new(&graph, &sine, 0, &graph.output_node(), 0).unwrap();
+    graph.commit_changes().unwrap();
+    let mut renderer = graph.renderer().unwrap();
+    renderer.set_idle_policy(policy);
+    renderer.initialize(48e3, 64);
+    (render(&mut renderer, 0, 1, 64, 64).remove(0), renderer)
+}
+
+/// The CPU time used by this process so far.
+#[cfg(target_os = "linux")]
+fn cpu_time() -> Duration {
+    // utime and stime, in clock ticks of 10ms, come 12 and 13 fields after the command name.
+    let stat = std::fs::read_to_string("/proc/self/stat").unwrap();
+    let fields = stat[stat.rfind(')').unwrap() + 2..]
+        .split(' ')
+        .collect::<Vec<_>>();
+    let ticks = fields[11].parse::<u64>().unwrap() + fields[12].parse::<u64>().unwrap();
+    Duration::from_millis(ticks * 10)
+}
+
+#[test]
+fn parked_workers_render_the_same_without_using_cpu() {
+    let (spun, renderer) = sine(IdlePolicy::Spin);
+    drop(renderer);
+    let (parked, _renderer) = sine(IdlePolicy::Park);
+    assert_eq!(spun, parked);
+
+    // Spinning workers would keep at least one core busy while the renderer is idle.
+    #[cfg(target_os = "linux")]
+    {
+        let start = cpu_time();
+        std::thread::sleep(Duration::from_millis(500));
+        let used = cpu_time() - start;
+        assert!(used < Duration::from_millis(100), "{used:?}");
+    }
+}
@@ -0,0 +1,129 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use common::{render, Constant};
+use tesi_graph::{
+    edge::Edge,
+    node,
+    proc::{self, builtin::gain},
+    Graph, Options,
+};
+
+/// The configurations an [Initialized] processor was initialized with.
+type Calls = Arc<Mutex<Vec<(f64, usize)>>>;
+
+/// Records every call to initialize and writes its buffer size to its first output.
+struct Initialized(Calls);
+
+impl proc::Processor for Initialized {
+    fn initialize(&mut self, sample_rate: f64, max_num_frames: usize) {
+        self.0.lock().unwrap().push((sample_rate, max_num_frames));
+    }
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        let max_num_frames = self.0.lock().unwrap().last().map_or(0, |call| call.1);
+        for channel in context.output(0).iter() {
+            channel.fill(max_num_frames as f32);
+        }
+    }
+
+    fn reset(&mut self) {}
+}
+
+fn source() -> node::Options {
+    node::Options {
+        audio_inputs: vec![],
+        audio_outputs: vec![1],
+    }
+}
+
+#[test]
+fn current_config_follows_the_renderer() {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 0,
+    });
+    graph.commit_changes().unwrap();
+    assert_eq!(graph.current_sample_rate(), 0.0);
+    assert_eq!(graph.current_max_frames(), 0);
+
+    // The renderer is initialized on its own thread, as a host's audio thread would.
+    let mut renderer = graph.renderer().unwrap();
+    let mut renderer = std::thread::spawn(move || {
+        renderer.initialize(44100.0, 256);
+        renderer
+    })
+    .join()
+    .unwrap();
+    assert_eq!(graph.current_sample_rate(), 44100.0);
+    assert_eq!(graph.current_max_frames(), 256);
+
+    // The host changes the configuration.
+    renderer.initialize(48000.0, 512);
+    assert_eq!(graph.current_sample_rate(), 48000.0);
+    assert_eq!(graph.current_max_frames(), 512);
+}
+
+#[test]
+fn nodes_added_later_are_initialized_on_commit() {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 0,
+    });
+    graph.output_node().set_summing(0, true).unwrap();
+
+    // Nodes committed before the renderer is initialized are left to it.
+    let early_calls = Calls::default();
+    let early = node::Node::new(&graph, source(), Initialized(early_calls.clone()));
+    let _early_edge = Edge::new(&graph, &early, 0, &graph.output_node(), 0).unwrap();
+    graph.commit_changes().unwrap();
+    assert!(early_calls.lock().unwrap().is_empty());
+    let mut renderer = graph.renderer().unwrap();
+    renderer.initialize(48e3, 32);
+    assert_eq!(*early_calls.lock().unwrap(), [(48e3, 32)]);
+
+    // Later ones are initialized once, by the commit that adds them.
+    let late_calls = Calls::default();
+    let late = node::Node::new(&graph, source(), Initialized(late_calls.clone()));
+    let _late_edge = Edge::new(&graph, &late, 0, &graph.output_node(), 0).unwrap();
+    graph.commit_changes().unwrap();
+    graph.commit_changes().unwrap();
+    assert_eq!(*late_calls.lock().unwrap(), [(48e3, 32)]);
+    assert_eq!(*early_calls.lock().unwrap(), [(48e3, 32)]);
+    assert_eq!(render(&mut renderer, 0, 1, 32, 1), [[64.0; 32]]);
+
+    // And again with everything else when the host changes the configuration.
+    renderer.initialize(44.1e3, 16);
+    assert_eq!(*late_calls.lock().unwrap(), [(48e3, 32), (44.1e3, 16)]);
+}
+
+#[test]
+fn gain_added_after_the_renderer_renders() {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 0,
+    });
+    graph.commit_changes().unwrap();
+    let mut renderer = graph.renderer().unwrap();
+    renderer.initialize(48e3, 32);
+
+    let constant = node::Node::new(&graph, source(), Constant(1.0));
+    let fader = node::Node::new(
+        &graph,
+        node::Options {
+            audio_inputs: vec![1],
+            audio_outputs: vec![1],
+        },
+        gain::Processor::new(0.5),
+    );
+    let _edges = [
+        Edge::new(&graph, &constant, 0, &fader, 0).unwrap(),
+        Edge::new(&graph, &fader, 0, &graph.output_node(), 0).unwrap(),
+    ];
+    graph.commit_changes().unwrap();
+    assert_eq!(render(&mut renderer, 0, 1, 32, 1), [[0.5; 32]]);
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use common::{render, Constant};
+use tesi_graph::{
+    edge::Edge,
+    node,
+    proc::{self, cpu_features},
+    Graph, Options,
+};
+
+type Kernel = fn(&[f32], &mut [f32]);
+
+fn double_scalar(input: &[f32], output: &mut [f32]) {
+    for (x, y) in input.iter().zip(output) {
+        *y = 2.0 * x;
+    }
+}
+
+fn double_wide(input: &[f32], output: &mut [f32]) {
+    let mut inputs = input.chunks_exact(4);
+    let mut outputs = output.chunks_exact_mut(4);
+    for (x, y) in (&mut inputs).zip(&mut outputs) {
+        let x: [f32; 4] = x.try_into().unwrap();
+        y.copy_from_slice(&x.map(|x| 2.0 * x));
+    }
+    double_scalar(inputs.remainder(), outputs.into_remainder());
+}
+
+/// Doubles its input with a kernel picked from the CPU features on initialize.
+struct Double {
+    kernel: Option<Kernel>,
+}
+
+impl proc::Processor for Double {
+    fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {
+        let features = cpu_features();
+        self.kernel = Some(if features.sse2 || features.neon {
+            double_wide
+        } else {
+            double_scalar
+        });
+    }
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        let kernel = self.kernel.unwrap();
+        for (input, output) in context.zip_channels() {
+            kernel(input, output);
+        }
+    }
+
+    fn reset(&mut self) {}
+}
+
+#[test]
+fn detection_is_consistent() {
+    let features = cpu_features();
+    assert!((0..8).all(|_| cpu_features() == features));
+}
+
+#[test]
+fn processors_run_the_selected_kernel() {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 0,
+    });
+    let source = node::Node::new(
+        &graph,
+        node::Options {
+            audio_inputs: vec![],
+            audio_outputs: vec![1],
+        },
+        Constant(0.25),
+    );
+    let double = node::Node::new(
+        &graph,
+        node::Options {
+            audio_inputs: vec![1],
+            audio_outputs: vec![1],
+        },
+        Double { kernel: None },
+    );
+    let _edges = [
+        Edge::new(&graph, &source, 0, &double, 0).unwrap(),
+        Edge::new(&graph, &double, 0, &graph.output_node(), 0).unwrap(),
+    ];
+    graph.commit_changes().unwrap();
+
+    // An odd block size runs the remainder of the wide kernel too.
+    let mut renderer = graph.renderer().unwrap();
+    renderer.initialize(48e3, 30);
+    assert_eq!(render(&mut renderer, 0, 1, 30, 2).remove(0), [0.5; 60]);
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use common::{options, render, Impulse, Lookahead};
+use tesi_graph::{edge::Edge, node, Graph, Options};
+
+fn lookahead(graph: &Graph, latency: usize) -> node::Node {
+    let node = node::Node::new(graph, options(1, 1), Lookahead::new(latency));
+    node.latency_changed(latency);
+    node
+}
+
+#[test]
+fn parallel_paths_are_aligned_to_the_longest() {
+    for num_workers in [0, 2] {
+        let graph = Graph::new(Options {
+            num_input_channels: 0,
+            num_output_channels: 1,
+            num_workers,
+        });
+        let impulse = node::Node::new(&graph, options(0, 1), Impulse::default());
+        let output = graph.output_node();
+        output.set_summing(0, true).unwrap();
+
+        // Three paths to the output: through two nodes in series, through one node with their
+        // combined latency, and straight through.
+        let series = [lookahead(&graph, 16), lookahead(&graph, 32)];
+        let parallel = lookahead(&graph, 48);
+        let _edges = [
+            Edge::new(&graph, &impulse, 0, &series[0], 0).unwrap(),
+            Edge::new(&graph, &series[0], 0, &series[1], 0).unwrap(),
+            Edge::new(&graph, &series[1], 0, &output, 0).unwrap(),
+            Edge::new(&graph, &impulse, 0, &parallel, 0).unwrap(),
+            Edge::new(&graph, &parallel, 0, &output, 0).unwrap(),
+            Edge::new(&graph, &impulse, 0, &output, 0).unwrap(),
+        ];
+        graph.commit_changes().unwrap();
+        assert_eq!(graph.latency(), 48);
+
+        let mut renderer = graph.renderer().unwrap();
+        renderer.initialize(48e3, 32);
+        let mut expected = vec![0.0; 4 * 32];
+        expected[48] = 3.0;
+        let output = render(&mut renderer, 0, 1, 32, 4).remove(0);
+        assert_eq!(output, expected, "{num_workers} workers");
+    }
+}
+
+#[test]
+fn reported_latency_applies_on_the_next_commit() {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 0,
+    });
+    let impulse = node::Node::new(&graph, options(0, 1), Impulse::default());
+    let delayed = node::Node::new(&graph, options(1, 1), Lookahead::new(40));
+    let output = graph.output_node();
+    output.set_summing(0, true).unwrap();
+    let _edges = [
+        Edge::new(&graph, &impulse, 0, &delayed, 0).unwrap(),
+        Edge::new(&graph, &delayed, 0, &output, 0).unwrap(),
+        Edge::new(&graph, &impulse, 0, &output, 0).unwrap(),
+    ];
+    graph.commit_changes().unwrap();
+    assert_eq!(graph.latency(), 0);
+
+    // Until the latency is reported, the two paths are misaligned.
+    let mut renderer = graph.renderer().unwrap();
+    renderer.initialize(48e3, 32);
+    let output = render(&mut renderer, 0, 1, 32, 4).remove(0);
+    let mut expected = vec![0.0; 4 * 32];
+    expected[0] = 1.0;
+    expected[40] = 1.0;
+    assert_eq!(output, expected);
+
+    delayed.latency_changed(40);
+    assert_eq!(graph.latency(), 0);
+    graph.commit_changes().unwrap();
+    assert_eq!(graph.latency(), 40);
+
+    renderer.reset();
+    let output = render(&mut renderer, 0, 1, 32, 4).remove(0);
+    let mut expected = vec![0.0; 4 * 32];
+    expected[40] = 2.0;
+    assert_eq!(output, expected);
+}
@@ -0,0 +1,109 @@
+mod common;
+
+use common::{render, Impulse, Lookahead, Thru};
+use tesi_graph::{edge::Edge, node, Graph, Options};
+
+const LATENCY: usize = 64;
+
+/// An impulse mixed into the output directly and through a meter that watches a node with
+/// latency, connected with `connect`.
+fn metered(
+    connect: fn(&Graph, &node::Node, usize, &node::Node, usize) -> Edge,
+) -> (Graph, Vec<Edge>) {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 0,
+    });
+    let options = node::Options {
+        audio_inputs: vec![1],
+        audio_outputs: vec![1],
+    };
+    let source = node::Node::new(
+        &graph,
+        node::Options {
+            audio_inputs: vec![],
+            audio_outputs: vec![1],
+        },
+        Impulse::default(),
+    );
+    let lookahead = node::Node::new(&graph, options.clone(), Lookahead::new(LATENCY));
+    lookahead.latency_changed(LATENCY);
+    let meter = node::Node::new(&graph, options, Thru);
+    let output = graph.output_node();
+    output.set_summing(0, true).unwrap();
+    let edges = vec![
+        Edge::new(&graph, &source, 0, &lookahead, 0).unwrap(),
+        connect(&graph, &lookahead, 0, &meter, 0),
+        Edge::new(&graph, &meter, 0, &output, 0).unwrap(),
+        Edge::new(&graph, &source, 0, &output, 0).unwrap(),
+    ];
+    graph.commit_changes().unwrap();
+    (graph, edges)
+}
+
+fn impulse_response(graph: &Graph) -> Vec<f32> {
+    let mut renderer = graph.renderer().unwrap();
+    renderer.initialize(48e3, 32);
+    render(&mut renderer, 0, 1, 32, 4).remove(0)
+}
+
+#[test]
+fn edges_are_compensated() {
+    let (graph, _edges) = metered(|graph, source, output, sink, input| {
+        Edge::new(graph, source, output, sink, input).unwrap()
+    });
+    assert_eq!(graph.latency(), LATENCY);
+    let mut expected = vec![0.0; 4 * 32];
+    expected[LATENCY] = 2.0;
+    assert_eq!(impulse_response(&graph), expected);
+}
+
+#[test]
+fn monitors_are_not_compensated() {
+    let (graph, _edges) = metered(|graph, source, output, sink, input| {
+        Edge::new_monitor(graph, source, output, sink, input).unwrap()
+    });
+    assert_eq!(graph.latency(), 0);
+    let mut expected = vec![0.0; 4 * 32];
+    expected[0] = 1.0;
+    expected[LATENCY] = 1.0;
+    assert_eq!(impulse_response(&graph), expected);
+}
+
+#[test]
+fn monitors_are_restored() {
+    let (graph, _edges) = metered(|graph, source, output, sink, input| {
+        Edge::new_monitor(graph, source, output, sink, input).unwrap()
+    });
+    let state = graph.save_topology();
+    assert_eq!(state.edges.iter().filter(|edge| edge.monitor).count(), 1);
+
+    let restored = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 0,
+    });
+    let _restored = restored
+        .restore_topology(&state, |graph, saved| {
+            let options = node::Options {
+                audio_inputs: saved.audio_inputs.clone(),
+                audio_outputs: saved.audio_outputs.clone(),
+            };
+            if saved.audio_inputs.is_empty() {
+                node::Node::new(graph, options, Impulse::default())
+            } else if saved.latency > 0 {
+                node::Node::new(graph, options, Lookahead::new(saved.latency))
+            } else {
+                node::Node::new(graph, options, Thru)
+            }
+        })
+        .unwrap();
+    restored.commit_changes().unwrap();
+    assert_eq!(
+        restored.edges().collect::<Vec<_>>(),
+        graph.edges().collect::<Vec<_>>()
+    );
+    assert_eq!(restored.latency(), 0);
+    assert_eq!(impulse_response(&restored), impulse_response(&graph));
+}
@@ -0,0 +1,52 @@
+mod common;
+
+use common::{render, Sine};
+use tesi_graph::{edge::Edge, node, proc, Graph, Options};
+
+/// Emits a single NaN in an otherwise silent block.
+struct Nan;
+
+impl proc::Processor for Nan {
+    fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {}
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        let output = &mut context.audio_outputs[0];
+        output.clear();
+        output[0][3] = f32::NAN;
+    }
+
+    fn reset(&mut self) {}
+}
+
+#[test]
+fn nan_is_attributed_to_its_node() {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 0,
+    });
+    let source = node::Options {
+        audio_inputs: vec![],
+        audio_outputs: vec![1],
+    };
+    let sine = node::Node::new(&graph, source.clone(), Sine::new(1000.0));
+    let nan = node::Node::new(&graph, source, Nan);
+    let output = graph.output_node();
+    output.set_summing(0, true).unwrap();
+    let _edges = [
+        Edge::new(&graph, &sine, 0, &output, 0).unwrap(),
+        Edge::new(&graph, &nan, 0, &output, 0).unwrap(),
+    ];
+    graph.commit_changes().unwrap();
+
+    let mut renderer = graph.renderer().unwrap();
+    renderer.initialize(48e3, 16);
+    render(&mut renderer, 0, 1, 16, 1);
+    assert_eq!(renderer.nan_detected(), None, "the check is off by default");
+
+    renderer.set_nan_check(true);
+    let output = render(&mut renderer, 0, 1, 16, 1).remove(0);
+    assert_eq!(renderer.nan_detected(), Some(nan.id()));
+    assert_eq!(renderer.nan_detected(), None);
+    assert!(output.iter().all(|sample| sample.is_finite()));
+}
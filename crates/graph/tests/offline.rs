@@ -0,0 +1,117 @@
+mod common;
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use common::{Sine, Sum};
+use tesi_graph::{edge::Edge, node, proc, Error, Graph, Options};
+
+/// Writes ones to its output and counts how often it's initialized.
+struct Initialized(Arc<AtomicUsize>);
+
+impl proc::Processor for Initialized {
+    fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        for channel in context.output(0).iter() {
+            channel.fill(1.0);
+        }
+    }
+
+    fn reset(&mut self) {}
+}
+
+fn stereo(num_workers: usize) -> Graph {
+    Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 2,
+        num_workers,
+    })
+}
+
+#[test]
+fn offline_renders_are_periodic_and_reproducible() {
+    // The example's graph, at frequencies whose period is a whole number of frames.
+    let graph = stereo(2);
+    let sine = node::Options {
+        audio_inputs: vec![],
+        audio_outputs: vec![2],
+    };
+    let sines = [375.0, 750.0].map(|freq| node::Node::new(&graph, sine.clone(), Sine::new(freq)));
+    let sum = node::Node::new(
+        &graph,
+        node::Options {
+            audio_inputs: vec![2, 2],
+            audio_outputs: vec![2],
+        },
+        Sum,
+    );
+    let _edges = [
+        Edge::new(&graph, &sines[0], 0, &sum, 0).unwrap(),
+        Edge::new(&graph, &sines[1], 0, &sum, 1).unwrap(),
+        Edge::new(&graph, &sum, 0, &graph.output_node(), 0).unwrap(),
+    ];
+    graph.commit_changes().unwrap();
+
+    // One second, in blocks that don't divide it.
+    let bounce = || {
+        let mut channels = vec![vec![0.0; 48000]; 2];
+        let mut outputs = channels
+            .iter_mut()
+            .map(Vec::as_mut_slice)
+            .collect::<Vec<_>>();
+        assert_eq!(graph.render_offline(&[], &mut outputs, 100).unwrap(), 48000);
+        channels
+    };
+    let output = bounce();
+    assert_eq!(output[0], output[1]);
+    const PERIOD: usize = 128;
+    assert!(output[0].iter().any(|sample| sample.abs() > 1.0));
+    for (a, b) in output[0].iter().zip(&output[0][PERIOD..]) {
+        assert!((a - b).abs() < 1e-3, "{a} != {b}");
+    }
+    assert_eq!(bounce(), output);
+}
+
+#[test]
+fn mismatched_channels_fail_before_initializing() {
+    let graph = stereo(0);
+    let initialized = Arc::new(AtomicUsize::new(0));
+    let node = node::Node::new(
+        &graph,
+        node::Options {
+            audio_inputs: vec![],
+            audio_outputs: vec![2],
+        },
+        Initialized(initialized.clone()),
+    );
+    let _edge = Edge::new(&graph, &node, 0, &graph.output_node(), 0).unwrap();
+    graph.commit_changes().unwrap();
+
+    let mut channels = vec![vec![0.0; 64]; 3];
+    let mut outputs = channels
+        .iter_mut()
+        .map(Vec::as_mut_slice)
+        .collect::<Vec<_>>();
+    let input = [0.0; 64];
+    for (inputs, num_outputs, block_size) in [
+        (&[][..], 1, 32),
+        (&[][..], 3, 32),
+        (&[&input[..]][..], 2, 32),
+        (&[][..], 2, 0),
+    ] {
+        assert!(matches!(
+            graph.render_offline(inputs, &mut outputs[..num_outputs], block_size),
+            Err(Error::InvalidConfiguration)
+        ));
+    }
+    assert_eq!(initialized.load(Ordering::Relaxed), 0);
+
+    assert!(graph.render_offline(&[], &mut outputs[..2], 32).is_ok());
+    assert_eq!(initialized.load(Ordering::Relaxed), 1);
+    assert_eq!(channels[..2], [[1.0; 64], [1.0; 64]]);
+}
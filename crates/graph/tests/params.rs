@@ -0,0 +1,656 @@
+mod common;
+
+use common::{render, Constant, Ramp, Thru};
+use tesi_graph::{
+    edge::Edge,
+    node,
+    proc::{
+        self,
+        builtin::{dynamics, gain},
+        params::{Flags, Parameter, ParameterTree},
+        ParamChange,
+    },
+    Error, Graph, Options, ParamEvent, ParamOrigin, PresetReport, PRESET_VERSION,
+};
+
+fn gain_node(graph: &Graph) -> node::Node {
+    let options = node::Options {
+        audio_inputs: vec![1],
+        audio_outputs: vec![1],
+    };
+    node::Node::new(graph, options, gain::Processor::new(1.0))
+}
+
+#[test]
+fn param_value_follows_sent_changes() {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 0,
+        num_workers: 0,
+    });
+    let gain = gain_node(&graph);
+    assert_eq!(gain.param_value(gain::GAIN), None);
+
+    let change = ParamChange {
+        id: gain::GAIN,
+        time: 0,
+        value: 0.25,
+    };
+    gain.send_param_change(change).unwrap();
+    assert_eq!(gain.param_value(gain::GAIN), Some(0.25));
+
+    // Loading "Mute" sets the gain like any other change.
+    gain.load_preset(2).unwrap();
+    assert_eq!(gain.param_value(gain::GAIN), Some(0.0));
+
+    // Values are kept per node, and for any id sent, described or not.
+    let other = gain_node(&graph);
+    let change = ParamChange {
+        id: 5000,
+        time: 0,
+        value: 3.0,
+    };
+    other.send_param_change(change).unwrap();
+    assert_eq!(other.param_value(gain::GAIN), None);
+    assert_eq!(other.param_value(5000), Some(3.0));
+    assert_eq!(gain.param_value(5000), None);
+}
+
+#[test]
+fn parameter_lists_hold_current_values() {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 0,
+        num_workers: 0,
+    });
+    let gate = node::Node::new(
+        &graph,
+        node::Options {
+            audio_inputs: vec![1],
+            audio_outputs: vec![1],
+        },
+        dynamics::Processor::new(dynamics::Options {
+            detector: dynamics::Detector::Peak,
+            mode: dynamics::Mode::Gate,
+            threshold_db: -40.0,
+            ratio: 2.0,
+            attack: 0.01,
+            release: 0.1,
+            sidechain: false,
+        }),
+    );
+    let change = ParamChange {
+        id: dynamics::RELEASE,
+        time: 0,
+        value: 0.5,
+    };
+    gate.send_param_change(change).unwrap();
+
+    // Parameters that haven't been set hold their defaults.
+    let list = gate.parameter_list();
+    let values = list
+        .iter()
+        .map(|param| (param.id, param.value))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        values,
+        [
+            (dynamics::THRESHOLD, -40.0),
+            (dynamics::RATIO, 2.0),
+            (dynamics::ATTACK, 0.01),
+            (dynamics::RELEASE, 0.5),
+        ]
+    );
+    let threshold = &list[0];
+    assert_eq!(threshold.name, "Threshold");
+    assert_eq!((threshold.min, threshold.max), (-80.0, 0.0));
+    assert_eq!(threshold.unit, "dB");
+    assert!(threshold.flags.contains(Flags::AUTOMATABLE));
+
+    // Processors without parameters list none.
+    let thru = node::Node::new(
+        &graph,
+        node::Options {
+            audio_inputs: vec![1],
+            audio_outputs: vec![1],
+        },
+        Thru,
+    );
+    assert!(thru.parameter_list().is_empty());
+}
+
+const CUTOFF: usize = 0;
+const WINDOW: usize = 1;
+
+/// Sets its cutoff to Nyquist and its window to the block size once it knows them.
+#[derive(Default)]
+struct Adaptive {
+    cutoff: f32,
+    window: f32,
+}
+
+impl proc::Processor for Adaptive {
+    fn initialize(&mut self, sample_rate: f64, max_num_frames: usize) {
+        self.cutoff = sample_rate as f32 / 2.0;
+        self.window = max_num_frames as f32;
+    }
+
+    fn process(&mut self, _context: &mut proc::Context<'_>) {}
+
+    fn reset(&mut self) {}
+
+    fn initial_values(&self) -> Vec<(usize, f32)> {
+        vec![(CUTOFF, self.cutoff), (WINDOW, self.window)]
+    }
+}
+
+#[test]
+fn initial_values_are_cached() {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 0,
+    });
+    let adaptive = node::Node::new(&graph, common::options(0, 1), Adaptive::default());
+    graph.commit_changes().unwrap();
+    assert_eq!(adaptive.param_value(CUTOFF), None);
+
+    // Read back without sending any changes.
+    let mut renderer = graph.renderer().unwrap();
+    renderer.initialize(48e3, 64);
+    assert_eq!(adaptive.param_value(CUTOFF), Some(24e3));
+    assert_eq!(adaptive.param_value(WINDOW), Some(64.0));
+
+    // Every initialization reports them again, as do processors added since.
+    renderer.initialize(96e3, 128);
+    assert_eq!(adaptive.param_value(CUTOFF), Some(48e3));
+    assert_eq!(adaptive.param_value(WINDOW), Some(128.0));
+    let later = node::Node::new(&graph, common::options(0, 1), Adaptive::default());
+    assert_eq!(later.param_value(CUTOFF), None);
+    graph.commit_changes().unwrap();
+    assert_eq!(later.param_value(CUTOFF), Some(48e3));
+    assert_eq!(later.param_value(WINDOW), Some(128.0));
+}
+
+#[test]
+fn latched_params_ignore_changes() {
+    let graph = Graph::new(Options {
+        num_input_channels: 1,
+        num_output_channels: 1,
+        num_workers: 0,
+    });
+    let gain = gain_node(&graph);
+    let _edges = [
+        Edge::new(&graph, &graph.input_node(), 0, &gain, 0).unwrap(),
+        Edge::new(&graph, &gain, 0, &graph.output_node(), 0).unwrap(),
+    ];
+    graph.commit_changes().unwrap();
+    let render = |value: f32| {
+        let change = ParamChange {
+            id: gain::GAIN,
+            time: 0,
+            value,
+        };
+        gain.send_param_change(change).unwrap();
+        let input = vec![1.0; 64];
+        let mut output = vec![0.0; 64];
+        graph
+            .render_offline(&[&input], &mut [&mut output], 64)
+            .unwrap();
+        output[63]
+    };
+    assert_eq!(render(0.5), 0.5);
+
+    // Latched, the gain holds through changes and presets.
+    gain.set_param_latch(gain::GAIN, true);
+    assert!(gain.is_param_latched(gain::GAIN));
+    assert_eq!(render(0.25), 0.5);
+    gain.load_preset(2).unwrap();
+    assert_eq!(render(0.25), 0.5);
+    assert_eq!(gain.param_value(gain::GAIN), Some(0.5));
+
+    gain.set_param_latch(gain::GAIN, false);
+    assert!(!gain.is_param_latched(gain::GAIN));
+    assert_eq!(render(0.25), 0.25);
+}
+
+#[test]
+fn params_with_any_id_can_be_latched() {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 0,
+        num_workers: 0,
+    });
+    let gain = gain_node(&graph);
+    let ids = (0..32).map(|n| usize::MAX - n).collect::<Vec<_>>();
+    for id in &ids {
+        gain.set_param_latch(*id, true);
+    }
+    assert!(ids.iter().all(|id| gain.is_param_latched(*id)));
+    assert!(!gain.is_param_latched(gain::GAIN));
+
+    let change = ParamChange {
+        id: usize::MAX,
+        time: 0,
+        value: 1.0,
+    };
+    gain.send_param_change(change).unwrap();
+    assert_eq!(gain.param_value(usize::MAX), None);
+    gain.set_param_latch(usize::MAX, false);
+    gain.send_param_change(change).unwrap();
+    assert_eq!(gain.param_value(usize::MAX), Some(1.0));
+}
+
+#[test]
+fn presets_round_trip() {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 0,
+        num_workers: 0,
+    });
+    let set = |node: &node::Node, value: f32| {
+        let change = ParamChange {
+            id: gain::GAIN,
+            time: 0,
+            value,
+        };
+        node.send_param_change(change).unwrap();
+    };
+    let first = gain_node(&graph);
+    let second = gain_node(&graph);
+    let untouched = gain_node(&graph);
+    set(&first, 0.25);
+    set(&second, 0.5);
+    let preset = graph.export_preset();
+    assert_eq!(preset.version, PRESET_VERSION);
+    let ids = preset.nodes.iter().map(|node| node.id).collect::<Vec<_>>();
+    assert_eq!(ids, [first.id(), second.id()]);
+    assert_eq!(untouched.param_value(gain::GAIN), None);
+
+    set(&first, 1.0);
+    set(&second, 1.0);
+    assert_eq!(
+        graph.import_preset(&preset).unwrap(),
+        PresetReport::default()
+    );
+    assert_eq!(first.param_value(gain::GAIN), Some(0.25));
+    assert_eq!(second.param_value(gain::GAIN), Some(0.5));
+
+    // A removed node is reported, and the rest still get their values. So is a node whose id is
+    // now taken by a different processor.
+    let second_id = second.id();
+    drop(second);
+    set(&first, 1.0);
+    let report = graph.import_preset(&preset).unwrap();
+    assert_eq!(report.missing, [second_id]);
+    assert_eq!(first.param_value(gain::GAIN), Some(0.25));
+    let thru = node::Node::new(
+        &graph,
+        node::Options {
+            audio_inputs: vec![1],
+            audio_outputs: vec![1],
+        },
+        Thru,
+    );
+    assert_eq!(thru.id(), second_id);
+    let report = graph.import_preset(&preset).unwrap();
+    assert_eq!(report.missing, [second_id]);
+    assert_eq!(thru.param_value(gain::GAIN), None);
+}
+
+#[test]
+fn newer_presets_are_rejected() {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 0,
+        num_workers: 0,
+    });
+    let mut preset = graph.export_preset();
+    preset.version += 1;
+    let version = PRESET_VERSION + 1;
+    assert!(matches!(
+        graph.import_preset(&preset),
+        Err(Error::UnsupportedPresetVersion(v)) if v == version
+    ));
+}
+
+#[test]
+fn presets_with_a_repeated_node_are_rejected() {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 0,
+        num_workers: 0,
+    });
+    let gain = gain_node(&graph);
+    let change = ParamChange {
+        id: gain::GAIN,
+        time: 0,
+        value: 0.5,
+    };
+    gain.send_param_change(change).unwrap();
+    let mut preset = graph.export_preset();
+    let repeated = preset.nodes[0].clone();
+    preset.nodes.extend(vec![repeated; 1000]);
+
+    // Nothing is sent, even to a node with room for one copy of its values.
+    assert_eq!(graph.poll_param_changes().len(), 1);
+    assert!(matches!(
+        graph.import_preset(&preset),
+        Err(Error::DuplicatePresetNode(id)) if id == gain.id()
+    ));
+    assert!(graph.poll_param_changes().is_empty());
+}
+
+const CC: usize = 1;
+
+/// Maps the last value of its "CC" parameter onto its gain, like a MIDI learn.
+struct Learn;
+
+impl proc::Processor for Learn {
+    fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {}
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        let cc = context
+            .param_changes
+            .iter()
+            .rev()
+            .find(|change| change.id == CC);
+        if let Some(cc) = cc {
+            context.notify_param_change(ParamChange {
+                id: gain::GAIN,
+                time: cc.time,
+                value: cc.value / 127.0,
+            });
+        }
+        for output in context.audio_outputs.iter_mut() {
+            output.clear();
+        }
+    }
+
+    fn reset(&mut self) {}
+}
+
+#[test]
+fn polled_changes_are_tagged_with_their_origin() {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 0,
+    });
+    let learn = node::Node::new(
+        &graph,
+        node::Options {
+            audio_inputs: vec![],
+            audio_outputs: vec![1],
+        },
+        Learn,
+    );
+    let _edge = Edge::new(&graph, &learn, 0, &graph.output_node(), 0).unwrap();
+    graph.commit_changes().unwrap();
+    assert!(graph.poll_param_changes().is_empty());
+
+    let cc = ParamChange {
+        id: CC,
+        time: 3,
+        value: 127.0,
+    };
+    learn.send_param_change(cc).unwrap();
+    let mut output = vec![0.0; 64];
+    graph.render_offline(&[], &mut [&mut output], 64).unwrap();
+
+    let learned = ParamChange {
+        id: gain::GAIN,
+        time: 3,
+        value: 1.0,
+    };
+    let events = graph.poll_param_changes();
+    let expected = [
+        ParamEvent {
+            node: learn.id(),
+            change: cc,
+            origin: ParamOrigin::Host,
+        },
+        ParamEvent {
+            node: learn.id(),
+            change: learned,
+            origin: ParamOrigin::Processor,
+        },
+    ];
+    assert_eq!(events, expected);
+    assert_eq!(learn.param_value(gain::GAIN), Some(1.0));
+    assert!(graph.poll_param_changes().is_empty());
+}
+
+/// Reports `count` changes of parameter 0 per block, counting up from zero.
+struct Notifier {
+    count: usize,
+    next: usize,
+}
+
+impl proc::Processor for Notifier {
+    fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {}
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        for _ in 0..self.count {
+            context.notify_param_change(ParamChange {
+                id: 0,
+                time: 0,
+                value: self.next as f32,
+            });
+            self.next += 1;
+        }
+        context.silent_outputs = 1;
+    }
+
+    fn reset(&mut self) {}
+}
+
+/// A graph with a notifier and a gain feeding its output.
+fn notifier(num_workers: usize, count: usize) -> (Graph, node::Node, node::Node, Vec<Edge>) {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers,
+    });
+    let notifier = node::Node::new(
+        &graph,
+        node::Options {
+            audio_inputs: vec![],
+            audio_outputs: vec![1],
+        },
+        Notifier { count, next: 0 },
+    );
+    let gain = gain_node(&graph);
+    graph.output_node().set_summing(0, true).unwrap();
+    let edges = vec![
+        Edge::new(&graph, &notifier, 0, &graph.output_node(), 0).unwrap(),
+        Edge::new(&graph, &gain, 0, &graph.output_node(), 0).unwrap(),
+    ];
+    graph.commit_changes().unwrap();
+    (graph, notifier, gain, edges)
+}
+
+#[test]
+fn nodes_poll_their_processors_changes() {
+    for num_workers in [0, 2] {
+        let (graph, notifier, gain, _edges) = notifier(num_workers, 2);
+        let mut renderer = graph.renderer().unwrap();
+        renderer.initialize(48e3, 64);
+        render(&mut renderer, 0, 1, 64, 2);
+
+        let values = notifier
+            .poll_param_changes()
+            .map(|change| change.value)
+            .collect::<Vec<_>>();
+        assert_eq!(values, [0.0, 1.0, 2.0, 3.0]);
+        assert_eq!(notifier.poll_param_changes().count(), 0);
+        assert_eq!(gain.poll_param_changes().count(), 0);
+
+        // Host changes aren't echoed back.
+        let change = ParamChange {
+            id: gain::GAIN,
+            time: 0,
+            value: 0.5,
+        };
+        gain.send_param_change(change).unwrap();
+        render(&mut renderer, 0, 1, 64, 1);
+        assert_eq!(gain.poll_param_changes().count(), 0);
+        assert_eq!(notifier.poll_param_changes().count(), 2);
+    }
+}
+
+#[test]
+fn notifications_past_capacity_drop_the_oldest() {
+    let (graph, notifier, _gain, _edges) = notifier(0, 1000);
+    let mut output = vec![0.0; 64];
+    graph.render_offline(&[], &mut [&mut output], 64).unwrap();
+
+    // The latest values make it, in order.
+    let values = notifier
+        .poll_param_changes()
+        .map(|change| change.value as usize)
+        .collect::<Vec<_>>();
+    assert!(!values.is_empty() && values.len() < 1000);
+    assert_eq!(values, (1000 - values.len()..1000).collect::<Vec<_>>());
+}
+
+#[test]
+fn named_presets_load_through_the_node() {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 0,
+    });
+    let source = node::Node::new(
+        &graph,
+        node::Options {
+            audio_inputs: vec![],
+            audio_outputs: vec![1],
+        },
+        Constant(1.0),
+    );
+    let gain = gain_node(&graph);
+    let _edges = [
+        Edge::new(&graph, &source, 0, &gain, 0).unwrap(),
+        Edge::new(&graph, &gain, 0, &graph.output_node(), 0).unwrap(),
+    ];
+    graph.commit_changes().unwrap();
+
+    // Processors without presets have none.
+    assert_eq!(source.preset_count(), 0);
+    assert_eq!(source.preset_name(0), None);
+    assert!(matches!(
+        source.load_preset(0),
+        Err(Error::InvalidPreset(0))
+    ));
+
+    let names = (0..gain.preset_count())
+        .map(|index| gain.preset_name(index).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["Unity", "-6 dB", "Mute"]);
+    assert_eq!(gain.preset_name(3), None);
+    assert_eq!(gain.current_preset(), None);
+
+    // Loading sends the values for the next block.
+    gain.load_preset(1).unwrap();
+    assert_eq!(gain.current_preset(), Some(1));
+    assert_eq!(gain.param_value(gain::GAIN), Some(0.5));
+    let mut output = vec![0.0; 64];
+    graph.render_offline(&[], &mut [&mut output], 64).unwrap();
+    assert_eq!(output, [0.5; 64]);
+
+    assert!(matches!(gain.load_preset(3), Err(Error::InvalidPreset(3))));
+    assert_eq!(gain.current_preset(), Some(1));
+}
+
+#[test]
+fn presets_that_dont_fit_send_nothing() {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 0,
+        num_workers: 0,
+    });
+    let gain = gain_node(&graph);
+    let change = ParamChange {
+        id: gain::GAIN,
+        time: 0,
+        value: 2.0,
+    };
+    while gain.send_param_change(change).is_ok() {}
+    assert!(matches!(
+        gain.send_param_change(change),
+        Err(Error::TooManyParamChanges)
+    ));
+    assert!(matches!(
+        gain.load_preset(2),
+        Err(Error::TooManyParamChanges)
+    ));
+    assert_eq!(gain.current_preset(), None);
+    assert_eq!(gain.param_value(gain::GAIN), Some(2.0));
+}
+
+const LEVEL: usize = 0;
+
+/// Passes its first input through and reads out its peak over each block.
+struct Meter;
+
+impl proc::Processor for Meter {
+    fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {}
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        let mut peak = 0.0f32;
+        for (input, output) in context.zip_channels() {
+            output.copy_from_slice(input);
+            peak = input
+                .iter()
+                .fold(peak, |peak, sample| peak.max(sample.abs()));
+        }
+        context.set_param_output(LEVEL, peak);
+    }
+
+    fn reset(&mut self) {}
+
+    fn parameters(&self) -> Option<ParameterTree> {
+        let level = Parameter::new(LEVEL, "Level", 0.0, f32::MAX, 0.0).flags(Flags::OUTPUT);
+        Some(ParameterTree::leaf(level))
+    }
+}
+
+#[test]
+fn output_params_are_read_every_block() {
+    for num_workers in [0, 2] {
+        let graph = Graph::new(Options {
+            num_input_channels: 0,
+            num_output_channels: 1,
+            num_workers,
+        });
+        let ramp = node::Node::new(&graph, common::options(0, 1), Ramp::default());
+        let meter = node::Node::new(&graph, common::options(1, 1), Meter);
+        let _edges = [
+            Edge::new(&graph, &ramp, 0, &meter, 0).unwrap(),
+            Edge::new(&graph, &meter, 0, &graph.output_node(), 0).unwrap(),
+        ];
+        graph.commit_changes().unwrap();
+        let mut renderer = graph.renderer().unwrap();
+        renderer.initialize(48e3, 64);
+        assert_eq!(meter.output_value(LEVEL), None);
+
+        // The ramp peaks at the end of each block.
+        for block in 1..=4 {
+            render(&mut renderer, 0, 1, 64, 1);
+            assert_eq!(meter.output_value(LEVEL), Some(64.0 * block as f32));
+        }
+        assert_eq!(meter.parameter_list()[0].value, 256.0);
+
+        // The host can't set the level, and it isn't saved.
+        let change = ParamChange {
+            id: LEVEL,
+            time: 0,
+            value: 1.0,
+        };
+        meter.send_param_change(change).unwrap();
+        assert!(graph.poll_param_changes().is_empty());
+        assert_eq!(meter.output_value(LEVEL), Some(256.0));
+        assert!(graph.export_preset().nodes.is_empty());
+    }
+}
@@ -0,0 +1,139 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use common::{render, Ramp, Thru};
+use tesi_graph::{edge::Edge, node, proc, Graph, Options};
+
+/// Plays `remaining` frames of ones, writing ones over the whole block regardless.
+struct Player {
+    remaining: usize,
+}
+
+impl proc::Processor for Player {
+    fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {}
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        for channel in context.output(0).iter() {
+            channel.fill(1.0);
+        }
+        let num_frames = context.num_frames().min(self.remaining);
+        self.remaining -= num_frames;
+        context.num_frames_produced = num_frames;
+    }
+
+    fn reset(&mut self) {}
+}
+
+/// Records whether its input is silent and copies it to its output.
+struct Probe(Arc<Mutex<Vec<bool>>>);
+
+impl proc::Processor for Probe {
+    fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {}
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        self.0.lock().unwrap().push(context.input(0).is_silent());
+        proc::Processor::process(&mut Thru, context);
+    }
+
+    fn reset(&mut self) {}
+}
+
+#[test]
+fn players_stopping_mid_block_leave_the_rest_silent() {
+    for num_workers in [0, 2] {
+        let graph = Graph::new(Options {
+            num_input_channels: 0,
+            num_output_channels: 2,
+            num_workers,
+        });
+        let blocks = Arc::new(Mutex::new(vec![]));
+        let player = node::Node::new(
+            &graph,
+            node::Options {
+                audio_inputs: vec![],
+                audio_outputs: vec![2],
+            },
+            Player { remaining: 50 },
+        );
+        let probe = node::Node::new(
+            &graph,
+            node::Options {
+                audio_inputs: vec![2],
+                audio_outputs: vec![2],
+            },
+            Probe(blocks.clone()),
+        );
+        let _edges = [
+            Edge::new(&graph, &player, 0, &probe, 0).unwrap(),
+            Edge::new(&graph, &probe, 0, &graph.output_node(), 0).unwrap(),
+        ];
+        graph.commit_changes().unwrap();
+        let mut renderer = graph.renderer().unwrap();
+        renderer.initialize(48e3, 128);
+
+        // The sink only sees the frames played, and nothing once the player is done.
+        let output = render(&mut renderer, 0, 2, 128, 2);
+        for channel in &output {
+            assert_eq!(channel[..50], [1.0; 50]);
+            assert_eq!(channel[50..], [0.0; 206]);
+        }
+        assert_eq!(*blocks.lock().unwrap(), [false, true]);
+    }
+}
+
+/// Copies at most `limit` frames of its input to its output per call, like a rate-limited
+/// processor, and counts its calls.
+struct Limited {
+    limit: usize,
+    calls: Arc<Mutex<usize>>,
+}
+
+impl proc::Processor for Limited {
+    fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {}
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        *self.calls.lock().unwrap() += 1;
+        let num_frames = context.num_frames().min(self.limit);
+        for (input, output) in context.zip_channels() {
+            output[..num_frames].copy_from_slice(&input[..num_frames]);
+        }
+        context.num_frames_produced = num_frames;
+    }
+
+    fn reset(&mut self) {}
+}
+
+#[test]
+fn processors_consuming_part_of_a_block_are_run_for_the_rest() {
+    for num_workers in [0, 2] {
+        let graph = Graph::new(Options {
+            num_input_channels: 0,
+            num_output_channels: 1,
+            num_workers,
+        });
+        let calls = Arc::new(Mutex::new(0));
+        let ramp = node::Node::new(&graph, common::options(0, 1), Ramp::default());
+        let limited = node::Node::new(
+            &graph,
+            common::options(1, 1),
+            Limited {
+                limit: 64,
+                calls: calls.clone(),
+            },
+        );
+        let _edges = [
+            Edge::new(&graph, &ramp, 0, &limited, 0).unwrap(),
+            Edge::new(&graph, &limited, 0, &graph.output_node(), 0).unwrap(),
+        ];
+        graph.commit_changes().unwrap();
+        let mut renderer = graph.renderer().unwrap();
+        renderer.initialize(48e3, 128);
+
+        // Every frame comes out, in two calls per block.
+        let output = render(&mut renderer, 0, 1, 128, 2);
+        let expected = (1..=256).map(|frame| frame as f32).collect::<Vec<_>>();
+        assert_eq!(output[0], expected);
+        assert_eq!(*calls.lock().unwrap(), 4);
+    }
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use common::{render, Constant, Thru};
+use tesi_graph::{edge::Edge, node, Error, Graph, Options, XrunKind};
+
+fn graph() -> (Graph, node::Node, Edge) {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 2,
+        num_workers: 0,
+    });
+    graph.configure(48e3, 64).unwrap();
+    let source = node::Node::new(
+        &graph,
+        node::Options {
+            audio_inputs: vec![],
+            audio_outputs: vec![2],
+        },
+        Constant(0.25),
+    );
+    let edge = Edge::new(&graph, &source, 0, &graph.output_node(), 0).unwrap();
+    (graph, source, edge)
+}
+
+#[test]
+fn too_small_pool_is_rejected() {
+    let (graph, _source, _edge) = graph();
+    // Room for a single buffer.
+    let pool = Vec::with_capacity(64);
+    assert!(matches!(
+        graph.commit_changes_with_pool(pool),
+        Err(Error::PoolTooSmall)
+    ));
+}
+
+#[test]
+fn adequate_pool_renders() {
+    let (graph, _source, _edge) = graph();
+    let pool = Vec::with_capacity(graph.pool_size());
+    graph.commit_changes_with_pool(pool).unwrap();
+    let mut outputs = [vec![0.0; 256], vec![0.0; 256]];
+    let [left, right] = &mut outputs;
+    graph.render_offline(&[], &mut [left, right], 64).unwrap();
+    assert!(outputs.iter().flatten().all(|sample| *sample == 0.25));
+}
+
+#[test]
+fn undersized_pool_reports_xruns() {
+    let (graph, source, edge) = graph();
+    let options = node::Options {
+        audio_inputs: vec![2],
+        audio_outputs: vec![2],
+    };
+    let first = node::Node::new(&graph, options.clone(), Thru);
+    let second = node::Node::new(&graph, options, Thru);
+    drop(edge);
+    // A summing output keeps the chain's last buffers off the host, so every node in the
+    // chain needs a buffer from the pool at render time.
+    graph.output_node().set_summing(0, true).unwrap();
+    let _edges = [
+        Edge::new(&graph, &source, 0, &first, 0).unwrap(),
+        Edge::new(&graph, &first, 0, &second, 0).unwrap(),
+        Edge::new(&graph, &second, 0, &graph.output_node(), 0).unwrap(),
+    ];
+
+    // The smallest pool that holds the buffers the graph starts out with, in whole buffers.
+    let size = (1..)
+        .map(|num_buffers| num_buffers * 64)
+        .find(|size| {
+            graph
+                .commit_changes_with_pool(Vec::with_capacity(*size))
+                .is_ok()
+        })
+        .unwrap();
+    assert!(size < graph.pool_size());
+
+    // The renderer runs out of buffers, skips what it can't process and says so.
+    let mut renderer = graph.renderer().unwrap();
+    renderer.initialize(48e3, 64);
+    let output = render(&mut renderer, 0, 2, 64, 2);
+    assert_ne!(output, [[0.25; 128], [0.25; 128]]);
+    let xruns = renderer.xruns().collect::<Vec<_>>();
+    assert!(!xruns.is_empty());
+    assert!(xruns
+        .iter()
+        .all(|xrun| xrun.kind == XrunKind::PoolExhausted && xrun.node.is_some()));
+
+    // With a full pool nothing runs out.
+    graph
+        .commit_changes_with_pool(Vec::with_capacity(graph.pool_size()))
+        .unwrap();
+    let output = render(&mut renderer, 0, 2, 64, 2);
+    assert_eq!(output, [[0.25; 128], [0.25; 128]]);
+    assert_eq!(renderer.xruns().count(), 0);
+}
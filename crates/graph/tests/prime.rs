@@ -0,0 +1,50 @@
+mod common;
+
+use common::{render, Lookahead, Sine};
+use tesi_graph::{edge::Edge, node, Error, Graph, Options};
+
+const LOOKAHEAD: usize = 64;
+
+#[test]
+fn priming_fills_the_lookahead() {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 0,
+    });
+    let sine = node::Node::new(
+        &graph,
+        node::Options {
+            audio_inputs: vec![],
+            audio_outputs: vec![1],
+        },
+        Sine::new(440.0),
+    );
+    let limiter = node::Node::new(
+        &graph,
+        node::Options {
+            audio_inputs: vec![1],
+            audio_outputs: vec![1],
+        },
+        Lookahead::new(LOOKAHEAD),
+    );
+    let _edges = [
+        Edge::new(&graph, &sine, 0, &limiter, 0).unwrap(),
+        Edge::new(&graph, &limiter, 0, &graph.output_node(), 0).unwrap(),
+    ];
+    graph.commit_changes().unwrap();
+
+    let mut renderer = graph.renderer().unwrap();
+    assert!(matches!(
+        renderer.prime(LOOKAHEAD),
+        Err(Error::Uninitialized)
+    ));
+    renderer.initialize(48e3, 128);
+    let cold = render(&mut renderer, 0, 1, 128, 1).remove(0);
+    assert!(cold[..LOOKAHEAD].iter().all(|sample| *sample == 0.0));
+
+    renderer.reset();
+    renderer.prime(LOOKAHEAD).unwrap();
+    let primed = render(&mut renderer, 0, 1, 128, 1).remove(0);
+    assert_eq!(primed[..128 - LOOKAHEAD], cold[LOOKAHEAD..]);
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{render, Constant};
+use tesi_graph::{edge::Edge, node, proc, Graph, NodeProfile, Options};
+
+/// Sleeps for a while every block.
+struct Slow(Duration);
+
+impl proc::Processor for Slow {
+    fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {}
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        std::thread::sleep(self.0);
+        let (inputs, outputs) = context.split_io();
+        outputs[0][0].copy_from_slice(&inputs[0][0]);
+    }
+
+    fn reset(&mut self) {}
+}
+
+#[test]
+fn profiles_find_the_hottest_node() {
+    for num_workers in [0, 2] {
+        let graph = Graph::new(Options {
+            num_input_channels: 0,
+            num_output_channels: 1,
+            num_workers,
+        });
+        let source = node::Node::new(
+            &graph,
+            node::Options {
+                audio_inputs: vec![],
+                audio_outputs: vec![1],
+            },
+            Constant(0.5),
+        );
+        let slow = node::Node::new(
+            &graph,
+            node::Options {
+                audio_inputs: vec![1],
+                audio_outputs: vec![1],
+            },
+            Slow(Duration::from_millis(2)),
+        );
+        let _edges = [
+            Edge::new(&graph, &source, 0, &slow, 0).unwrap(),
+            Edge::new(&graph, &slow, 0, &graph.output_node(), 0).unwrap(),
+        ];
+        graph.commit_changes().unwrap();
+        let mut renderer = graph.renderer().unwrap();
+        renderer.initialize(48e3, 32);
+
+        // Nothing is timed until profiling is enabled.
+        render(&mut renderer, 0, 1, 32, 2);
+        assert_eq!(slow.profile(), NodeProfile::default());
+
+        renderer.set_profiling(true);
+        assert_eq!(render(&mut renderer, 0, 1, 32, 4), [[0.5; 128]]);
+        let profile = slow.profile();
+        assert_eq!(profile.num_blocks, 4);
+        assert!(profile.min >= Duration::from_millis(2));
+        assert!(profile.min <= profile.mean && profile.mean <= profile.max);
+        assert_eq!(source.profile().num_blocks, 4);
+        assert!(
+            source.profile().mean < profile.mean,
+            "{num_workers} workers"
+        );
+
+        // Stopping keeps the statistics, resetting clears them.
+        renderer.set_profiling(false);
+        render(&mut renderer, 0, 1, 32, 1);
+        assert_eq!(slow.profile(), profile);
+        slow.reset_profile();
+        assert_eq!(slow.profile(), NodeProfile::default());
+    }
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use std::{sync::mpsc, time::Duration};
+
+use common::{render, Constant, Thru};
+use tesi_graph::{edge::Edge, node, Graph, Options};
+
+#[test]
+fn nodes_without_bound_inputs_are_scheduled() {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 2,
+    });
+    let options = |num_inputs| node::Options {
+        audio_inputs: vec![1; num_inputs],
+        audio_outputs: vec![1],
+    };
+
+    // A generator with an input nothing is bound to, feeding the output, next to a generator and
+    // a sink that aren't connected to anything.
+    let lfo = node::Node::new(&graph, options(1), Constant(0.5));
+    let _unconnected = [
+        node::Node::new(&graph, options(0), Constant(1.0)),
+        node::Node::new(&graph, options(1), Thru),
+    ];
+    let _edge = Edge::new(&graph, &lfo, 0, &graph.output_node(), 0).unwrap();
+    graph.commit_changes().unwrap();
+
+    let mut renderer = graph.renderer().unwrap();
+    renderer.initialize(48e3, 32);
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        sender.send(render(&mut renderer, 0, 1, 32, 256)).ok();
+    });
+    let output = receiver
+        .recv_timeout(Duration::from_secs(10))
+        .expect("the audio thread hung");
+    assert_eq!(output, [[0.5; 32 * 256]]);
+}
@@ -0,0 +1,143 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use common::{options, render, Constant};
+use tesi_graph::{
+    edge::Edge,
+    node,
+    proc::{self, builtin::gain},
+    Graph, Options,
+};
+
+/// Writes ones to its first output, reporting it silent when told to.
+struct Gated(Arc<Mutex<bool>>);
+
+impl proc::Processor for Gated {
+    fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {}
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        for channel in context.output(0).iter() {
+            channel.fill(1.0);
+        }
+        if *self.0.lock().unwrap() {
+            context.silent_outputs = 1;
+        }
+    }
+
+    fn reset(&mut self) {}
+}
+
+/// The inputs a [Probe] saw as silent, per block.
+type Blocks = Arc<Mutex<Vec<Vec<bool>>>>;
+
+/// Records which of its inputs are silent and sums them into its first output.
+struct Probe(Blocks);
+
+impl proc::Processor for Probe {
+    fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {}
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        let silent = context.audio_inputs.iter().map(|input| input.is_silent());
+        self.0.lock().unwrap().push(silent.collect());
+        proc::Processor::process(&mut common::Sum, context);
+    }
+
+    fn reset(&mut self) {}
+}
+
+#[test]
+fn reported_silence_reaches_the_sinks() {
+    for num_workers in [0, 2] {
+        let graph = Graph::new(Options {
+            num_input_channels: 0,
+            num_output_channels: 1,
+            num_workers,
+        });
+        let gate = Arc::new(Mutex::new(true));
+        let blocks = Arc::new(Mutex::new(vec![]));
+
+        // A gated source alone, summed with a constant, summed with itself, and nothing.
+        let gated = node::Node::new(&graph, options(0, 1), Gated(gate.clone()));
+        let constant = node::Node::new(&graph, options(0, 1), Constant(0.5));
+        let probe = node::Node::new(&graph, options(4, 1), Probe(blocks.clone()));
+        probe.set_summing(1, true).unwrap();
+        probe.set_summing(2, true).unwrap();
+        let _edges = [
+            Edge::new(&graph, &gated, 0, &probe, 0).unwrap(),
+            Edge::new(&graph, &gated, 0, &probe, 1).unwrap(),
+            Edge::new(&graph, &constant, 0, &probe, 1).unwrap(),
+            Edge::new(&graph, &gated, 0, &probe, 2).unwrap(),
+            Edge::new(&graph, &probe, 0, &graph.output_node(), 0).unwrap(),
+        ];
+        graph.commit_changes().unwrap();
+        let mut renderer = graph.renderer().unwrap();
+        renderer.initialize(48e3, 16);
+
+        // Reported outputs are zeroed, whatever the processor wrote.
+        assert_eq!(render(&mut renderer, 0, 1, 16, 1), [[0.5; 16]]);
+        *gate.lock().unwrap() = false;
+        assert_eq!(render(&mut renderer, 0, 1, 16, 1), [[3.5; 16]]);
+        assert_eq!(
+            *blocks.lock().unwrap(),
+            [
+                vec![true, false, true, true],
+                vec![false, false, false, true],
+            ]
+        );
+    }
+}
+
+#[test]
+fn delayed_inputs_are_never_silent() {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 0,
+    });
+    let gate = Arc::new(Mutex::new(false));
+    let blocks = Arc::new(Mutex::new(vec![]));
+    let gated = node::Node::new(&graph, options(0, 1), Gated(gate.clone()));
+    let probe = node::Node::new(&graph, options(1, 1), Probe(blocks.clone()));
+    let delayed = Edge::new(&graph, &gated, 0, &probe, 0).unwrap();
+    delayed.set_delay(8);
+    let _edge = Edge::new(&graph, &probe, 0, &graph.output_node(), 0).unwrap();
+    graph.commit_changes().unwrap();
+    let mut renderer = graph.renderer().unwrap();
+    renderer.initialize(48e3, 16);
+
+    // The delay still holds the source's last frames after it goes silent.
+    render(&mut renderer, 0, 1, 16, 1);
+    *gate.lock().unwrap() = true;
+    let output = render(&mut renderer, 0, 1, 16, 2);
+    assert_eq!(output[0][..8], [1.0; 8]);
+    assert_eq!(output[0][8..], [0.0; 24]);
+    assert_eq!(*blocks.lock().unwrap(), [[false]; 3]);
+}
+
+#[test]
+fn gain_passes_silence_through() {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 0,
+    });
+    let gate = Arc::new(Mutex::new(true));
+    let blocks = Arc::new(Mutex::new(vec![]));
+    let gated = node::Node::new(&graph, options(0, 1), Gated(gate.clone()));
+    let fader = node::Node::new(&graph, options(1, 1), gain::Processor::new(0.5));
+    let probe = node::Node::new(&graph, options(1, 1), Probe(blocks.clone()));
+    let _edges = [
+        Edge::new(&graph, &gated, 0, &fader, 0).unwrap(),
+        Edge::new(&graph, &fader, 0, &probe, 0).unwrap(),
+        Edge::new(&graph, &probe, 0, &graph.output_node(), 0).unwrap(),
+    ];
+    graph.commit_changes().unwrap();
+    let mut renderer = graph.renderer().unwrap();
+    renderer.initialize(48e3, 16);
+
+    assert_eq!(render(&mut renderer, 0, 1, 16, 1), [[0.0; 16]]);
+    *gate.lock().unwrap() = false;
+    assert_eq!(render(&mut renderer, 0, 1, 16, 1), [[0.5; 16]]);
+    assert_eq!(*blocks.lock().unwrap(), [[true], [false]]);
+}
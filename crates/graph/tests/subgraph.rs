@@ -0,0 +1,76 @@
+mod common;
+
+use common::Sine;
+use tesi_graph::{edge::Edge, node, proc::builtin::subgraph, Graph, Options};
+
+/// Two sines summed into the output.
+fn sine_sum() -> (Graph, Vec<node::Node>, Vec<Edge>) {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 2,
+        num_workers: 0,
+    });
+    graph.configure(48e3, 64).unwrap();
+    let options = node::Options {
+        audio_inputs: vec![],
+        audio_outputs: vec![2],
+    };
+    let output = graph.output_node();
+    output.set_summing(0, true).unwrap();
+    let nodes = vec![
+        node::Node::new(&graph, options.clone(), Sine::new(440.0)),
+        node::Node::new(&graph, options, Sine::new(660.0)),
+    ];
+    let edges = nodes
+        .iter()
+        .map(|node| Edge::new(&graph, node, 0, &output, 0).unwrap())
+        .collect();
+    graph.commit_changes().unwrap();
+    (graph, nodes, edges)
+}
+
+fn render(graph: &Graph) -> [Vec<f32>; 2] {
+    let mut outputs = [vec![0.0; 512], vec![0.0; 512]];
+    let [left, right] = &mut outputs;
+    graph.render_offline(&[], &mut [left, right], 64).unwrap();
+    outputs
+}
+
+#[test]
+fn nested_render_matches_the_inner_graph() {
+    let (inner, _nodes, _edges) = sine_sum();
+    let expected = render(&inner);
+    assert!(expected[0].iter().any(|sample| *sample != 0.0));
+
+    let outer = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 2,
+        num_workers: 0,
+    });
+    outer.configure(48e3, 64).unwrap();
+    let wrapper = node::Node::new(
+        &outer,
+        subgraph::Processor::node_options(&inner),
+        subgraph::Processor::new(&inner).unwrap(),
+    );
+    let _edge = Edge::new(&outer, &wrapper, 0, &outer.output_node(), 0).unwrap();
+    outer.commit_changes().unwrap();
+    assert_eq!(render(&outer), expected);
+}
+
+#[test]
+fn driven_renderers_cant_be_wrapped() {
+    let (inner, _nodes, _edges) = sine_sum();
+    inner.renderer().unwrap().initialize(48e3, 64);
+    assert!(subgraph::Processor::new(&inner).is_none());
+}
+
+#[test]
+fn wrapping_holds_the_renderer() {
+    let (inner, _nodes, _edges) = sine_sum();
+    let wrapped = subgraph::Processor::new(&inner).unwrap();
+    assert!(subgraph::Processor::new(&inner).is_none());
+    assert!(inner.renderer().is_none());
+    drop(wrapped);
+    assert!(inner.renderer().is_some());
+}
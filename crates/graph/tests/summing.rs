@@ -0,0 +1,62 @@
+mod common;
+
+use common::{render, Constant, Thru};
+use tesi_graph::{edge::Edge, node, Error, Graph, Options};
+
+fn source(graph: &Graph, value: f32) -> node::Node {
+    let options = node::Options {
+        audio_inputs: vec![],
+        audio_outputs: vec![2],
+    };
+    node::Node::new(graph, options, Constant(value))
+}
+
+#[test]
+fn summing_inputs_add_their_sources() {
+    for num_workers in [0, 2] {
+        let graph = Graph::new(Options {
+            num_input_channels: 0,
+            num_output_channels: 2,
+            num_workers,
+        });
+        let mixer = node::Node::new(
+            &graph,
+            node::Options {
+                audio_inputs: vec![2],
+                audio_outputs: vec![2],
+            },
+            Thru,
+        );
+        let sources = [0.125, 0.25, 0.5].map(|value| source(&graph, value));
+
+        // Only summing inputs take more than one edge.
+        let _first = Edge::new(&graph, &sources[0], 0, &mixer, 0).unwrap();
+        assert!(matches!(
+            Edge::new(&graph, &sources[1], 0, &mixer, 0),
+            Err(Error::AlreadyConnected { port: 0, .. })
+        ));
+        mixer.set_summing(0, true).unwrap();
+        let _rest = [
+            Edge::new(&graph, &sources[1], 0, &mixer, 0).unwrap(),
+            Edge::new(&graph, &sources[2], 0, &mixer, 0).unwrap(),
+        ];
+        assert!(matches!(
+            mixer.set_summing(0, false),
+            Err(Error::AlreadyConnected { .. })
+        ));
+
+        // The last source also feeds the output directly, so its buffer is shared by two sinks.
+        let output = graph.output_node();
+        output.set_summing(0, true).unwrap();
+        let _outputs = [
+            Edge::new(&graph, &mixer, 0, &output, 0).unwrap(),
+            Edge::new(&graph, &sources[2], 0, &output, 0).unwrap(),
+        ];
+        graph.commit_changes().unwrap();
+
+        let mut renderer = graph.renderer().unwrap();
+        renderer.initialize(48e3, 32);
+        let outputs = render(&mut renderer, 0, 2, 32, 3);
+        assert_eq!(outputs, [[1.375; 96], [1.375; 96]], "{num_workers} workers");
+    }
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use std::sync::{
+    atomic::{AtomicU32, Ordering},
+    Arc,
+};
+
+use common::Thru;
+use tesi_graph::{edge::Edge, node, proc, Graph, Options};
+
+/// Records the peak of its first input.
+struct Meter(Arc<AtomicU32>);
+
+impl proc::Processor for Meter {
+    fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {}
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        let peak = context.audio_inputs[0]
+            .iter()
+            .flatten()
+            .fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+        self.0.store(peak.to_bits(), Ordering::Relaxed);
+    }
+
+    fn reset(&mut self) {}
+}
+
+#[test]
+fn meter_sees_the_host_input() {
+    let graph = Graph::new(Options {
+        num_input_channels: 1,
+        num_output_channels: 1,
+        num_workers: 0,
+    });
+    let options = node::Options {
+        audio_inputs: vec![1],
+        audio_outputs: vec![1],
+    };
+    let thru = node::Node::new(&graph, options, Thru);
+    let peak = Arc::new(AtomicU32::new(0));
+    let meter = node::Node::new(
+        &graph,
+        node::Options {
+            audio_inputs: vec![1],
+            audio_outputs: vec![],
+        },
+        Meter(peak.clone()),
+    );
+    let _edges = [
+        Edge::new(&graph, &graph.input_node(), 0, &thru, 0).unwrap(),
+        Edge::new(&graph, &thru, 0, &graph.output_node(), 0).unwrap(),
+        meter.tap_graph_input(0).unwrap(),
+    ];
+    assert!(meter.tap_graph_input(0).is_err(), "the input is taken");
+    graph.commit_changes().unwrap();
+
+    let input = vec![0.5; 64];
+    let mut output = vec![0.0; 64];
+    graph
+        .render_offline(&[&input], &mut [&mut output], 64)
+        .unwrap();
+    assert_eq!(f32::from_bits(peak.load(Ordering::Relaxed)), 0.5);
+    assert_eq!(output, input);
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use common::render;
+use tesi_graph::{edge::Edge, node, proc, Graph, Options};
+
+/// Outputs ones and counts its blocks.
+struct Counted(Arc<AtomicUsize>);
+
+impl proc::Processor for Counted {
+    fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {}
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+        for channel in context.output(0).iter() {
+            channel.fill(1.0);
+        }
+    }
+
+    fn reset(&mut self) {}
+}
+
+#[test]
+fn dropping_the_graph_mid_stream_detaches_the_renderer() {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 2,
+    });
+    let blocks = Arc::new(AtomicUsize::new(0));
+    let node = node::Node::new(
+        &graph,
+        node::Options {
+            audio_inputs: vec![],
+            audio_outputs: vec![1],
+        },
+        Counted(blocks.clone()),
+    );
+    let edge = Edge::new(&graph, &node, 0, &graph.output_node(), 0).unwrap();
+    graph.commit_changes().unwrap();
+    let mut renderer = graph.renderer().unwrap();
+    renderer.initialize(48e3, 32);
+
+    // Render on an audio thread until the renderer notices the graph is gone.
+    let audio_thread = std::thread::spawn(move || {
+        let mut last = vec![];
+        while !renderer.is_detached() {
+            last = render(&mut renderer, 0, 1, 32, 1).remove(0);
+        }
+        (renderer, last)
+    });
+    while blocks.load(Ordering::Relaxed) < 16 {
+        std::thread::yield_now();
+    }
+    drop((edge, node, graph));
+    let (mut renderer, last) = audio_thread.join().unwrap();
+    assert!(last == [1.0; 32] || last == [0.0; 32]);
+
+    // Once detached, the renderer outputs silence without calling into processors.
+    let before = blocks.load(Ordering::Relaxed);
+    assert_eq!(render(&mut renderer, 0, 1, 32, 4).remove(0), [0.0; 128]);
+    assert_eq!(blocks.load(Ordering::Relaxed), before);
+
+    // The processor goes with the renderer.
+    drop(renderer);
+    assert_eq!(Arc::strong_count(&blocks), 1);
+}
+
+#[test]
+fn dropping_the_graph_after_the_renderer_releases_the_processors() {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 2,
+    });
+    let blocks = Arc::new(AtomicUsize::new(0));
+    let node = node::Node::new(
+        &graph,
+        node::Options {
+            audio_inputs: vec![],
+            audio_outputs: vec![1],
+        },
+        Counted(blocks.clone()),
+    );
+    let edge = Edge::new(&graph, &node, 0, &graph.output_node(), 0).unwrap();
+    graph.commit_changes().unwrap();
+    let mut renderer = graph.renderer().unwrap();
+    renderer.initialize(48e3, 32);
+    assert_eq!(render(&mut renderer, 0, 1, 32, 1).remove(0), [1.0; 32]);
+
+    // The renderer goes back to the graph, and everything goes with the graph.
+    drop(renderer);
+    drop((edge, node, graph));
+    assert_eq!(Arc::strong_count(&blocks), 1);
+}
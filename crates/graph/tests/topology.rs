@@ -0,0 +1,148 @@
+mod common;
+
+use std::collections::BTreeMap;
+
+use common::{options, render, Constant, Sine, Sum, Thru};
+use tesi_graph::{edge::Edge, node, EdgeInfo, Graph, Options};
+
+fn edge(source: usize, output: usize, sink: usize, input: usize) -> EdgeInfo {
+    EdgeInfo {
+        source,
+        output,
+        sink,
+        input,
+        delay: 0,
+        monitor: false,
+    }
+}
+
+#[test]
+fn snapshots_follow_uncommitted_changes() {
+    let graph = Graph::new(Options {
+        num_input_channels: 1,
+        num_output_channels: 1,
+        num_workers: 0,
+    });
+    let source = node::Node::new(&graph, options(0, 1), Constant(1.0));
+    let thru = node::Node::new(&graph, options(2, 1), Thru);
+    thru.set_summing(1, true).unwrap();
+    let output = graph.output_node();
+    let first = Edge::new(&graph, &source, 0, &thru, 0).unwrap();
+    let _rest = [
+        Edge::new(&graph, &graph.input_node(), 0, &thru, 1).unwrap(),
+        Edge::new(&graph, &thru, 0, &output, 0).unwrap(),
+    ];
+
+    // The root nodes come first, then the others in order of id.
+    let nodes = graph.nodes().collect::<Vec<_>>();
+    let ids = nodes.iter().map(|node| node.id).collect::<Vec<_>>();
+    assert_eq!(ids, [0, 1, source.id(), thru.id()]);
+    let info = &nodes[3];
+    assert!(info.name.ends_with("Thru"));
+    assert_eq!(info.options.audio_inputs, [1, 1]);
+    assert_eq!(info.options.audio_outputs, [1]);
+    assert_eq!(info.summing, [false, true]);
+
+    // Edges are listed by source, then output.
+    let edges = graph.edges().collect::<Vec<_>>();
+    assert_eq!(
+        edges,
+        [
+            edge(0, 0, thru.id(), 1),
+            edge(source.id(), 0, thru.id(), 0),
+            edge(thru.id(), 0, 1, 0),
+        ]
+    );
+
+    // Snapshots are owned, they don't see later changes or hold the graph.
+    drop(first);
+    drop(source);
+    assert_eq!(graph.edges().count(), 2);
+    assert_eq!(graph.nodes().count(), 3);
+    assert_eq!(edges.len(), 3);
+}
+
+fn mono() -> Graph {
+    Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 0,
+    })
+}
+
+#[test]
+fn topology_round_trips_onto_fresh_ids() {
+    // The example's graph: two sines mixed by a sum node into the output.
+    let graph = mono();
+    let sines = [440.0, 880.0].map(|freq| node::Node::new(&graph, options(0, 1), Sine::new(freq)));
+    let sum = node::Node::new(&graph, options(2, 1), Sum);
+    sum.latency_changed(16);
+    let edges = [
+        Edge::new(&graph, &sines[0], 0, &sum, 0).unwrap(),
+        Edge::new(&graph, &sines[1], 0, &sum, 1).unwrap(),
+        Edge::new(&graph, &sum, 0, &graph.output_node(), 0).unwrap(),
+    ];
+    edges[1].set_delay(8);
+    let state = graph.save_topology();
+    assert_eq!(state.nodes.len(), 3);
+    assert_eq!(state.edges.len(), 3);
+
+    // Restore into a graph that already has a node, so the saved ids are taken.
+    let freqs = BTreeMap::from([(sines[0].id(), 440.0), (sines[1].id(), 880.0)]);
+    let restored_graph = mono();
+    let taken = node::Node::new(&restored_graph, options(0, 1), Constant(0.0));
+    let restored = restored_graph
+        .restore_topology(&state, |graph, saved| {
+            let options = node::Options {
+                audio_inputs: saved.audio_inputs.clone(),
+                audio_outputs: saved.audio_outputs.clone(),
+            };
+            match freqs.get(&saved.id) {
+                Some(freq) => node::Node::new(graph, options, Sine::new(*freq)),
+                None => node::Node::new(graph, options, Sum),
+            }
+        })
+        .unwrap();
+    let ids = |id: usize| match id {
+        0 | 1 => id,
+        id => restored.nodes[&id].id(),
+    };
+    assert_eq!(restored.nodes.len(), 3);
+    assert!(state.nodes.iter().all(|node| ids(node.id) != node.id));
+
+    // The restored graph saves the same topology under the new ids, less the extra node.
+    let mut resaved = restored_graph.save_topology();
+    resaved.nodes.retain(|node| node.id != taken.id());
+    let remapped = state
+        .nodes
+        .iter()
+        .map(|node| (ids(node.id), &node.name, &node.summing, node.latency))
+        .collect::<Vec<_>>();
+    let resaved_nodes = resaved
+        .nodes
+        .iter()
+        .map(|node| (node.id, &node.name, &node.summing, node.latency))
+        .collect::<Vec<_>>();
+    assert_eq!(resaved_nodes, remapped);
+    let mut remapped = state
+        .edges
+        .iter()
+        .map(|edge| EdgeInfo {
+            source: ids(edge.source),
+            sink: ids(edge.sink),
+            ..*edge
+        })
+        .collect::<Vec<_>>();
+    remapped.sort_by_key(|edge| (edge.source, edge.output, edge.sink, edge.input));
+    assert_eq!(resaved.edges, remapped);
+
+    // And sounds the same.
+    let outputs = [&graph, &restored_graph].map(|graph| {
+        graph.commit_changes().unwrap();
+        let mut renderer = graph.renderer().unwrap();
+        renderer.initialize(48e3, 64);
+        render(&mut renderer, 0, 1, 64, 4)
+    });
+    assert_eq!(outputs[0], outputs[1]);
+    assert!(outputs[0][0].iter().any(|sample| *sample != 0.0));
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use common::{Sine, Thru};
+use tesi_graph::{edge::Edge, node, proc::builtin::gain, Graph, Options};
+
+fn stereo(num_inputs: usize) -> node::Options {
+    node::Options {
+        audio_inputs: vec![2; num_inputs],
+        audio_outputs: vec![2],
+    }
+}
+
+#[test]
+fn traffic_counts_every_committed_buffer() {
+    // The example: two sines summed into a gain.
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 2,
+        num_workers: 0,
+    });
+    graph.configure(48e3, 64).unwrap();
+    let sine440 = node::Node::new(&graph, stereo(0), Sine::new(440.0));
+    let sine880 = node::Node::new(&graph, stereo(0), Sine::new(880.0));
+    let sum = node::Node::new(&graph, stereo(2), Thru);
+    let gain = node::Node::new(&graph, stereo(1), gain::Processor::new(1.0));
+    let _edges = [
+        Edge::new(&graph, &sine440, 0, &sum, 0).unwrap(),
+        Edge::new(&graph, &sine880, 0, &sum, 1).unwrap(),
+        Edge::new(&graph, &sum, 0, &gain, 0).unwrap(),
+        Edge::new(&graph, &gain, 0, &graph.output_node(), 0).unwrap(),
+    ];
+    assert_eq!(graph.memory_traffic_per_block(), 0);
+    graph.commit_changes().unwrap();
+
+    // Sines 2 + 2, sum 4 + 2, gain 2 + 2, the root input 0 and the root output 2 channels.
+    let bytes_per_channel = 64 * std::mem::size_of::<f32>();
+    let expected = (2 + 2 + (4 + 2) + (2 + 2) + 2) * bytes_per_channel;
+    assert_eq!(graph.memory_traffic_per_block(), expected);
+
+    // Uncommitted nodes don't count until the next commit.
+    let _extra = node::Node::new(&graph, stereo(1), Thru);
+    assert_eq!(graph.memory_traffic_per_block(), expected);
+    graph.commit_changes().unwrap();
+    assert_eq!(
+        graph.memory_traffic_per_block(),
+        expected + 4 * bytes_per_channel
+    );
+}
@@ -0,0 +1,110 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use common::render;
+use tesi_graph::{
+    edge::Edge,
+    node,
+    proc::{self, builtin::subgraph, Transport},
+    Graph, Options,
+};
+
+/// The transport of every block a [Recorder] processed.
+type Blocks = Arc<Mutex<Vec<Option<Transport>>>>;
+
+/// Records the transport of every block and writes silence.
+struct Recorder(Blocks);
+
+impl proc::Processor for Recorder {
+    fn initialize(&mut self, _sample_rate: f64, _max_num_frames: usize) {}
+
+    fn process(&mut self, context: &mut proc::Context<'_>) {
+        self.0.lock().unwrap().push(context.transport);
+        context.silent_outputs = 1;
+    }
+
+    fn reset(&mut self) {}
+}
+
+/// A graph with a recorder feeding its output.
+fn graph(num_workers: usize) -> (Graph, Blocks, node::Node, Edge) {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers,
+    });
+    graph.configure(48e3, 64).unwrap();
+    let blocks = Arc::new(Mutex::new(vec![]));
+    let recorder = node::Node::new(
+        &graph,
+        node::Options {
+            audio_inputs: vec![],
+            audio_outputs: vec![1],
+        },
+        Recorder(blocks.clone()),
+    );
+    let edge = Edge::new(&graph, &recorder, 0, &graph.output_node(), 0).unwrap();
+    graph.commit_changes().unwrap();
+    (graph, blocks, recorder, edge)
+}
+
+fn transport(song_pos_beats: f64) -> Transport {
+    Transport {
+        tempo_bpm: 120.0,
+        time_sig: (6, 8),
+        song_pos_beats,
+        is_playing: true,
+        loop_beats: Some((4.0, 8.0)),
+    }
+}
+
+#[test]
+fn processors_see_the_latest_transport() {
+    for num_workers in [0, 2] {
+        let (graph, blocks, _recorder, _edge) = graph(num_workers);
+        let mut renderer = graph.renderer().unwrap();
+        renderer.initialize(48e3, 64);
+
+        // Nothing until the host provides a transport, then whatever it set last.
+        render(&mut renderer, 0, 1, 64, 1);
+        renderer.set_transport(transport(0.0));
+        render(&mut renderer, 0, 1, 64, 1);
+        renderer.set_transport(transport(0.0));
+        renderer.set_transport(transport(0.125));
+        render(&mut renderer, 0, 1, 64, 2);
+        assert_eq!(
+            *blocks.lock().unwrap(),
+            [
+                None,
+                Some(transport(0.0)),
+                Some(transport(0.125)),
+                Some(transport(0.125)),
+            ]
+        );
+    }
+}
+
+#[test]
+fn subgraphs_forward_the_transport() {
+    let (inner, blocks, _recorder, _edge) = graph(0);
+    let outer = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 0,
+    });
+    let wrapper = node::Node::new(
+        &outer,
+        subgraph::Processor::node_options(&inner),
+        subgraph::Processor::new(&inner).unwrap(),
+    );
+    let _edge = Edge::new(&outer, &wrapper, 0, &outer.output_node(), 0).unwrap();
+    outer.commit_changes().unwrap();
+    let mut renderer = outer.renderer().unwrap();
+    renderer.initialize(48e3, 64);
+
+    render(&mut renderer, 0, 1, 64, 1);
+    renderer.set_transport(transport(2.5));
+    render(&mut renderer, 0, 1, 64, 1);
+    assert_eq!(*blocks.lock().unwrap(), [None, Some(transport(2.5))]);
+}
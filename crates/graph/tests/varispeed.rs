@@ -0,0 +1,29 @@
+mod common;
+
+use common::{frequency, render, Sine};
+use tesi_graph::{edge::Edge, node, Graph, Options};
+
+#[test]
+fn half_rate_drops_an_octave() {
+    let graph = Graph::new(Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 0,
+    });
+    let options = node::Options {
+        audio_inputs: vec![],
+        audio_outputs: vec![1],
+    };
+    let sine = node::Node::new(&graph, options, Sine::new(1000.0));
+    let _edge = Edge::new(&graph, &sine, 0, &graph.output_node(), 0).unwrap();
+    graph.commit_changes().unwrap();
+
+    let mut renderer = graph.renderer().unwrap();
+    renderer.initialize(48e3, 128);
+    let native = frequency(&render(&mut renderer, 0, 1, 128, 375)[0], 48e3);
+    renderer.reset();
+    renderer.set_rate_scale(0.5);
+    let slowed = frequency(&render(&mut renderer, 0, 1, 128, 375)[0], 48e3);
+    assert!((native - 1000.0).abs() < 5.0, "{native}");
+    assert!((slowed - 500.0).abs() < 5.0, "{slowed}");
+}
@@ -0,0 +1,87 @@
+mod common;
+
+use common::{render, Constant};
+use tesi_graph::{edge::Edge, node, Error, Graph, Options, ThreadPriority, WorkerConfig};
+
+fn options() -> Options {
+    Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 2,
+    }
+}
+
+/// A field of this process's status, e.g. its number of threads.
+#[cfg(target_os = "linux")]
+fn status(field: &str) -> String {
+    let status = std::fs::read_to_string("/proc/self/status").unwrap();
+    let line = status.lines().find(|line| line.starts_with(field)).unwrap();
+    line[field.len() + 1..].trim().to_owned()
+}
+
+#[test]
+fn worker_configs_are_checked() {
+    for priority in [0, 100, 255] {
+        let config = WorkerConfig {
+            affinity: vec![],
+            priority: Some(ThreadPriority(priority)),
+        };
+        assert!(matches!(
+            Graph::new_with_workers(options(), config),
+            Err(Error::InvalidPriority(p)) if p == priority
+        ));
+    }
+
+    // A priority the OS may refuse, depending on permissions, is either applied or reported.
+    let config = WorkerConfig {
+        affinity: vec![],
+        priority: Some(ThreadPriority(10)),
+    };
+    match Graph::new_with_workers(options(), config) {
+        Ok(_) | Err(Error::WorkerConfigRefused(_)) => (),
+        Err(error) => panic!("{error}"),
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        // A CPU that doesn't exist is refused, and the workers that were started are stopped.
+        let threads = status("Threads:");
+        let config = WorkerConfig {
+            affinity: vec![1 << 20],
+            priority: None,
+        };
+        let Err(Error::WorkerConfigRefused(error)) = Graph::new_with_workers(options(), config)
+        else {
+            panic!("the CPU wasn't refused");
+        };
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(status("Threads:"), threads);
+
+        // Pinning to a CPU this process may run on works.
+        let allowed = status("Cpus_allowed_list:");
+        let cpu = allowed
+            .split(|c: char| !c.is_ascii_digit())
+            .next()
+            .unwrap()
+            .parse()
+            .unwrap();
+        let config = WorkerConfig {
+            affinity: vec![cpu],
+            priority: None,
+        };
+        let graph = Graph::new_with_workers(options(), config).unwrap();
+        let source = node::Node::new(
+            &graph,
+            node::Options {
+                audio_inputs: vec![],
+                audio_outputs: vec![1],
+            },
+            Constant(0.5),
+        );
+        let _edge = Edge::new(&graph, &source, 0, &graph.output_node(), 0).unwrap();
+        graph.commit_changes().unwrap();
+        let mut renderer = graph.renderer().unwrap();
+        renderer.initialize(48e3, 32);
+        assert_eq!(render(&mut renderer, 0, 1, 32, 4), [[0.5; 128]]);
+    }
+}
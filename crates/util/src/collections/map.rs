@@ -0,0 +1,200 @@
+//! Maps keyed by small integers.
+//!
+//! [IntMap] is tuned for ids handed out from a counter, like parameter ids. Keys below
+//! [DENSE_LIMIT] index straight into a vector and keys above it fall back to an ordered tree, so
+//! a few stray large ids don't blow up the dense part. Lookups never allocate.
+use std::collections::{btree_map, BTreeMap};
+
+/// Keys below this are stored densely.
+pub const DENSE_LIMIT: u64 = 1024;
+
+#[derive(Clone, Debug)]
+pub struct IntMap<V> {
+    len: usize,
+    dense: Vec<Option<V>>,
+    sparse: BTreeMap<u64, V>,
+}
+
+pub struct Iter<'a, V> {
+    dense: std::iter::Enumerate<std::slice::Iter<'a, Option<V>>>,
+    sparse: btree_map::Iter<'a, u64, V>,
+}
+
+impl<V> IntMap<V> {
+    pub fn new() -> Self {
+        Self {
+            len: 0,
+            dense: Vec::new(),
+            sparse: BTreeMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn contains_key(&self, key: u64) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn get(&self, key: u64) -> Option<&V> {
+        if key < DENSE_LIMIT {
+            self.dense.get(key as usize)?.as_ref()
+        } else {
+            self.sparse.get(&key)
+        }
+    }
+
+    pub fn get_mut(&mut self, key: u64) -> Option<&mut V> {
+        if key < DENSE_LIMIT {
+            self.dense.get_mut(key as usize)?.as_mut()
+        } else {
+            self.sparse.get_mut(&key)
+        }
+    }
+
+    /// Insert a value, returning the value previously stored under `key`, if any.
+    pub fn insert(&mut self, key: u64, value: V) -> Option<V> {
+        let previous = if key < DENSE_LIMIT {
+            let index = key as usize;
+            if index >= self.dense.len() {
+                self.dense.resize_with(index + 1, || None);
+            }
+            self.dense[index].replace(value)
+        } else {
+            self.sparse.insert(key, value)
+        };
+        if previous.is_none() {
+            self.len += 1;
+        }
+        previous
+    }
+
+    /// Remove the value stored under `key`, if any.
+    pub fn remove(&mut self, key: u64) -> Option<V> {
+        let previous = if key < DENSE_LIMIT {
+            self.dense.get_mut(key as usize)?.take()
+        } else {
+            self.sparse.remove(&key)
+        };
+        if previous.is_some() {
+            self.len -= 1;
+        }
+        previous
+    }
+
+    /// Remove every value, keeping the dense allocation for reuse.
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.dense.fill_with(|| None);
+        self.sparse.clear();
+    }
+
+    /// Iterate over the entries in ascending key order.
+    pub fn iter(&self) -> Iter<'_, V> {
+        Iter {
+            dense: self.dense.iter().enumerate(),
+            sparse: self.sparse.iter(),
+        }
+    }
+}
+
+impl<V> Default for IntMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, V> Iterator for Iter<'a, V> {
+    type Item = (u64, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        for (key, value) in self.dense.by_ref() {
+            if let Some(value) = value {
+                return Some((key as u64, value));
+            }
+        }
+        self.sparse.next().map(|(key, value)| (*key, value))
+    }
+}
+
+impl<'a, V> IntoIterator for &'a IntMap<V> {
+    type IntoIter = Iter<'a, V>;
+    type Item = (u64, &'a V);
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<V> FromIterator<(u64, V)> for IntMap<V> {
+    fn from_iter<T: IntoIterator<Item = (u64, V)>>(iter: T) -> Self {
+        let mut map = Self::new();
+        for (key, value) in iter {
+            map.insert(key, value);
+        }
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dense_keys() {
+        let mut map = IntMap::new();
+        for key in 0..DENSE_LIMIT {
+            assert_eq!(map.insert(key, key * 2), None);
+        }
+        assert_eq!(map.len(), DENSE_LIMIT as usize);
+        assert!(map.sparse.is_empty());
+        assert_eq!(map.insert(7, 0), Some(14));
+        assert_eq!(map.get(7), Some(&0));
+        assert_eq!(map.remove(8), Some(16));
+        assert_eq!(map.get(8), None);
+        assert_eq!(map.len(), DENSE_LIMIT as usize - 1);
+        *map.get_mut(9).unwrap() += 1;
+        assert_eq!(map.get(9), Some(&19));
+    }
+
+    #[test]
+    fn sparse_keys() {
+        let keys = [DENSE_LIMIT, 1 << 20, u64::MAX, 3];
+        let mut map = keys.iter().map(|key| (*key, *key)).collect::<IntMap<_>>();
+        assert_eq!(map.len(), keys.len());
+        assert_eq!(map.dense.len(), 4);
+        for key in keys {
+            assert_eq!(map.get(key), Some(&key));
+        }
+
+        // Looking up or removing missing keys doesn't grow the dense part.
+        assert_eq!(map.get(DENSE_LIMIT - 1), None);
+        assert_eq!(map.remove(500), None);
+        assert_eq!(map.get(DENSE_LIMIT + 1), None);
+        assert_eq!(map.dense.len(), 4);
+
+        assert_eq!(map.remove(u64::MAX), Some(u64::MAX));
+        assert!(!map.contains_key(u64::MAX));
+        map.clear();
+        assert!(map.is_empty() && map.iter().next().is_none());
+    }
+
+    #[test]
+    fn iteration_is_ordered_by_key() {
+        let keys = [5000, 3, DENSE_LIMIT - 1, 0, DENSE_LIMIT, 64, 1 << 40];
+        let forward = keys.iter().map(|key| (*key, ())).collect::<IntMap<_>>();
+        let backward = keys
+            .iter()
+            .rev()
+            .map(|key| (*key, ()))
+            .collect::<IntMap<_>>();
+        let mut sorted = keys.to_vec();
+        sorted.sort_unstable();
+        let order = |map: &IntMap<()>| map.iter().map(|(key, _)| key).collect::<Vec<_>>();
+        assert_eq!(order(&forward), sorted);
+        assert_eq!(order(&backward), sorted);
+    }
+}
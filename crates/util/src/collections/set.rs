@@ -0,0 +1,269 @@
+//! Sets of small integers.
+//!
+//! [SmallIntSet] is tuned for the short traversals over node indices done when editing a graph.
+//! Small sets live inline in a sorted array and never touch the heap. Once a set outgrows the
+//! array it spills into a bit set, which suits the dense index ranges used for node ids.
+use std::slice;
+
+const INLINE_CAPACITY: usize = 16;
+
+#[derive(Clone, Debug)]
+pub struct SmallIntSet {
+    repr: Repr,
+}
+
+#[derive(Clone, Debug)]
+enum Repr {
+    Inline {
+        len: usize,
+        items: [usize; INLINE_CAPACITY],
+    },
+    Bits {
+        len: usize,
+        words: Vec<u64>,
+    },
+}
+
+pub enum Iter<'a> {
+    Inline(slice::Iter<'a, usize>),
+    Bits {
+        words: &'a [u64],
+        index: usize,
+        word: u64,
+    },
+}
+
+impl SmallIntSet {
+    pub fn new() -> Self {
+        Self {
+            repr: Repr::Inline {
+                len: 0,
+                items: [0; INLINE_CAPACITY],
+            },
+        }
+    }
+
+    /// Whether the set has spilled to the heap.
+    pub fn spilled(&self) -> bool {
+        matches!(self.repr, Repr::Bits { .. })
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.repr {
+            Repr::Inline { len, .. } | Repr::Bits { len, .. } => *len,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn contains(&self, value: usize) -> bool {
+        match &self.repr {
+            Repr::Inline { len, items } => items[..*len].binary_search(&value).is_ok(),
+            Repr::Bits { words, .. } => words
+                .get(value / 64)
+                .is_some_and(|word| word & (1 << (value % 64)) != 0),
+        }
+    }
+
+    /// Add a value to the set. Returns whether it was newly inserted.
+    pub fn insert(&mut self, value: usize) -> bool {
+        match &mut self.repr {
+            Repr::Inline { len, items } => {
+                let Err(index) = items[..*len].binary_search(&value) else {
+                    return false;
+                };
+                if *len == INLINE_CAPACITY {
+                    self.spill();
+                    return self.insert(value);
+                }
+                items.copy_within(index..*len, index + 1);
+                items[index] = value;
+                *len += 1;
+                true
+            }
+            Repr::Bits { len, words } => {
+                let (word, bit) = (value / 64, 1 << (value % 64));
+                if word >= words.len() {
+                    words.resize(word + 1, 0);
+                }
+                if words[word] & bit != 0 {
+                    return false;
+                }
+                words[word] |= bit;
+                *len += 1;
+                true
+            }
+        }
+    }
+
+    /// Remove a value from the set. Returns whether it was present.
+    pub fn remove(&mut self, value: usize) -> bool {
+        match &mut self.repr {
+            Repr::Inline { len, items } => {
+                let Ok(index) = items[..*len].binary_search(&value) else {
+                    return false;
+                };
+                items.copy_within(index + 1..*len, index);
+                *len -= 1;
+                true
+            }
+            Repr::Bits { len, words } => {
+                let (word, bit) = (value / 64, 1 << (value % 64));
+                let Some(word) = words.get_mut(word) else {
+                    return false;
+                };
+                if *word & bit == 0 {
+                    return false;
+                }
+                *word &= !bit;
+                *len -= 1;
+                true
+            }
+        }
+    }
+
+    /// Remove every value, keeping any heap allocation for reuse.
+    pub fn clear(&mut self) {
+        match &mut self.repr {
+            Repr::Inline { len, .. } => *len = 0,
+            Repr::Bits { len, words } => {
+                *len = 0;
+                words.fill(0);
+            }
+        }
+    }
+
+    /// Iterate over the values in ascending order.
+    pub fn iter(&self) -> Iter<'_> {
+        match &self.repr {
+            Repr::Inline { len, items } => Iter::Inline(items[..*len].iter()),
+            Repr::Bits { words, .. } => Iter::Bits {
+                words,
+                index: 0,
+                word: words.first().copied().unwrap_or(0),
+            },
+        }
+    }
+
+    fn spill(&mut self) {
+        let Repr::Inline { len, items } = &self.repr else {
+            return;
+        };
+        let max = items[..*len].last().copied().unwrap_or(0);
+        let mut words = vec![0u64; max / 64 + 1];
+        for value in &items[..*len] {
+            words[value / 64] |= 1 << (value % 64);
+        }
+        self.repr = Repr::Bits { len: *len, words };
+    }
+}
+
+impl Default for SmallIntSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = usize;
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::Inline(iter) => iter.next().copied(),
+            Self::Bits { words, index, word } => loop {
+                if *word != 0 {
+                    let bit = word.trailing_zeros() as usize;
+                    *word &= *word - 1;
+                    return Some(*index * 64 + bit);
+                }
+                *index += 1;
+                *word = *words.get(*index)?;
+            },
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a SmallIntSet {
+    type IntoIter = Iter<'a>;
+    type Item = usize;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl FromIterator<usize> for SmallIntSet {
+    fn from_iter<T: IntoIterator<Item = usize>>(iter: T) -> Self {
+        let mut set = Self::new();
+        for value in iter {
+            set.insert(value);
+        }
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    // A fixed linear congruential sequence, so failures reproduce.
+    fn values(count: usize, range: usize) -> impl Iterator<Item = usize> {
+        let mut state = 0x2545_f491_u64;
+        (0..count).map(move |_| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) as usize % range
+        })
+    }
+
+    #[test]
+    fn small_sets_stay_inline() {
+        let mut set = SmallIntSet::new();
+        for value in (0..INLINE_CAPACITY).rev() {
+            assert!(set.insert(value * 1000));
+        }
+        assert!(!set.spilled());
+        assert!(!set.insert(0));
+        assert!(!set.spilled());
+        assert!(set.insert(1));
+        assert!(set.spilled());
+        assert_eq!(set.len(), INLINE_CAPACITY + 1);
+    }
+
+    #[test]
+    fn matches_btree_set() {
+        for range in [8, 40, 300] {
+            let mut set = SmallIntSet::new();
+            let mut expected = BTreeSet::new();
+            for (step, value) in values(500, range).enumerate() {
+                if step % 3 == 2 {
+                    assert_eq!(set.remove(value), expected.remove(&value));
+                } else {
+                    assert_eq!(set.insert(value), expected.insert(value));
+                }
+                assert_eq!(set.len(), expected.len());
+                assert_eq!(set.contains(value), expected.contains(&value));
+            }
+            assert!(set.iter().eq(expected.iter().copied()));
+            for value in 0..range + 64 {
+                assert_eq!(set.contains(value), expected.contains(&value));
+            }
+        }
+    }
+
+    #[test]
+    fn clear_empties_both_representations() {
+        let mut inline = (0..4).collect::<SmallIntSet>();
+        inline.clear();
+        assert!(inline.is_empty() && inline.iter().next().is_none());
+
+        let mut spilled = (0..100).collect::<SmallIntSet>();
+        assert!(spilled.spilled());
+        spilled.clear();
+        assert!(spilled.is_empty() && !spilled.contains(50));
+        assert!(spilled.iter().next().is_none());
+        assert!(spilled.insert(50) && spilled.contains(50));
+    }
+}
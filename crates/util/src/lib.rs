@@ -1,5 +1,7 @@
 use std::ops::{Deref, DerefMut};
 
+pub mod collections;
+
 #[repr(transparent)]
 pub struct IsSend<T: ?Sized>(T);
 
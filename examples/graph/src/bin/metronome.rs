@@ -0,0 +1,112 @@
+use tesi_graph as graph;
+use tesi_graph::proc::Transport;
+
+/// Clicks on every beat of a playing transport, accenting the first beat of each bar.
+struct Metronome {
+    sample_rate: f64,
+    phase: f64,
+    freq: f64,
+    remaining: usize,
+}
+
+impl Metronome {
+    fn new() -> Self {
+        Self {
+            sample_rate: 48e3,
+            phase: 0.0,
+            freq: 0.0,
+            remaining: 0,
+        }
+    }
+}
+
+impl graph::proc::Processor for Metronome {
+    fn initialize(&mut self, sample_rate: f64, _max_num_frames: usize) {
+        self.sample_rate = sample_rate;
+    }
+
+    fn process(&mut self, context: &mut graph::proc::Context<'_>) {
+        let output = &mut context.audio_outputs[0];
+        let transport = context.transport.filter(|transport| transport.is_playing);
+        let click_len = (0.02 * self.sample_rate) as usize;
+        for frame in 0..output.num_frames() {
+            // Start a click on the frame nearest to each beat.
+            if let Some(transport) = transport {
+                let beats_per_frame = transport.tempo_bpm / 60.0 / self.sample_rate;
+                let position = transport.song_pos_beats + frame as f64 * beats_per_frame;
+                let beat = (position + beats_per_frame / 2.0).floor();
+                if beat > (position - beats_per_frame / 2.0).floor() {
+                    let downbeat = beat.rem_euclid(transport.time_sig.0 as f64) == 0.0;
+                    self.freq = if downbeat { 1500.0 } else { 1000.0 };
+                    self.phase = 0.25;
+                    self.remaining = click_len;
+                }
+            }
+
+            // A decaying cosine burst.
+            let mut sample = 0.0;
+            if self.remaining > 0 {
+                let t = (click_len - self.remaining) as f64 / self.sample_rate;
+                sample = (self.phase * std::f64::consts::TAU).sin() * (-t / 0.005).exp();
+                self.phase = (self.phase + self.freq / self.sample_rate).fract();
+                self.remaining -= 1;
+            }
+            for channel in output.iter() {
+                channel[frame] = sample as f32;
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.remaining = 0;
+    }
+}
+
+fn main() {
+    let options = graph::graph::Options {
+        num_input_channels: 0,
+        num_output_channels: 1,
+        num_workers: 0,
+    };
+    let graph = graph::Graph::new(options);
+
+    let options = graph::node::Options {
+        audio_inputs: vec![],
+        audio_outputs: vec![1],
+    };
+    let metronome = graph::node::Node::new(&graph, options, Metronome::new());
+    let _edge = graph::edge::Edge::new(&graph, &metronome, 0, &graph.output_node(), 0).unwrap();
+    graph.commit_changes().unwrap();
+
+    let mut renderer = graph.renderer().unwrap();
+    let sample_rate = 48e3;
+    let buffer_size = 512;
+    renderer.initialize(sample_rate, buffer_size);
+
+    // Play two bars of 3/4 at 120 bpm, advancing the transport like a host would.
+    let mut transport = Transport {
+        tempo_bpm: 120.0,
+        time_sig: (3, 4),
+        song_pos_beats: 0.0,
+        is_playing: true,
+        loop_beats: None,
+    };
+    let mut output = vec![0.0; buffer_size];
+    let mut frame = 0;
+    let mut quiet_until = 0;
+    while transport.song_pos_beats < 6.0 {
+        renderer.set_transport(transport);
+        let mut ptrs = vec![output.as_mut_ptr()];
+        renderer.render(std::ptr::null(), ptrs.as_mut_ptr(), 0, 1, buffer_size);
+
+        // Report where each click starts, skipping the rest of its burst.
+        for sample in &output {
+            if frame >= quiet_until && *sample != 0.0 {
+                println!("click at frame {frame}");
+                quiet_until = frame + sample_rate as usize / 10;
+            }
+            frame += 1;
+        }
+        transport.song_pos_beats += buffer_size as f64 * transport.tempo_bpm / 60.0 / sample_rate;
+    }
+}
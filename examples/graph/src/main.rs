@@ -1,4 +1,5 @@
 use tesi_graph as graph;
+use tesi_graph::proc::{builtin::gain, ParamChange};
 
 #[derive(Copy, Clone, Debug, Default)]
 pub struct Sine {
@@ -49,9 +50,7 @@ impl graph::proc::Processor for Sum {
         output.clear();
         for input in context.audio_inputs {
             for channel in 0..input.num_channels() {
-                for (i, o) in input[channel].iter().zip(output[channel].iter_mut()) {
-                    *o += *i;
-                }
+                graph::bus::kernels::add_assign(&mut output[channel], &input[channel]);
             }
         }
     }
@@ -83,13 +82,21 @@ fn main() {
     };
     let sum = graph::node::Node::new(&graph, options, Sum);
 
+    // Create a gain to automate.
+    let options = graph::node::Options {
+        audio_inputs: vec![2],
+        audio_outputs: vec![2],
+    };
+    let gain = graph::node::Node::new(&graph, options, gain::Processor::new(1.0));
+
     // Connect the graph.
     let _e1 = graph::edge::Edge::new(&graph, &sine440, 0, &sum, 0).unwrap();
     let _e2 = graph::edge::Edge::new(&graph, &sine880, 0, &sum, 1).unwrap();
-    let _e3 = graph::edge::Edge::new(&graph, &sum, 0, &graph.output_node(), 0);
+    let _e3 = graph::edge::Edge::new(&graph, &sum, 0, &gain, 0).unwrap();
+    let _e4 = graph::edge::Edge::new(&graph, &gain, 0, &graph.output_node(), 0);
 
     // Compile the changes.
-    graph.commit_changes();
+    graph.commit_changes().unwrap();
 
     // Get the renderer.
     let mut renderer = graph.renderer().unwrap();
@@ -101,8 +108,17 @@ fn main() {
     let mut output_ptrs =
         unsafe { vec![output.as_mut_ptr(), output.as_mut_ptr().add(buffer_size)] };
 
-    // Render.
+    // Fade out over the block, reaching silence on its last frame.
+    let fade_out = ParamChange {
+        id: gain::GAIN,
+        time: buffer_size - 1,
+        value: 0.0,
+    };
+    gain.send_param_change(fade_out).unwrap();
+
+    // Render, timing each node.
     renderer.initialize(48e3, buffer_size);
+    renderer.set_profiling(true);
     renderer.render(input.as_ptr(), output_ptrs.as_mut_ptr(), 0, 2, buffer_size);
 
     let (left, right) = output.split_at(buffer_size);
@@ -110,4 +126,15 @@ fn main() {
 
     println!("l = {left:?};");
     println!("r = {right:?};");
+
+    // Find the node that took the longest to process.
+    let nodes = [
+        ("sine440", &sine440),
+        ("sine880", &sine880),
+        ("sum", &sum),
+        ("gain", &gain),
+    ];
+    if let Some((name, node)) = nodes.iter().max_by_key(|(_, node)| node.profile().mean) {
+        println!("hottest node: {name} ({:?})", node.profile().mean);
+    }
 }